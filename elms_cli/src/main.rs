@@ -0,0 +1,363 @@
+use std::collections::HashMap;
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use marker_core::catalogue::{load_zones, BUNDLED_CATALOGUE};
+use marker_core::convert::convert_marker;
+use marker_core::diagnostic::{Diagnostic, Severity};
+use marker_core::export::bounds;
+use marker_core::marker::{
+    build_combined_string, get_marker_map_id, get_marker_position, parse_combined_string, BreadcrumbLine, Marker, MarkerTypes,
+};
+use marker_core::render::{render, Image};
+use marker_core::zone::Zone;
+
+/// World units shown around the markers and lines when cropping a render.
+const CROP_MARGIN: f64 = 1000.0;
+
+#[derive(Parser)]
+#[command(name = "elms-cli", about = "Lint, convert and filter Elms, m0r and Breadcrumbs strings")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+    /// Zone catalogue override file, applied over the bundled `static/zones.json`. Repeatable
+    #[arg(long = "zones", value_name = "FILE", global = true)]
+    zone_files: Vec<PathBuf>,
+}
+
+#[derive(Args)]
+struct Input {
+    /// File to read. Reads stdin if omitted or `-`
+    file: Option<PathBuf>,
+    /// Unix time written into m0r strings. Defaults to now
+    #[arg(long)]
+    timestamp: Option<u64>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Re-emit the input in the editor's normalised form, optionally converting every marker
+    Convert {
+        #[command(flatten)]
+        input: Input,
+        #[arg(long, value_enum)]
+        to: Option<Format>,
+    },
+    /// Report problems with the input. Exits non-zero if there are any errors
+    Validate {
+        #[command(flatten)]
+        input: Input,
+        /// Treat warnings as errors
+        #[arg(long)]
+        strict: bool,
+    },
+    /// Count markers and lines per zone and map
+    Stats {
+        #[command(flatten)]
+        input: Input,
+    },
+    /// Drop repeated markers and lines
+    Dedupe {
+        #[command(flatten)]
+        input: Input,
+    },
+    /// Keep only markers and lines in the given zone and/or map
+    Filter {
+        #[command(flatten)]
+        input: Input,
+        #[arg(long)]
+        zone: Option<u16>,
+        #[arg(long)]
+        map: Option<u16>,
+    },
+    /// Draw one map with its markers and lines to a PNG, from the editor's map tiles and icons
+    ///
+    /// No text is drawn: m0r marker text, the title and the legend are left
+    /// out. Export from the editor for an image with them.
+    Render {
+        #[command(flatten)]
+        input: Input,
+        /// Zone to draw. Defaults to the only zone in the input
+        #[arg(long)]
+        zone: Option<u16>,
+        /// Map to draw. Defaults to the map with the most markers and lines
+        #[arg(long)]
+        map: Option<u16>,
+        /// Pixels across the longer side of the image
+        #[arg(long, default_value_t = 1024)]
+        size: u32,
+        /// Show only the area around the markers and lines
+        #[arg(long)]
+        crop: bool,
+        /// The editor's `static` folder, holding `maps` and `icons`
+        #[arg(long = "static", value_name = "DIR", default_value = "static")]
+        static_dir: PathBuf,
+        /// PNG file to write
+        #[arg(short, long, value_name = "FILE")]
+        output: PathBuf,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Elms,
+    M0r,
+}
+
+impl From<Format> for MarkerTypes {
+    fn from(f: Format) -> Self {
+        match f {
+            Format::Elms => MarkerTypes::Elms,
+            Format::M0r => MarkerTypes::M0r,
+        }
+    }
+}
+
+struct Loaded {
+    name: String,
+    text: String,
+    markers: HashMap<u16, Vec<Marker>>,
+    lines: HashMap<u16, Vec<BreadcrumbLine>>,
+    diagnostics: Vec<Diagnostic>,
+    timestamp: u64,
+}
+
+fn load(input: &Input, zones: &[Zone]) -> io::Result<Loaded> {
+    let (name, text) = match &input.file {
+        Some(path) if path.as_os_str() != "-" => (path.display().to_string(), std::fs::read_to_string(path)?),
+        _ => {
+            let mut text = String::new();
+            io::stdin().read_to_string(&mut text)?;
+            ("<stdin>".to_string(), text)
+        }
+    };
+    let parsed = parse_combined_string(&text, zones.to_vec());
+    let (markers, lines) = parsed.data;
+    let timestamp = input.timestamp.unwrap_or_else(|| {
+        SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+    });
+    Ok(Loaded { name, text, markers, lines, diagnostics: parsed.diagnostics, timestamp })
+}
+
+/// 1-based line and column of a byte offset.
+fn line_col(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset.min(text.len())];
+    let line = before.matches('\n').count() + 1;
+    let col = before.rfind('\n').map_or(before.chars().count(), |i| before[i + 1..].chars().count()) + 1;
+    (line, col)
+}
+
+fn format_diagnostic(loaded: &Loaded, d: &Diagnostic) -> String {
+    let (line, col) = line_col(&loaded.text, d.span.start);
+    format!("{}:{}:{}: {}: {}", loaded.name, line, col, d.severity, d.message)
+}
+
+fn report_diagnostics(loaded: &Loaded) {
+    for d in &loaded.diagnostics {
+        eprintln!("{}", format_diagnostic(loaded, d));
+    }
+}
+
+fn emit(loaded: &Loaded) {
+    println!("{}", build_combined_string(&loaded.markers, &loaded.lines, loaded.timestamp));
+}
+
+/// Keeps the first of each run of equal items. Returns how many were dropped.
+fn dedupe_by<T: Clone>(items: &mut Vec<T>, same: impl Fn(&T, &T) -> bool) -> usize {
+    let mut unique: Vec<T> = Vec::new();
+    for item in items.iter() {
+        if !unique.iter().any(|u| same(u, item)) {
+            unique.push(item.clone());
+        }
+    }
+    let removed = items.len() - unique.len();
+    *items = unique;
+    removed
+}
+
+fn stats(loaded: &Loaded, zones: &[Zone]) {
+    let mut zone_ids: Vec<u16> = loaded.markers.keys().chain(loaded.lines.keys()).copied().collect();
+    zone_ids.sort();
+    zone_ids.dedup();
+
+    for zone_id in zone_ids {
+        let markers = loaded.markers.get(&zone_id).map(Vec::as_slice).unwrap_or_default();
+        let lines = loaded.lines.get(&zone_id).map(Vec::as_slice).unwrap_or_default();
+        let zone = zones.iter().find(|z| z.id == zone_id);
+        let elms = markers.iter().filter(|m| matches!(m, Marker::Elms(_))).count();
+
+        println!("{} ({})", zone.map_or("Unknown zone", |z| z.name.as_str()), zone_id);
+        println!("  {} elms markers, {} m0r markers, {} lines", elms, markers.len() - elms, lines.len());
+
+        let mut map_ids: Vec<u16> = markers.iter().map(get_marker_map_id).chain(lines.iter().map(|l| l.map_id)).collect();
+        map_ids.sort();
+        map_ids.dedup();
+        for map_id in map_ids {
+            let map_name = zone
+                .and_then(|z| z.maps.iter().find(|m| m.map_id == map_id))
+                .map_or("Outside every map", |m| m.name.as_str());
+            let marker_count = markers.iter().filter(|m| get_marker_map_id(m) == map_id).count();
+            let line_count = lines.iter().filter(|l| l.map_id == map_id).count();
+            println!("  {} ({}): {} markers, {} lines", map_name, map_id, marker_count, line_count);
+        }
+    }
+}
+
+fn run(cli: Cli) -> io::Result<ExitCode> {
+    let mut overrides = Vec::new();
+    for path in &cli.zone_files {
+        overrides.push((path.display().to_string(), std::fs::read_to_string(path)?));
+    }
+    let overrides: Vec<(&str, &str)> = overrides.iter().map(|(n, c)| (n.as_str(), c.as_str())).collect();
+    let zones = match load_zones(BUNDLED_CATALOGUE, &overrides) {
+        Ok(zones) => zones,
+        Err(errors) => {
+            for e in errors {
+                eprintln!("elms-cli: {}", e);
+            }
+            return Ok(ExitCode::FAILURE);
+        }
+    };
+
+    match cli.command {
+        Command::Convert { input, to } => {
+            let mut loaded = load(&input, &zones)?;
+            report_diagnostics(&loaded);
+            if let Some(to) = to {
+                let mut zone_ids: Vec<u16> = loaded.markers.keys().copied().collect();
+                zone_ids.sort();
+                for zone_id in zone_ids {
+                    for m in loaded.markers.get_mut(&zone_id).into_iter().flatten() {
+                        let conversion = convert_marker(m, to.into());
+                        for loss in &conversion.losses {
+                            let p = get_marker_position(m);
+                            eprintln!("{}: zone {} marker at {}, {}: {}", loaded.name, zone_id, p.x, p.z, loss);
+                        }
+                        *m = conversion.marker;
+                    }
+                }
+            }
+            emit(&loaded);
+        }
+        Command::Validate { input, strict } => {
+            let loaded = load(&input, &zones)?;
+            for d in &loaded.diagnostics {
+                println!("{}", format_diagnostic(&loaded, d));
+            }
+            let failed = loaded.diagnostics.iter().any(|d| strict || d.severity == Severity::Error);
+            if failed {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        Command::Stats { input } => {
+            let loaded = load(&input, &zones)?;
+            report_diagnostics(&loaded);
+            stats(&loaded, &zones);
+        }
+        Command::Dedupe { input } => {
+            let mut loaded = load(&input, &zones)?;
+            report_diagnostics(&loaded);
+            let mut removed = 0;
+            for markers in loaded.markers.values_mut() {
+                removed += dedupe_by(markers, |a, b| a == b);
+            }
+            for lines in loaded.lines.values_mut() {
+                removed += dedupe_by(lines, |a, b| {
+                    a.colour == b.colour
+                        && ((a.position1 == b.position1 && a.position2 == b.position2)
+                            || (a.position1 == b.position2 && a.position2 == b.position1))
+                });
+            }
+            eprintln!("{}: removed {} duplicates", loaded.name, removed);
+            emit(&loaded);
+        }
+        Command::Filter { input, zone, map } => {
+            let mut loaded = load(&input, &zones)?;
+            report_diagnostics(&loaded);
+            let keep_zone = |zone_id: &u16| zone.is_none_or(|z| z == *zone_id);
+            loaded.markers.retain(|zone_id, _| keep_zone(zone_id));
+            loaded.lines.retain(|zone_id, _| keep_zone(zone_id));
+            if let Some(map) = map {
+                for markers in loaded.markers.values_mut() {
+                    markers.retain(|m| get_marker_map_id(m) == map);
+                }
+                for lines in loaded.lines.values_mut() {
+                    lines.retain(|l| l.map_id == map);
+                }
+            }
+            emit(&loaded);
+        }
+        Command::Render { input, zone, map, size, crop, static_dir, output } => {
+            if !static_dir.join("maps").is_dir() {
+                eprintln!("elms-cli: no map tiles in {}; point --static at the editor's static folder", static_dir.display());
+                return Ok(ExitCode::FAILURE);
+            }
+            let loaded = load(&input, &zones)?;
+            report_diagnostics(&loaded);
+            let mut zone_ids: Vec<u16> = loaded.markers.keys().chain(loaded.lines.keys()).copied().collect();
+            zone_ids.sort();
+            zone_ids.dedup();
+            let zone_id = match (zone, &zone_ids[..]) {
+                (Some(zone), _) => zone,
+                (None, [zone]) => *zone,
+                (None, []) => {
+                    eprintln!("elms-cli: nothing to draw; give --zone to draw an empty map");
+                    return Ok(ExitCode::FAILURE);
+                }
+                (None, _) => {
+                    eprintln!("elms-cli: the input covers zones {:?}; pick one with --zone", zone_ids);
+                    return Ok(ExitCode::FAILURE);
+                }
+            };
+            let Some(zone) = zones.iter().find(|z| z.id == zone_id) else {
+                eprintln!("elms-cli: zone {} unknown", zone_id);
+                return Ok(ExitCode::FAILURE);
+            };
+            let markers = loaded.markers.get(&zone_id).map(Vec::as_slice).unwrap_or_default();
+            let lines = loaded.lines.get(&zone_id).map(Vec::as_slice).unwrap_or_default();
+            let busiest = zone.maps.iter().max_by_key(|m| {
+                let count = markers.iter().filter(|marker| get_marker_map_id(marker) == m.map_id).count()
+                    + lines.iter().filter(|l| l.map_id == m.map_id).count();
+                // The first map wins a tie.
+                (count, std::cmp::Reverse(m.map_id))
+            });
+            let Some(map_data) = (match map {
+                Some(map) => zone.maps.iter().find(|m| m.map_id == map),
+                None => busiest,
+            }) else {
+                eprintln!("elms-cli: zone {} has no map {}", zone_id, map.map_or("to draw".to_string(), |m| m.to_string()));
+                return Ok(ExitCode::FAILURE);
+            };
+
+            let crop = if crop { bounds(markers, lines, map_data.map_id, CROP_MARGIN) } else { None };
+            let read = |path: &str| {
+                let file = static_dir.join(path);
+                match std::fs::read(&file).map_err(|e| e.to_string()).and_then(|bytes| Image::from_png(&bytes).map_err(|e| e.to_string())) {
+                    Ok(image) => Some(image),
+                    Err(e) => {
+                        eprintln!("elms-cli: {}: {}", file.display(), e);
+                        None
+                    }
+                }
+            };
+            let image = render(map_data, markers, lines, size, crop, &read);
+            std::fs::write(&output, image.to_png())?;
+            eprintln!("{}: drew {} ({}) at {}x{}", output.display(), map_data.name, map_data.map_id, image.width, image.height);
+        }
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("elms-cli: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn run(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_elms-cli"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("elms-cli starts");
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn convert_to_m0r_replaces_elms_markers() {
+    let output = run(&["convert", "--to", "m0r", "--timestamp", "1700000000"], "/1263//92500,35000,95000,1/");
+    assert!(output.status.success());
+    let out = stdout(&output);
+    assert!(out.starts_with("<1263]1700000000]"), "{out}");
+    assert!(!out.contains("/1263//"), "{out}");
+}
+
+#[test]
+fn validate_reports_line_and_column() {
+    let output = run(&["validate"], "/1263//92500,35000,95000,1/\n/9999//0,0,0,1/");
+    assert!(!output.status.success());
+    let out = stdout(&output);
+    assert!(out.starts_with("<stdin>:2:2: error: zone 9999 unknown"), "{out}");
+}
+
+#[test]
+fn validate_strict_fails_on_warnings() {
+    let input = "/1263//92500,35000,95000,1//1263//92500,35000,95000,1/";
+    assert!(run(&["validate"], input).status.success());
+    assert!(!run(&["validate", "--strict"], input).status.success());
+}
+
+#[test]
+fn dedupe_drops_reversed_lines() {
+    let output = run(&["dedupe"], "4EF;16954;88B8;17318;1;FF0000;2;0;0;0;64;0;0;2;1;1;2;1;2;1;");
+    assert!(output.status.success());
+    assert_eq!(stdout(&output).trim(), "4EF;16954;88B8;17318;1;FF0000;2;0;0;0;64;0;0;1;1;1;2;");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("removed 1 duplicates"));
+}
+
+#[test]
+fn filter_keeps_only_the_requested_zone() {
+    let output = run(&["filter", "--zone", "1263"], "/1263//92500,35000,95000,1//1344//0,0,0,1/");
+    assert!(output.status.success());
+    assert_eq!(stdout(&output).trim(), "/1263//92500,35000,95000,1/");
+}
+
+#[test]
+fn convert_reports_what_was_lost() {
+    let m0r = "<1263]1700000000]16954:88b8:17318]2:1]]0:1]ff0000:1]^3:1]0:0:0:Hello>";
+    let output = run(&["convert", "--to", "elms"], m0r);
+    assert!(output.status.success());
+    let err = String::from_utf8_lossy(&output.stderr);
+    assert!(err.contains("text \"Hello\" dropped"), "{err}");
+    assert!(err.contains("size 2 dropped"), "{err}");
+}
+
+#[test]
+fn zone_overrides_add_new_zones() {
+    let path = std::env::temp_dir().join(format!("elms-cli-zones-{}.json", std::process::id()));
+    std::fs::write(&path, r#"{"version": 1, "zones": [{"id": 1228, "name": "Black Drake Villa", "category": "dungeon", "maps": [
+        {"id": 1921, "name": "Black Drake Villa", "tiles": "westernskyrim/blackdrakevilla_base_", "tile_count": 3, "min_x": 0.0, "max_x": 1000.0, "min_z": 0.0, "max_z": 1000.0}
+    ]}]}"#).unwrap();
+
+    let input = "/1228//500,0,500,1/";
+    assert!(!run(&["validate"], input).status.success());
+    let output = run(&["stats", "--zones", path.to_str().unwrap()], input);
+    std::fs::remove_file(&path).unwrap();
+    assert!(output.status.success());
+    assert!(stdout(&output).starts_with("Black Drake Villa (1228)"), "{}", stdout(&output));
+}
+
+#[test]
+fn render_writes_a_png_of_the_busiest_map() {
+    let path = std::env::temp_dir().join(format!("elms-cli-render-{}.png", std::process::id()));
+    let output = run(&["render", "--static", "../static", "--size", "64", "-o", path.to_str().unwrap()], "/1263//92500,35000,95000,1/");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let image = marker_core::render::Image::from_png(&std::fs::read(&path).unwrap()).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!((image.width, image.height), (64, 64));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("(2004)"));
+
+    assert!(!run(&["render", "--static", "../static", "-o", "unused.png"], "/1263//0,0,0,1//636//0,0,0,1/").status.success(), "two zones need --zone");
+}
+
+#[test]
+fn render_help_says_no_text_is_drawn() {
+    let output = run(&["render", "--help"], "");
+    assert!(output.status.success());
+    assert!(stdout(&output).contains("No text is drawn"), "{}", stdout(&output));
+}
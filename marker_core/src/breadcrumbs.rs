@@ -0,0 +1,72 @@
+//! Drawing and editing Breadcrumbs lines.
+
+use crate::marker::{get_marker_active, get_marker_map_id, get_marker_position, BreadcrumbLine, Marker, Position3D};
+
+/// One end of a line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endpoint {
+    Start,
+    End,
+}
+
+impl BreadcrumbLine {
+    pub fn endpoint(&self, end: Endpoint) -> Position3D {
+        match end {
+            Endpoint::Start => self.position1,
+            Endpoint::End => self.position2,
+        }
+    }
+}
+
+fn distance(p: Position3D, x: f64, z: f64) -> f64 {
+    (p.x as f64 - x).hypot(p.z as f64 - z)
+}
+
+/// Segments joining `points` in order, numbered on from `first_id`. Stops
+/// early rather than run out of ids.
+pub fn polyline(points: &[Position3D], colour: (u8, u8, u8, u8), map_id: u16, first_id: u16) -> Vec<BreadcrumbLine> {
+    points.windows(2)
+        .zip(first_id..=u16::MAX)
+        .map(|(pair, id)| BreadcrumbLine { position1: pair[0], position2: pair[1], active: true, colour, id, map_id })
+        .collect()
+}
+
+/// The nearest line end or marker on `map_id` within `radius` of world `x`,
+/// `z`, so new ends join up with what is already there. Line ends win ties.
+pub fn snap_point(x: f64, z: f64, markers: &[Marker], lines: &[BreadcrumbLine], map_id: u16, radius: f64) -> Option<Position3D> {
+    let ends = lines.iter()
+        .filter(|l| l.active && l.map_id == map_id)
+        .flat_map(|l| [l.position1, l.position2]);
+    let marker_points = markers.iter()
+        .filter(|m| get_marker_active(m) && get_marker_map_id(m) == map_id)
+        .map(get_marker_position);
+    ends.chain(marker_points)
+        .map(|p| (distance(p, x, z), p))
+        .filter(|(d, _)| *d <= radius)
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, p)| p)
+}
+
+/// The line end on `map_id` nearest to world `x`, `z`, if any is within `radius`.
+pub fn endpoint_at(lines: &[BreadcrumbLine], map_id: u16, x: f64, z: f64, radius: f64) -> Option<(u16, Endpoint)> {
+    lines.iter()
+        .filter(|l| l.active && l.map_id == map_id)
+        .flat_map(|l| [(l, Endpoint::Start), (l, Endpoint::End)])
+        .map(|(l, end)| (distance(l.endpoint(end), x, z), l.id, end))
+        .filter(|(d, _, _)| *d <= radius)
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, id, end)| (id, end))
+}
+
+/// Moves every end on the same map sitting where `from` is to `to`, so the
+/// lines meeting at a point stay joined.
+pub fn move_endpoint(lines: &mut [BreadcrumbLine], map_id: u16, from: Position3D, to: Position3D) {
+    for line in lines.iter_mut().filter(|l| l.map_id == map_id) {
+        if line.position1 == from {
+            line.position1 = to;
+        }
+        if line.position2 == from {
+            line.position2 = to;
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::zone::{Map, MapScaleData, Tile, Zone};
+
+/// Newest catalogue format this build understands.
+pub const CATALOGUE_VERSION: u32 = 1;
+
+/// `static/zones.json` as it was when this build was made. The web editor
+/// fetches the live file at startup; native tools use this directly.
+pub const BUNDLED_CATALOGUE: &str = include_str!("../../static/zones.json");
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ZoneCategory {
+    Trial,
+    Dungeon,
+    Arena,
+    #[default]
+    Other,
+}
+
+impl fmt::Display for ZoneCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ZoneCategory::Trial => "Trials",
+            ZoneCategory::Dungeon => "Dungeons",
+            ZoneCategory::Arena => "Arenas",
+            ZoneCategory::Other => "Other",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Catalogue {
+    pub version: u32,
+    pub zones: Vec<ZoneEntry>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ZoneEntry {
+    pub id: u16,
+    pub name: String,
+    #[serde(default)]
+    pub category: ZoneCategory,
+    pub maps: Vec<MapEntry>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MapEntry {
+    pub id: u16,
+    pub name: String,
+    /// Tile path prefix under `static/maps`, completed with `{index}.png`
+    pub tiles: String,
+    /// Tiles per side
+    pub tile_count: u8,
+    #[serde(default)]
+    pub scale_factor: f32,
+    pub min_x: f32,
+    pub max_x: f32,
+    pub min_z: f32,
+    pub max_z: f32,
+    /// Floor height, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub y: Option<f32>,
+    /// Height range that belongs to this map, where maps are stacked over one
+    /// another. Either end can be left open.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_y: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_y: Option<f32>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CatalogueError {
+    Json(String),
+    UnsupportedVersion(u32),
+    DuplicateZone(u16),
+    DuplicateMap(u16),
+    NoMaps(u16),
+    EmptyName { zone_id: u16, map_id: Option<u16> },
+    BadTiles { map_id: u16 },
+    BadBounds { map_id: u16 },
+    BadYRange { map_id: u16 },
+}
+
+impl fmt::Display for CatalogueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CatalogueError::Json(e) => write!(f, "invalid catalogue: {}", e),
+            CatalogueError::UnsupportedVersion(v) => write!(f, "catalogue version {} is newer than this editor understands", v),
+            CatalogueError::DuplicateZone(id) => write!(f, "zone {} is listed more than once", id),
+            CatalogueError::DuplicateMap(id) => write!(f, "map {} is listed more than once", id),
+            CatalogueError::NoMaps(id) => write!(f, "zone {} has no maps", id),
+            CatalogueError::EmptyName { zone_id, map_id: None } => write!(f, "zone {} has no name", zone_id),
+            CatalogueError::EmptyName { zone_id, map_id: Some(map_id) } => write!(f, "map {} in zone {} has no name", map_id, zone_id),
+            CatalogueError::BadTiles { map_id } => write!(f, "map {} needs a tile path and between 1 and 16 tiles per side", map_id),
+            CatalogueError::BadBounds { map_id } => write!(f, "map {} has a minimum that isn't below its maximum", map_id),
+            CatalogueError::BadYRange { map_id } => write!(f, "map {} has a height range that is empty or doesn't hold its floor", map_id),
+        }
+    }
+}
+
+impl Catalogue {
+    /// Reads a catalogue or override file, reporting every problem found.
+    pub fn from_json(s: &str) -> Result<Catalogue, Vec<CatalogueError>> {
+        let catalogue: Catalogue = serde_json::from_str(s).map_err(|e| vec![CatalogueError::Json(e.to_string())])?;
+        if catalogue.version > CATALOGUE_VERSION {
+            return Err(vec![CatalogueError::UnsupportedVersion(catalogue.version)]);
+        }
+        let errors = catalogue.validate();
+        if errors.is_empty() { Ok(catalogue) } else { Err(errors) }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("catalogue is always serialisable")
+    }
+
+    pub fn validate(&self) -> Vec<CatalogueError> {
+        let mut errors = Vec::new();
+        let mut zone_ids = HashSet::new();
+        let mut map_ids = HashSet::new();
+
+        for zone in &self.zones {
+            if !zone_ids.insert(zone.id) {
+                errors.push(CatalogueError::DuplicateZone(zone.id));
+            }
+            if zone.name.trim().is_empty() {
+                errors.push(CatalogueError::EmptyName { zone_id: zone.id, map_id: None });
+            }
+            if zone.maps.is_empty() {
+                errors.push(CatalogueError::NoMaps(zone.id));
+            }
+            for map in &zone.maps {
+                if !map_ids.insert(map.id) {
+                    errors.push(CatalogueError::DuplicateMap(map.id));
+                }
+                if map.name.trim().is_empty() {
+                    errors.push(CatalogueError::EmptyName { zone_id: zone.id, map_id: Some(map.id) });
+                }
+                if map.tiles.trim().is_empty() || !(1..=16).contains(&map.tile_count) {
+                    errors.push(CatalogueError::BadTiles { map_id: map.id });
+                }
+                if map.min_x >= map.max_x || map.min_z >= map.max_z {
+                    errors.push(CatalogueError::BadBounds { map_id: map.id });
+                }
+                let empty_range = matches!((map.min_y, map.max_y), (Some(min), Some(max)) if min >= max);
+                let floor_outside = map.y.is_some_and(|y| map.min_y.is_some_and(|min| y < min) || map.max_y.is_some_and(|max| y > max));
+                if empty_range || floor_outside {
+                    errors.push(CatalogueError::BadYRange { map_id: map.id });
+                }
+            }
+        }
+        errors
+    }
+
+    /// Applies an override file. Zones in `overrides` replace zones with the
+    /// same id whole; new ids are added.
+    pub fn merge(&mut self, overrides: Catalogue) {
+        for zone in overrides.zones {
+            match self.zones.iter_mut().find(|z| z.id == zone.id) {
+                Some(existing) => *existing = zone,
+                None => self.zones.push(zone),
+            }
+        }
+    }
+
+    /// Zones sorted by id, each with its maps sorted by id.
+    pub fn to_zones(&self) -> Vec<Zone> {
+        let mut zones: Vec<Zone> = self.zones.iter().map(|z| {
+            let mut maps: Vec<Map> = z.maps.iter().map(|m| Map {
+                name: m.name.clone(),
+                tiles: (0..(m.tile_count as usize).pow(2)).map(|i| Tile { path: format!("{}{}.png", m.tiles, i) }).collect(),
+                map_id: m.id,
+                zone_id: z.id,
+                count: m.tile_count,
+                scale_data: MapScaleData {
+                    scale_factor: m.scale_factor,
+                    min_x: m.min_x,
+                    max_x: m.max_x,
+                    min_z: m.min_z,
+                    max_z: m.max_z,
+                    y: m.y,
+                    min_y: m.min_y,
+                    max_y: m.max_y,
+                },
+            }).collect();
+            maps.sort_by_key(|m| m.map_id);
+            Zone { id: z.id, maps, name: z.name.clone(), category: z.category }
+        }).collect();
+        zones.sort_by_key(|z| z.id);
+        zones
+    }
+}
+
+/// Builds the zone list from a catalogue and any number of override files,
+/// applied in order. Errors are prefixed with the file they came from.
+pub fn load_zones(base: &str, overrides: &[(&str, &str)]) -> Result<Vec<Zone>, Vec<String>> {
+    let mut catalogue = Catalogue::from_json(base)
+        .map_err(|errors| errors.iter().map(|e| format!("zones.json: {}", e)).collect::<Vec<_>>())?;
+    if catalogue.zones.is_empty() {
+        return Err(vec!["zones.json: the catalogue has no zones".to_string()]);
+    }
+
+    let mut errors = Vec::new();
+    for (name, contents) in overrides {
+        match Catalogue::from_json(contents) {
+            Ok(o) => catalogue.merge(o),
+            Err(e) => errors.extend(e.iter().map(|e| format!("{}: {}", name, e))),
+        }
+    }
+    // Overrides can each be fine but clash with one another.
+    errors.extend(catalogue.validate().iter().map(|e| format!("after overrides: {}", e)));
+
+    if errors.is_empty() { Ok(catalogue.to_zones()) } else { Err(errors) }
+}
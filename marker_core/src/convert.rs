@@ -0,0 +1,253 @@
+use crate::marker::{m0r_texture_to_og, ElmMarker, ElmsIcon, M0rIcon, M0rMarker, M0rTexture, Marker, MarkerTypes};
+
+/// Colours the Elms square icons are drawn in, as (Red, Green, Blue).
+const BLUE: (u8, u8, u8) = (0, 100, 255);
+const GREEN: (u8, u8, u8) = (0, 200, 0);
+const ORANGE: (u8, u8, u8) = (255, 128, 0);
+const PINK: (u8, u8, u8) = (255, 0, 255);
+const RED: (u8, u8, u8) = (220, 0, 0);
+const YELLOW: (u8, u8, u8) = (255, 230, 0);
+const WHITE: (u8, u8, u8) = (255, 255, 255);
+/// `MarkerLightBlue` is very definitely green
+const MARKER_GREEN: (u8, u8, u8) = (80, 255, 170);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SquareColour {
+    Blue,
+    Green,
+    Orange,
+    Pink,
+    Red,
+    Yellow,
+}
+
+impl SquareColour {
+    const ALL: [SquareColour; 6] = [
+        SquareColour::Blue,
+        SquareColour::Green,
+        SquareColour::Orange,
+        SquareColour::Pink,
+        SquareColour::Red,
+        SquareColour::Yellow,
+    ];
+
+    fn rgb(self) -> (u8, u8, u8) {
+        match self {
+            SquareColour::Blue => BLUE,
+            SquareColour::Green => GREEN,
+            SquareColour::Orange => ORANGE,
+            SquareColour::Pink => PINK,
+            SquareColour::Red => RED,
+            SquareColour::Yellow => YELLOW,
+        }
+    }
+
+    fn nearest(rgb: (u8, u8, u8)) -> SquareColour {
+        let distance = |c: SquareColour| {
+            let (r, g, b) = c.rgb();
+            let dr = r as i32 - rgb.0 as i32;
+            let dg = g as i32 - rgb.1 as i32;
+            let db = b as i32 - rgb.2 as i32;
+            dr * dr + dg * dg + db * db
+        };
+        Self::ALL.into_iter().min_by_key(|c| distance(*c)).unwrap_or(SquareColour::Red)
+    }
+
+    /// Plain square, square with a number (1–4) or no icon if that colour has none.
+    fn icon(self, number: Option<u8>) -> Option<ElmsIcon> {
+        use ElmsIcon::*;
+        let icons = match self {
+            SquareColour::Blue => [SquareBlue, SquareTwoBlueOne, SquareTwoBlueTwo, SquareTwoBlueThree, SquareTwoBlueFour],
+            SquareColour::Green => [SquareGreen, SquareTwoGreenOne, SquareTwoGreenTwo, SquareTwoGreenThree, SquareTwoGreenFour],
+            SquareColour::Orange => [SquareOrange, SquareTwoOrangeOne, SquareTwoOrangeTwo, SquareTwoOrangeThree, SquareTwoOrangeFour],
+            SquareColour::Red => [SquareRed, SquareTwoRedOne, SquareTwoRedTwo, SquareTwoRedThree, SquareTwoRedFour],
+            SquareColour::Pink => return if number.is_none() { Some(SquarePink) } else { None },
+            SquareColour::Yellow => return if number.is_none() { Some(SquareYellow) } else { None },
+        };
+        icons.get(number.unwrap_or(0) as usize).copied()
+    }
+}
+
+/// The m0r texture, colour and text that best stand in for an Elms icon.
+fn elms_icon_to_m0r(icon: ElmsIcon) -> (M0rIcon, (u8, u8, u8), Option<String>) {
+    use ElmsIcon::*;
+    let square = |colour: (u8, u8, u8), text: Option<&str>| (M0rIcon::Square, colour, text.map(String::from));
+    match icon {
+        Num(n) => (M0rIcon::Blank, WHITE, Some(n.to_string())),
+        Letter(c) => (M0rIcon::Blank, WHITE, Some(c.to_ascii_uppercase().to_string())),
+        Arrow => (M0rIcon::Chevron, WHITE, None),
+        MarkerLightBlue | Unknown => (M0rIcon::Chevron, MARKER_GREEN, None),
+        SharkPog => (M0rIcon::SharkPog, WHITE, None),
+        SquareBlue | SquareTwoBlue => square(BLUE, None),
+        SquareGreen | SquareTwoGreen => square(GREEN, None),
+        SquareOrange | SquareTwoOrange => square(ORANGE, None),
+        SquarePink | SquareTwoPink => square(PINK, None),
+        SquareRed | SquareTwoRed => square(RED, None),
+        SquareYellow | SquareTwoYellow => square(YELLOW, None),
+        SquareOrangeOT => square(ORANGE, Some("OT")),
+        SquareRedMT => square(RED, Some("MT")),
+        SquareTwoBlueOne => square(BLUE, Some("1")),
+        SquareTwoBlueTwo => square(BLUE, Some("2")),
+        SquareTwoBlueThree => square(BLUE, Some("3")),
+        SquareTwoBlueFour => square(BLUE, Some("4")),
+        SquareTwoGreenOne => square(GREEN, Some("1")),
+        SquareTwoGreenTwo => square(GREEN, Some("2")),
+        SquareTwoGreenThree => square(GREEN, Some("3")),
+        SquareTwoGreenFour => square(GREEN, Some("4")),
+        SquareTwoOrangeOne => square(ORANGE, Some("1")),
+        SquareTwoOrangeTwo => square(ORANGE, Some("2")),
+        SquareTwoOrangeThree => square(ORANGE, Some("3")),
+        SquareTwoOrangeFour => square(ORANGE, Some("4")),
+        SquareTwoRedOne => square(RED, Some("1")),
+        SquareTwoRedTwo => square(RED, Some("2")),
+        SquareTwoRedThree => square(RED, Some("3")),
+        SquareTwoRedFour => square(RED, Some("4")),
+    }
+}
+
+/// The Elms icon closest to an m0r texture, colour and text.
+fn m0r_to_elms_icon(texture: &M0rTexture, colour: (u8, u8, u8, u8), text: Option<&str>) -> ElmsIcon {
+    let (r, g, b, _) = colour;
+    let text = text.map(str::trim).filter(|t| !t.is_empty());
+    let number = text.and_then(|t| t.parse::<u8>().ok());
+    let letter = text.filter(|t| t.len() == 1).and_then(|t| t.chars().next()).filter(|c| c.is_ascii_alphabetic());
+
+    match texture {
+        M0rTexture::Known(M0rIcon::Square) => {
+            let square = SquareColour::nearest((r, g, b));
+            match (square, text) {
+                (SquareColour::Orange, Some("OT")) => ElmsIcon::SquareOrangeOT,
+                (SquareColour::Red, Some("MT")) => ElmsIcon::SquareRedMT,
+                _ => square
+                    .icon(number.filter(|n| (1..=4).contains(n)))
+                    .or_else(|| square.icon(None))
+                    .unwrap_or(ElmsIcon::SquareRed),
+            }
+        }
+        M0rTexture::Known(M0rIcon::Chevron) if (r, g, b) == WHITE => ElmsIcon::Arrow,
+        M0rTexture::Known(M0rIcon::SharkPog) => ElmsIcon::SharkPog,
+        _ => match (number, letter) {
+            (Some(n), _) if (1..=12).contains(&n) => ElmsIcon::Num(n),
+            (_, Some(c)) => ElmsIcon::Letter(c.to_ascii_lowercase()),
+            _ => ElmsIcon::MarkerLightBlue,
+        },
+    }
+}
+
+/// Something about a marker that the target format has no way to hold.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Loss {
+    /// The icon has no exact counterpart and was swapped for the nearest one.
+    Icon { from: String, to: String },
+    Texture(M0rTexture),
+    Text(String),
+    Colour((u8, u8, u8, u8)),
+    Orientation((i8, i16)),
+    Size(f32),
+}
+
+impl std::fmt::Display for Loss {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Loss::Icon { from, to } => write!(f, "icon {} approximated as {}", from, to),
+            Loss::Texture(texture) => write!(f, "texture {} dropped", m0r_texture_to_og(texture)),
+            Loss::Text(text) => write!(f, "text {:?} dropped", text),
+            Loss::Colour((r, g, b, a)) => write!(f, "colour {:02x}{:02x}{:02x} (alpha {}) dropped", r, g, b, a),
+            Loss::Orientation((pitch, yaw)) => write!(f, "orientation (pitch {}, yaw {}) dropped", pitch, yaw),
+            Loss::Size(size) => write!(f, "size {} dropped", size),
+        }
+    }
+}
+
+/// A converted marker and everything that didn't survive the conversion.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conversion<T> {
+    pub marker: T,
+    pub losses: Vec<Loss>,
+}
+
+impl<T> Conversion<T> {
+    pub fn is_lossless(&self) -> bool {
+        self.losses.is_empty()
+    }
+}
+
+pub fn elms_to_m0r(marker: &ElmMarker) -> Conversion<M0rMarker> {
+    let (texture, (r, g, b), text) = elms_icon_to_m0r(marker.icon);
+    let colour = (r, g, b, 255);
+    let background_texture = M0rTexture::Known(texture);
+
+    let mut losses = Vec::new();
+    let back = m0r_to_elms_icon(&background_texture, colour, text.as_deref());
+    if back != marker.icon {
+        losses.push(Loss::Icon { from: format!("{:?}", marker.icon), to: m0r_texture_to_og(&background_texture) });
+    }
+
+    Conversion {
+        marker: M0rMarker {
+            position: marker.position,
+            background_texture,
+            text,
+            size: marker.size as f32,
+            colour,
+            orientation: None,
+            active: marker.active,
+            id: marker.id,
+            map_id: marker.map_id,
+        },
+        losses,
+    }
+}
+
+pub fn m0r_to_elms(marker: &M0rMarker) -> Conversion<ElmMarker> {
+    let icon = m0r_to_elms_icon(&marker.background_texture, marker.colour, marker.text.as_deref());
+
+    // Whatever the Elms icon would turn back into is what it kept.
+    let (kept_texture, (r, g, b), kept_text) = elms_icon_to_m0r(icon);
+    let mut losses = Vec::new();
+    if marker.background_texture != M0rTexture::Known(kept_texture) {
+        losses.push(Loss::Texture(marker.background_texture.clone()));
+    }
+    if let Some(text) = marker.text.as_deref() {
+        let kept = kept_text.as_deref().is_some_and(|k| k.eq_ignore_ascii_case(text.trim()));
+        if !kept && !text.trim().is_empty() {
+            losses.push(Loss::Text(text.to_string()));
+        }
+    }
+    if marker.colour != (r, g, b, 255) {
+        losses.push(Loss::Colour(marker.colour));
+    }
+    if let Some(orientation) = marker.orientation {
+        losses.push(Loss::Orientation(orientation));
+    }
+    if marker.size != 1.0 {
+        losses.push(Loss::Size(marker.size));
+    }
+
+    Conversion {
+        marker: ElmMarker {
+            position: marker.position,
+            icon,
+            size: 1,
+            active: marker.active,
+            id: marker.id,
+            map_id: marker.map_id,
+        },
+        losses,
+    }
+}
+
+/// Converts a marker to the given type, leaving it alone if it already is one.
+pub fn convert_marker(marker: &Marker, to: MarkerTypes) -> Conversion<Marker> {
+    match (marker, to) {
+        (Marker::Elms(m), MarkerTypes::M0r) => {
+            let Conversion { marker, losses } = elms_to_m0r(m);
+            Conversion { marker: Marker::M0r(marker), losses }
+        }
+        (Marker::M0r(m), MarkerTypes::Elms) => {
+            let Conversion { marker, losses } = m0r_to_elms(m);
+            Conversion { marker: Marker::Elms(marker), losses }
+        }
+        _ => Conversion { marker: marker.clone(), losses: Vec::new() },
+    }
+}
@@ -0,0 +1,75 @@
+use std::{fmt, ops::Range};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Severity {
+    /// Something was read but may not be what the author intended.
+    Warning,
+    /// Something could not be read and was dropped.
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Diagnostic {
+    /// Byte range into the parsed string
+    pub span: Range<usize>,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn warning(span: Range<usize>, message: impl Into<String>) -> Self {
+        Diagnostic { span, severity: Severity::Warning, message: message.into() }
+    }
+
+    pub fn error(span: Range<usize>, message: impl Into<String>) -> Self {
+        Diagnostic { span, severity: Severity::Error, message: message.into() }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}..{}: {}", self.severity, self.span.start, self.span.end, self.message)
+    }
+}
+
+/// Parsed data along with everything that went wrong while reading it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseResult<T> {
+    pub data: T,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl<T> ParseResult<T> {
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics.iter().any(|d| d.severity == Severity::Error)
+    }
+}
+
+/// Splits `s` on `sep`, yielding each piece with its byte range in the
+/// original string (offset by `base`).
+pub(crate) fn split_spans_raw(s: &str, base: usize, sep: char) -> impl Iterator<Item = (Range<usize>, &str)> {
+    let mut offset = base;
+    s.split(sep).map(move |piece| {
+        let start = offset;
+        offset += piece.len() + sep.len_utf8();
+        (start..start + piece.len(), piece)
+    })
+}
+
+/// Like [`split_spans_raw`], with each piece and its range trimmed.
+pub(crate) fn split_spans(s: &str, base: usize, sep: char) -> impl Iterator<Item = (Range<usize>, &str)> {
+    split_spans_raw(s, base, sep).map(|(span, piece)| {
+        let start = span.start + (piece.len() - piece.trim_start().len());
+        let trimmed = piece.trim();
+        (start..start + trimmed.len(), trimmed)
+    })
+}
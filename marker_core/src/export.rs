@@ -0,0 +1,299 @@
+//! Exporting a map view as an SVG image: the map's tiles, lines and markers,
+//! and optionally a title and a legend. Images are linked through a callback,
+//! so the browser can embed them and a script can point at files.
+
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+use crate::marker::{get_marker_active, get_marker_map_id, get_marker_position, get_svg, BreadcrumbLine, ElmsIcon, M0rTexture, Marker};
+use crate::selection::WorldRect;
+use crate::zone::{Map, Tile};
+
+/// Markers are this fraction of the view across at size 1, as on screen.
+pub const ICON_FRACTION: f64 = 1.0 / 30.0;
+/// The whole map is this many units across inside the image.
+pub(crate) const MAP_UNITS: f64 = 1000.0;
+const TITLE_HEIGHT: f64 = 48.0;
+const LEGEND_WIDTH: f64 = 280.0;
+const LEGEND_ROW: f64 = 30.0;
+const BACKGROUND: &str = "#333";
+
+/// Paths under `static/` for a map tile and the marker images.
+pub fn tile_path(tile: &Tile) -> String {
+    format!("maps/{}", tile.path)
+}
+
+pub fn elms_icon_path(icon: ElmsIcon) -> String {
+    format!("icons/{}", String::from(icon))
+}
+
+pub fn m0r_texture_path(texture: &M0rTexture) -> String {
+    format!("icons/m0r/{}", String::from(texture.clone()))
+}
+
+/// Where a marker's icon goes, as its left, top and width, in the same units
+/// as `(x, z)`, the marker's projected position. `icon` is the width at size 1.
+pub fn icon_rect(marker: &Marker, (x, z): (f64, f64), icon: f64) -> (f64, f64, f64) {
+    match marker {
+        Marker::Elms(m) => {
+            let size = icon * m.size as f64;
+            (x - size / 2.0, z - size / 2.0, size)
+        }
+        Marker::M0r(m) => {
+            let size = icon * (1.0 + m.size as f64 / 8.0);
+            (x - size / 2.0, z - size * 0.25, size)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportOptions {
+    /// Pixels across the longer side of the map in the image
+    pub size: u32,
+    pub title: Option<String>,
+    pub legend: bool,
+    /// World area to show instead of the whole map
+    pub crop: Option<WorldRect>,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self { size: 2048, title: None, legend: false, crop: None }
+    }
+}
+
+/// An exported image and its size in pixels.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvgImage {
+    pub svg: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// The world area covering the enabled `markers` and `lines` on `map_id`,
+/// with `margin` world units around it.
+pub fn bounds(markers: &[Marker], lines: &[BreadcrumbLine], map_id: u16, margin: f64) -> Option<WorldRect> {
+    let marker_points = markers.iter()
+        .filter(|m| get_marker_active(m) && get_marker_map_id(m) == map_id)
+        .map(get_marker_position);
+    let line_points = lines.iter()
+        .filter(|l| l.active && l.map_id == map_id)
+        .flat_map(|l| [l.position1, l.position2]);
+    marker_points.chain(line_points)
+        .map(|p| WorldRect { min_x: p.x as f64, max_x: p.x as f64, min_z: p.z as f64, max_z: p.z as f64 })
+        .reduce(|a, b| WorldRect {
+            min_x: a.min_x.min(b.min_x),
+            max_x: a.max_x.max(b.max_x),
+            min_z: a.min_z.min(b.min_z),
+            max_z: a.max_z.max(b.max_z),
+        })
+        .map(|r| WorldRect { min_x: r.min_x - margin, max_x: r.max_x + margin, min_z: r.min_z - margin, max_z: r.max_z + margin })
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn rgb((r, g, b, _): (u8, u8, u8, u8)) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+fn opacity(colour: (u8, u8, u8, u8)) -> f64 {
+    colour.3 as f64 / 255.0
+}
+
+/// The filter that fills an image's shape with `colour`, as the map does
+/// with m0r textures.
+fn tint_id(colour: (u8, u8, u8, u8)) -> String {
+    format!("tint-{:02x}{:02x}{:02x}{:02x}", colour.0, colour.1, colour.2, colour.3)
+}
+
+/// A file name without its folder or extension, to name an icon by.
+fn stem(path: &str) -> String {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    name.rsplit_once('.').map_or(name, |(stem, _)| stem).to_string()
+}
+
+/// An m0r marker's texture filling `x, y, size`: the shape's path if it has
+/// one, otherwise the tinted image.
+fn m0r_shape(texture: &M0rTexture, colour: (u8, u8, u8, u8), (x, y, size): (f64, f64, f64), href: &dyn Fn(&str) -> String, tints: &mut BTreeSet<(u8, u8, u8, u8)>) -> String {
+    match get_svg(texture) {
+        Some(shape) => format!(
+            r#"<svg x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" viewBox="{}"><path d="{}" fill="{}" fill-opacity="{:.3}"/></svg>"#,
+            x, y, size, size, shape.view_box, shape.path, rgb(colour), opacity(colour)
+        ),
+        None => {
+            tints.insert(colour);
+            format!(
+                r#"<image href="{}" x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" filter="url(#{})"/>"#,
+                escape(&href(&m0r_texture_path(texture))), x, y, size, size, tint_id(colour)
+            )
+        }
+    }
+}
+
+struct LegendEntry {
+    key: String,
+    swatch: String,
+    label: String,
+    count: usize,
+}
+
+/// The part of `map` shown, in map units, as left, top, right and bottom:
+/// all of it, or `crop` if that covers some of the map.
+pub(crate) fn view_box(map: &Map, crop: Option<WorldRect>) -> (f64, f64, f64, f64) {
+    let Some(crop) = crop else {
+        return (0.0, 0.0, MAP_UNITS, MAP_UNITS);
+    };
+    let project = |x: f64, z: f64| {
+        let (nx, nz) = map.scale_data.normalise(x, z);
+        (nx * MAP_UNITS, nz * MAP_UNITS)
+    };
+    let (a, b) = (project(crop.min_x, crop.min_z), project(crop.max_x, crop.max_z));
+    let clamp = |v: f64| v.clamp(0.0, MAP_UNITS);
+    let (x0, z0, x1, z1) = (clamp(a.0.min(b.0)), clamp(a.1.min(b.1)), clamp(a.0.max(b.0)), clamp(a.1.max(b.1)));
+    if x1 > x0 && z1 > z0 { (x0, z0, x1, z1) } else { (0.0, 0.0, MAP_UNITS, MAP_UNITS) }
+}
+
+/// Renders `map` with the enabled `markers` and `lines` on it. `href` turns a
+/// path under `static/` into the link or data URL to use for that image.
+pub fn svg(map: &Map, markers: &[Marker], lines: &[BreadcrumbLine], options: &ExportOptions, href: &dyn Fn(&str) -> String) -> SvgImage {
+    let scale = &map.scale_data;
+    let markers: Vec<&Marker> = markers.iter().filter(|m| get_marker_active(m) && get_marker_map_id(m) == map.map_id).collect();
+    let lines: Vec<&BreadcrumbLine> = lines.iter().filter(|l| l.active && l.map_id == map.map_id).collect();
+    let project = |x: f64, z: f64| {
+        let (nx, nz) = scale.normalise(x, z);
+        (nx * MAP_UNITS, nz * MAP_UNITS)
+    };
+
+    let (x0, z0, x1, z1) = view_box(map, options.crop);
+    let (view_w, view_h) = (x1 - x0, z1 - z0);
+    let pixels = options.size.max(1) as f64 / view_w.max(view_h);
+    let (map_w, map_h) = ((view_w * pixels).round(), (view_h * pixels).round());
+    let icon = view_w.max(view_h) * ICON_FRACTION;
+
+    let mut tints = BTreeSet::new();
+    let mut legend: Vec<LegendEntry> = Vec::new();
+    let mut add_legend = |key: String, swatch: String, label: String| {
+        match legend.iter_mut().find(|e| e.key == key) {
+            Some(entry) => entry.count += 1,
+            None => legend.push(LegendEntry { key, swatch, label, count: 1 }),
+        }
+    };
+
+    let mut body = String::new();
+    let tile_size = MAP_UNITS / map.count.max(1) as f64;
+    for (i, tile) in map.tiles.iter().enumerate() {
+        let (col, row) = ((i % map.count.max(1) as usize) as f64, (i / map.count.max(1) as usize) as f64);
+        let (tx, tz) = (col * tile_size, row * tile_size);
+        if tx >= x1 || tz >= z1 || tx + tile_size <= x0 || tz + tile_size <= z0 {
+            continue;
+        }
+        let _ = write!(
+            body,
+            r#"<image href="{}" x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" preserveAspectRatio="none"/>"#,
+            escape(&href(&tile_path(tile))), tx, tz, tile_size, tile_size
+        );
+    }
+
+    for line in &lines {
+        let (a, b) = (project(line.position1.x as f64, line.position1.z as f64), project(line.position2.x as f64, line.position2.z as f64));
+        let _ = write!(
+            body,
+            r#"<line x1="{:.2}" y1="{:.2}" x2="{:.2}" y2="{:.2}" stroke="{}" stroke-opacity="0.9" stroke-width="{:.2}" stroke-linecap="round"/>"#,
+            a.0, a.1, b.0, b.1, rgb(line.colour), icon * 0.06
+        );
+        add_legend(
+            format!("line {}", rgb(line.colour)),
+            format!(r#"<line x1="2" y1="12" x2="22" y2="12" stroke="{}" stroke-width="3"/>"#, rgb(line.colour)),
+            "Line".to_string(),
+        );
+    }
+
+    for marker in &markers {
+        let p = get_marker_position(marker);
+        let (x, y, size) = icon_rect(marker, project(p.x as f64, p.z as f64), icon);
+        match marker {
+            Marker::Elms(m) => {
+                let path = href(&elms_icon_path(m.icon));
+                let _ = write!(body, r#"<image href="{}" x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}"/>"#, escape(&path), x, y, size, size);
+                add_legend(
+                    format!("elms {}", String::from(m.icon)),
+                    format!(r#"<image href="{}" width="24" height="24"/>"#, escape(&path)),
+                    stem(&String::from(m.icon)),
+                );
+            }
+            Marker::M0r(m) => {
+                body.push_str(&m0r_shape(&m.background_texture, m.colour, (x, y, size), href, &mut tints));
+                if let Some(text) = &m.text {
+                    let _ = write!(
+                        body,
+                        r#"<text x="{:.2}" y="{:.2}" font-size="{:.2}" fill="white" stroke="black" stroke-width="{:.2}" paint-order="stroke" text-anchor="middle" dominant-baseline="hanging">{}</text>"#,
+                        x + size / 2.0, y + size / 2.0, icon * 0.4, icon * 0.1, escape(text)
+                    );
+                }
+                add_legend(
+                    format!("m0r {:?} {} {:?}", m.background_texture, rgb(m.colour), m.text),
+                    m0r_shape(&m.background_texture, m.colour, (0.0, 0.0, 24.0), href, &mut tints),
+                    m.text.clone().unwrap_or_else(|| stem(&String::from(m.background_texture.clone()))),
+                );
+            }
+        }
+    }
+
+    let top = if options.title.is_some() { TITLE_HEIGHT } else { 0.0 };
+    let legend_rows = if options.legend { legend.len() } else { 0 };
+    let (legend_w, legend_h) = match legend_rows {
+        0 => (0.0, 0.0),
+        rows => (LEGEND_WIDTH, 40.0 + rows as f64 * LEGEND_ROW),
+    };
+    let width = map_w + legend_w;
+    let height = top + map_h.max(legend_h);
+
+    let mut svg = String::new();
+    let _ = write!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="Univers, sans-serif">"#,
+        w = width, h = height
+    );
+    if !tints.is_empty() {
+        svg.push_str("<defs>");
+        for colour in &tints {
+            let _ = write!(
+                svg,
+                r#"<filter id="{}"><feFlood flood-color="{}" flood-opacity="{:.3}"/><feComposite in2="SourceGraphic" operator="in"/></filter>"#,
+                tint_id(*colour), rgb(*colour), opacity(*colour)
+            );
+        }
+        svg.push_str("</defs>");
+    }
+    let _ = write!(svg, r#"<rect width="{}" height="{}" fill="{}"/>"#, width, height, BACKGROUND);
+    if let Some(title) = &options.title {
+        let _ = write!(
+            svg,
+            r#"<text x="{:.1}" y="{:.1}" font-size="28" fill="white" text-anchor="middle">{}</text>"#,
+            width / 2.0, TITLE_HEIGHT * 0.7, escape(title)
+        );
+    }
+    let _ = write!(
+        svg,
+        r#"<svg x="0" y="{}" width="{}" height="{}" viewBox="{:.2} {:.2} {:.2} {:.2}" preserveAspectRatio="none">{}</svg>"#,
+        top, map_w, map_h, x0, z0, view_w, view_h, body
+    );
+    if legend_rows > 0 {
+        let _ = write!(svg, r#"<g transform="translate({} {})"><text x="16" y="24" font-size="18" fill="white">Legend</text>"#, map_w, top);
+        for (i, entry) in legend.iter().enumerate() {
+            let y = 40.0 + i as f64 * LEGEND_ROW;
+            let label = if entry.count > 1 { format!("{} ×{}", entry.label, entry.count) } else { entry.label.clone() };
+            let _ = write!(
+                svg,
+                r#"<g transform="translate(16 {:.1})">{}<text x="34" y="17" font-size="15" fill="white">{}</text></g>"#,
+                y, entry.swatch, escape(&label)
+            );
+        }
+        svg.push_str("</g>");
+    }
+    svg.push_str("</svg>");
+
+    SvgImage { svg, width: width as u32, height: height as u32 }
+}
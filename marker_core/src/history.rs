@@ -0,0 +1,312 @@
+use std::collections::HashMap;
+
+use crate::marker::{get_marker_active, get_marker_id, get_marker_position, BreadcrumbLine, Marker};
+use crate::project::Annotations;
+
+/// Equality that also counts the fields `PartialEq` leaves out, such as `id`
+/// and a m0r marker's `active`, since undo has to restore those too.
+trait Exact {
+    fn exact(&self, other: &Self) -> bool;
+}
+
+impl Exact for Marker {
+    fn exact(&self, other: &Self) -> bool {
+        self == other && get_marker_id(self) == get_marker_id(other) && get_marker_active(self) == get_marker_active(other)
+    }
+}
+
+impl Exact for BreadcrumbLine {
+    fn exact(&self, other: &Self) -> bool {
+        self == other && self.id == other.id
+    }
+}
+
+fn exact_lists<T: Exact>(a: &[T], b: &[T]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(x, y)| x.exact(y))
+}
+
+/// Borrowed markers and lines, both keyed by zone id.
+pub type Snapshot<'a> = (&'a HashMap<u16, Vec<Marker>>, &'a HashMap<u16, Vec<BreadcrumbLine>>);
+
+/// A zone's list before and after an edit. `None` means the zone had no entry.
+type Swap<T> = (Option<Vec<T>>, Option<Vec<T>>);
+
+#[derive(Debug, Clone, PartialEq)]
+struct ZoneChange {
+    zone_id: u16,
+    markers: Option<Swap<Marker>>,
+    lines: Option<Swap<BreadcrumbLine>>,
+}
+
+/// One undoable edit, stored as the zones it touched and, if it changed any
+/// names, notes, groups, tags or layers, the annotations before and after.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Command {
+    pub label: String,
+    /// Whether a quick run of these may be merged into one entry
+    mergeable: bool,
+    changes: Vec<ZoneChange>,
+    annotations: Option<(Annotations, Annotations)>,
+}
+
+fn diff<T: Clone + Exact>(before: &HashMap<u16, Vec<T>>, after: &HashMap<u16, Vec<T>>, zone_id: u16) -> Option<Swap<T>> {
+    let (b, a) = (before.get(&zone_id), after.get(&zone_id));
+    // An empty zone and a missing one look the same to the user.
+    if exact_lists(b.map(Vec::as_slice).unwrap_or_default(), a.map(Vec::as_slice).unwrap_or_default()) {
+        return None;
+    }
+    Some((b.cloned(), a.cloned()))
+}
+
+fn put<T: Clone>(map: &mut HashMap<u16, Vec<T>>, zone_id: u16, value: &Option<Vec<T>>) {
+    match value {
+        Some(v) => {map.insert(zone_id, v.clone());},
+        None => {map.remove(&zone_id);},
+    }
+}
+
+fn plural(n: usize, what: &str) -> String {
+    if n == 1 { format!("{} {}", n, what) } else { format!("{} {}s", n, what) }
+}
+
+/// A short description of what changed in one list, e.g. "Move 3 markers".
+fn describe_list<T: Exact>(before: &[T], after: &[T], what: &str, moved: impl Fn(&T, &T) -> bool) -> String {
+    if after.len() > before.len() {
+        return format!("Add {}", plural(after.len() - before.len(), what));
+    }
+    if after.len() < before.len() {
+        return format!("Delete {}", plural(before.len() - after.len(), what));
+    }
+    let changed: Vec<(&T, &T)> = before.iter().zip(after).filter(|(b, a)| !b.exact(a)).collect();
+    if !changed.is_empty() && changed.iter().all(|(b, a)| moved(b, a)) {
+        format!("Move {}", plural(changed.len(), what))
+    } else {
+        format!("Edit {}", plural(changed.len().max(1), what))
+    }
+}
+
+fn marker_moved(before: &Marker, after: &Marker) -> bool {
+    let mut b = before.clone();
+    match (&mut b, after) {
+        (Marker::Elms(b), Marker::Elms(a)) => {b.position = a.position; b.map_id = a.map_id;},
+        (Marker::M0r(b), Marker::M0r(a)) => {b.position = a.position; b.map_id = a.map_id;},
+        _ => return false,
+    }
+    b.exact(after) && get_marker_position(before) != get_marker_position(after)
+}
+
+fn line_moved(before: &BreadcrumbLine, after: &BreadcrumbLine) -> bool {
+    before.colour == after.colour && before.active == after.active && before.id == after.id
+}
+
+impl Command {
+    /// The edit that turns `before` into `after`, or `None` if they're the same.
+    pub fn between(before: Snapshot, after: Snapshot) -> Option<Command> {
+        let mut zone_ids: Vec<u16> = before.0.keys().chain(before.1.keys()).chain(after.0.keys()).chain(after.1.keys()).copied().collect();
+        zone_ids.sort();
+        zone_ids.dedup();
+
+        let changes: Vec<ZoneChange> = zone_ids
+            .into_iter()
+            .map(|zone_id| ZoneChange {
+                zone_id,
+                markers: diff(before.0, after.0, zone_id),
+                lines: diff(before.1, after.1, zone_id),
+            })
+            .filter(|c| c.markers.is_some() || c.lines.is_some())
+            .collect();
+        if changes.is_empty() {
+            return None;
+        }
+
+        let label = match &changes[..] {
+            [change] => {
+                let markers = change.markers.as_ref().map(|(b, a)| {
+                    describe_list(b.as_deref().unwrap_or_default(), a.as_deref().unwrap_or_default(), "marker", marker_moved)
+                });
+                let lines = change.lines.as_ref().map(|(b, a)| {
+                    describe_list(b.as_deref().unwrap_or_default(), a.as_deref().unwrap_or_default(), "line", line_moved)
+                });
+                match (markers, lines) {
+                    (Some(m), Some(l)) => format!("{}, {}", m, l.to_lowercase()),
+                    (Some(m), None) => m,
+                    (None, Some(l)) => l,
+                    (None, None) => unreachable!(),
+                }
+            }
+            _ => format!("Edit {} zones", changes.len()),
+        };
+
+        Some(Command { label, mergeable: false, changes, annotations: None })
+    }
+
+    /// Like `between`, but also recording the change from `old` annotations
+    /// to `new`. An edit to annotations alone is labelled "Edit notes".
+    pub fn annotated(before: Snapshot, after: Snapshot, old: &Annotations, new: &Annotations) -> Option<Command> {
+        let annotations = (old != new).then(|| (old.clone(), new.clone()));
+        match Command::between(before, after) {
+            Some(command) => Some(Command { annotations, ..command }),
+            None => annotations.map(|annotations| Command {
+                label: "Edit notes".to_string(),
+                mergeable: false,
+                changes: Vec::new(),
+                annotations: Some(annotations),
+            }),
+        }
+    }
+
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = label.into();
+        self
+    }
+
+    /// Marks this as one step of a continuous edit, such as a keystroke in a
+    /// coordinate box, so it merges with the one before.
+    pub fn merging(mut self) -> Self {
+        self.mergeable = true;
+        self
+    }
+
+    pub fn apply(&self, markers: &mut HashMap<u16, Vec<Marker>>, lines: &mut HashMap<u16, Vec<BreadcrumbLine>>, annotations: &mut Annotations) {
+        for change in &self.changes {
+            if let Some((_, after)) = &change.markers {
+                put(markers, change.zone_id, after);
+            }
+            if let Some((_, after)) = &change.lines {
+                put(lines, change.zone_id, after);
+            }
+        }
+        if let Some((_, after)) = &self.annotations {
+            *annotations = after.clone();
+        }
+    }
+
+    pub fn revert(&self, markers: &mut HashMap<u16, Vec<Marker>>, lines: &mut HashMap<u16, Vec<BreadcrumbLine>>, annotations: &mut Annotations) {
+        for change in &self.changes {
+            if let Some((before, _)) = &change.markers {
+                put(markers, change.zone_id, before);
+            }
+            if let Some((before, _)) = &change.lines {
+                put(lines, change.zone_id, before);
+            }
+        }
+        if let Some((before, _)) = &self.annotations {
+            *annotations = before.clone();
+        }
+    }
+
+    /// Folds a later edit into this one, keeping this one's starting state.
+    fn absorb(&mut self, later: Command) {
+        if let Some((later_before, after)) = later.annotations {
+            let before = self.annotations.take().map_or(later_before, |(b, _)| b);
+            self.annotations = Some((before, after));
+        }
+        for change in later.changes {
+            match self.changes.iter_mut().find(|c| c.zone_id == change.zone_id) {
+                Some(existing) => {
+                    if let Some((later_before, after)) = change.markers {
+                        let before = existing.markers.take().map_or(later_before, |(b, _)| b);
+                        existing.markers = Some((before, after));
+                    }
+                    if let Some((later_before, after)) = change.lines {
+                        let before = existing.lines.take().map_or(later_before, |(b, _)| b);
+                        existing.lines = Some((before, after));
+                    }
+                }
+                None => self.changes.push(change),
+            }
+        }
+    }
+
+    fn zone_ids(&self) -> Vec<u16> {
+        self.changes.iter().map(|c| c.zone_id).collect()
+    }
+}
+
+/// Undo and redo stacks. Mergeable edits on the same zones that arrive within
+/// `merge_window_ms` of each other (typing into a coordinate box, say) are
+/// merged into one entry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct History {
+    done: Vec<Command>,
+    undone: Vec<Command>,
+    limit: usize,
+    merge_window_ms: f64,
+    last_push_ms: Option<f64>,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        History::new(200, 1000.0)
+    }
+}
+
+impl History {
+    pub fn new(limit: usize, merge_window_ms: f64) -> Self {
+        History { done: Vec::new(), undone: Vec::new(), limit, merge_window_ms, last_push_ms: None }
+    }
+
+    /// Records an edit that has already been applied. `now_ms` is any
+    /// millisecond clock, used only to decide whether to merge.
+    pub fn push(&mut self, command: Command, now_ms: f64) {
+        self.undone.clear();
+        let recent = self.last_push_ms.is_some_and(|t| now_ms - t <= self.merge_window_ms);
+        self.last_push_ms = Some(now_ms);
+
+        if let Some(last) = self.done.last_mut()
+            && recent
+            && last.mergeable
+            && command.mergeable
+            && last.zone_ids() == command.zone_ids()
+        {
+            last.absorb(command);
+            return;
+        }
+
+        self.done.push(command);
+        if self.done.len() > self.limit {
+            self.done.remove(0);
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.done.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    /// Reverts the latest edit, returning its label.
+    pub fn undo(&mut self, markers: &mut HashMap<u16, Vec<Marker>>, lines: &mut HashMap<u16, Vec<BreadcrumbLine>>, annotations: &mut Annotations) -> Option<String> {
+        let command = self.done.pop()?;
+        command.revert(markers, lines, annotations);
+        let label = command.label.clone();
+        self.undone.push(command);
+        self.last_push_ms = None;
+        Some(label)
+    }
+
+    /// Re-applies the latest undone edit, returning its label.
+    pub fn redo(&mut self, markers: &mut HashMap<u16, Vec<Marker>>, lines: &mut HashMap<u16, Vec<BreadcrumbLine>>, annotations: &mut Annotations) -> Option<String> {
+        let command = self.undone.pop()?;
+        command.apply(markers, lines, annotations);
+        let label = command.label.clone();
+        self.done.push(command);
+        self.last_push_ms = None;
+        Some(label)
+    }
+
+    /// Undoes or redoes until exactly `position` edits are applied.
+    pub fn jump_to(&mut self, position: usize, markers: &mut HashMap<u16, Vec<Marker>>, lines: &mut HashMap<u16, Vec<BreadcrumbLine>>, annotations: &mut Annotations) {
+        self.last_push_ms = None;
+        while self.done.len() > position && self.undo(markers, lines, annotations).is_some() {}
+        while self.done.len() < position && self.redo(markers, lines, annotations).is_some() {}
+    }
+
+    /// Labels of every edit, oldest first, and how many of them are applied.
+    pub fn entries(&self) -> (Vec<&str>, usize) {
+        let labels = self.done.iter().chain(self.undone.iter().rev()).map(|c| c.label.as_str()).collect();
+        (labels, self.done.len())
+    }
+}
@@ -0,0 +1,23 @@
+//! Marker and zone data shared by the web editor and native tooling.
+//!
+//! Nothing in here touches the browser, so it builds and tests on any host.
+
+pub mod breadcrumbs;
+pub mod catalogue;
+pub mod convert;
+pub mod diagnostic;
+pub mod export;
+pub mod history;
+pub mod lua;
+pub mod marker;
+pub mod measure;
+pub mod pattern;
+pub mod project;
+pub mod render;
+pub mod savedvars;
+pub mod saves;
+pub mod selection;
+pub mod share;
+pub mod snap;
+pub mod transform;
+pub mod zone;
@@ -0,0 +1,377 @@
+//! Just enough Lua to read and write the SavedVariables files the game keeps
+//! for each addon: assignments of nested tables of strings, numbers and
+//! booleans, written out the way the game writes them.
+
+use std::fmt;
+use std::ops::Range;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Nil,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Table(Table),
+}
+
+impl Value {
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            Value::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_table(&self) -> Option<&Table> {
+        match self {
+            Value::Table(t) => Some(t),
+            _ => None,
+        }
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::String(s.to_string())
+    }
+}
+
+impl From<i64> for Value {
+    fn from(n: i64) -> Self {
+        Value::Number(n as f64)
+    }
+}
+
+impl From<Vec<(Value, Value)>> for Value {
+    fn from(pairs: Vec<(Value, Value)>) -> Self {
+        Value::Table(Table::from_pairs(pairs))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub key: Value,
+    pub value: Value,
+    /// Byte range of the value in the parsed text
+    pub span: Range<usize>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Table {
+    pub entries: Vec<Entry>,
+    /// Byte range from the opening to the closing brace in the parsed text
+    pub span: Range<usize>,
+}
+
+impl Table {
+    /// A table to write out, holding `pairs` in order.
+    pub fn from_pairs(pairs: Vec<(Value, Value)>) -> Self {
+        Table { entries: pairs.into_iter().map(|(key, value)| Entry { key, value, span: 0..0 }).collect(), span: 0..0 }
+    }
+
+    /// The value under the string key `key`.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.entries.iter().find(|e| e.key.as_str() == Some(key)).map(|e| &e.value)
+    }
+
+    /// The value under the number key `key`.
+    pub fn index(&self, key: i64) -> Option<&Value> {
+        self.entries.iter().find(|e| e.key.as_number() == Some(key as f64)).map(|e| &e.value)
+    }
+}
+
+/// A top-level `name = value`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Assignment {
+    pub name: String,
+    pub value: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LuaError {
+    Truncated,
+    /// What was found at a byte offset, and what was expected there
+    Unexpected(usize, String, &'static str),
+    BadNumber(usize, String),
+}
+
+impl fmt::Display for LuaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LuaError::Truncated => write!(f, "file is cut short"),
+            LuaError::Unexpected(at, found, expected) => write!(f, "expected {} at byte {}, found '{}'", expected, at, found),
+            LuaError::BadNumber(at, text) => write!(f, "'{}' at byte {} isn't a number", text, at),
+        }
+    }
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    at: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.text[self.at..]
+    }
+
+    /// Steps over whitespace and `--` comments, including `--[[ ]]` ones.
+    fn skip_space(&mut self) {
+        loop {
+            let trimmed = self.rest().trim_start();
+            self.at = self.text.len() - trimmed.len();
+            let Some(comment) = trimmed.strip_prefix("--") else { return };
+            let end = match comment.strip_prefix("[[") {
+                Some(long) => long.find("]]").map_or(long.len(), |i| i + 2) + 2,
+                None => comment.find('\n').unwrap_or(comment.len()),
+            };
+            self.at += 2 + end;
+        }
+    }
+
+    fn unexpected(&self, expected: &'static str) -> LuaError {
+        match self.rest().chars().next() {
+            None => LuaError::Truncated,
+            Some(c) => LuaError::Unexpected(self.at, c.to_string(), expected),
+        }
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_space();
+        if self.rest().starts_with(token) {
+            self.at += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &'static str) -> Result<(), LuaError> {
+        if self.eat(token) { Ok(()) } else { Err(self.unexpected(token)) }
+    }
+
+    fn name(&mut self) -> Option<&'a str> {
+        self.skip_space();
+        let rest = self.rest();
+        if !rest.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+            return None;
+        }
+        let len = rest.find(|c: char| !c.is_ascii_alphanumeric() && c != '_').unwrap_or(rest.len());
+        let start = self.at;
+        self.at += len;
+        Some(&self.text[start..start + len])
+    }
+
+    fn string(&mut self, quote: char) -> Result<String, LuaError> {
+        self.at += 1;
+        let mut bytes = Vec::new();
+        let mut chars = self.rest().char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                c if c == quote => {
+                    self.at += i + 1;
+                    return Ok(String::from_utf8_lossy(&bytes).into_owned());
+                }
+                '\\' => {
+                    let Some((_, escaped)) = chars.next() else { break };
+                    match escaped {
+                        'n' => bytes.push(b'\n'),
+                        't' => bytes.push(b'\t'),
+                        'r' => bytes.push(b'\r'),
+                        'a' => bytes.push(7),
+                        'b' => bytes.push(8),
+                        'f' => bytes.push(12),
+                        'v' => bytes.push(11),
+                        '0'..='9' => {
+                            // Up to three decimal digits, giving one byte.
+                            let mut code = escaped.to_digit(10).unwrap();
+                            for _ in 0..2 {
+                                match chars.clone().next() {
+                                    Some((_, d)) if d.is_ascii_digit() => {
+                                        code = code * 10 + d.to_digit(10).unwrap();
+                                        chars.next();
+                                    }
+                                    _ => break,
+                                }
+                            }
+                            bytes.push(code.min(255) as u8);
+                        }
+                        other => bytes.extend_from_slice(other.encode_utf8(&mut [0; 4]).as_bytes()),
+                    }
+                }
+                '\n' => return Err(LuaError::Unexpected(self.at + i, "end of line".to_string(), "closing quote")),
+                c => bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+            }
+        }
+        Err(LuaError::Truncated)
+    }
+
+    fn number(&mut self) -> Result<f64, LuaError> {
+        let start = self.at;
+        let rest = self.rest();
+        let len = rest.find(|c: char| !c.is_ascii_alphanumeric() && !matches!(c, '.' | '-' | '+')).unwrap_or(rest.len());
+        let text = &rest[..len];
+        self.at += len;
+        let (sign, digits) = match text.strip_prefix('-') {
+            Some(digits) => (-1.0, digits),
+            None => (1.0, text),
+        };
+        let value = match digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+            Some(hex) => i64::from_str_radix(hex, 16).ok().map(|n| n as f64),
+            None => digits.parse::<f64>().ok(),
+        };
+        value.map(|v| sign * v).ok_or_else(|| LuaError::BadNumber(start, text.to_string()))
+    }
+
+    fn value(&mut self) -> Result<Value, LuaError> {
+        self.skip_space();
+        let rest = self.rest();
+        match rest.chars().next() {
+            None => Err(LuaError::Truncated),
+            Some('{') => self.table().map(Value::Table),
+            Some(q @ ('"' | '\'')) => self.string(q).map(Value::String),
+            Some(c) if c.is_ascii_digit() || c == '-' || c == '.' => self.number().map(Value::Number),
+            Some(_) => {
+                let start = self.at;
+                match self.name() {
+                    Some("true") => Ok(Value::Bool(true)),
+                    Some("false") => Ok(Value::Bool(false)),
+                    Some("nil") => Ok(Value::Nil),
+                    _ => {
+                        self.at = start;
+                        Err(self.unexpected("a value"))
+                    }
+                }
+            }
+        }
+    }
+
+    fn table(&mut self) -> Result<Table, LuaError> {
+        let start = self.at;
+        self.expect("{")?;
+        let mut entries = Vec::new();
+        let mut next_index = 1;
+        loop {
+            if self.eat("}") {
+                return Ok(Table { entries, span: start..self.at });
+            }
+            let key = if self.eat("[") {
+                let key = self.value()?;
+                self.expect("]")?;
+                self.expect("=")?;
+                Some(key)
+            } else {
+                let before = self.at;
+                match self.name() {
+                    Some(name) if !matches!(name, "true" | "false" | "nil") && self.eat("=") => Some(Value::String(name.to_string())),
+                    _ => {
+                        self.at = before;
+                        None
+                    }
+                }
+            };
+            // Entries without a key are numbered from 1, as in Lua.
+            let key = match key {
+                Some(key) => key,
+                None => {
+                    next_index += 1;
+                    Value::Number((next_index - 1) as f64)
+                }
+            };
+            self.skip_space();
+            let value_start = self.at;
+            let value = self.value()?;
+            entries.push(Entry { key, value, span: value_start..self.at });
+            if !self.eat(",") && !self.eat(";") {
+                self.expect("}").map_err(|_| self.unexpected("',' or '}'"))?;
+                return Ok(Table { entries, span: start..self.at });
+            }
+        }
+    }
+}
+
+/// Every top-level assignment in `text`, such as a SavedVariables file.
+pub fn parse(text: &str) -> Result<Vec<Assignment>, LuaError> {
+    let mut parser = Parser { text, at: 0 };
+    let mut assignments = Vec::new();
+    loop {
+        parser.skip_space();
+        if parser.rest().is_empty() {
+            return Ok(assignments);
+        }
+        let name = parser.name().ok_or_else(|| parser.unexpected("a name"))?.to_string();
+        parser.expect("=")?;
+        let value = parser.value()?;
+        assignments.push(Assignment { name, value });
+    }
+}
+
+fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn write_scalar(out: &mut String, value: &Value) {
+    match value {
+        Value::Nil => out.push_str("nil"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => out.push_str(&(*n as i64).to_string()),
+        Value::Number(n) => out.push_str(&n.to_string()),
+        Value::String(s) => write_string(out, s),
+        Value::Table(_) => out.push_str("{}"),
+    }
+}
+
+fn write_value(out: &mut String, value: &Value, indent: &str) {
+    match value {
+        Value::Table(table) => {
+            out.push('\n');
+            out.push_str(indent);
+            out.push('{');
+            out.push_str(&table_body(table, indent));
+            out.push('}');
+        }
+        scalar => write_scalar(out, scalar),
+    }
+}
+
+/// What goes between the braces of `table` when its closing brace sits at
+/// `indent`, one entry per line as the game writes them.
+pub fn table_body(table: &Table, indent: &str) -> String {
+    let inner = format!("{}    ", indent);
+    let mut out = String::from("\n");
+    for entry in &table.entries {
+        out.push_str(&inner);
+        out.push('[');
+        write_scalar(&mut out, &entry.key);
+        out.push_str("] = ");
+        write_value(&mut out, &entry.value, &inner);
+        out.push_str(",\n");
+    }
+    out.push_str(indent);
+    out
+}
+
+/// `name = value`, as the game writes a SavedVariables file.
+pub fn write(name: &str, value: &Value) -> String {
+    let mut out = format!("{} =", name);
+    write_value(&mut out, value, "");
+    out.push('\n');
+    out
+}
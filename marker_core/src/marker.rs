@@ -1,17 +1,18 @@
-use std::{collections::{HashMap, HashSet}, hash::{Hash, Hasher}, str::from_utf8};
+use std::{collections::{HashMap, HashSet}, hash::{Hash, Hasher}, ops::Range, str::from_utf8};
 
-use js_sys::Date;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 
+use crate::diagnostic::{split_spans, split_spans_raw, Diagnostic, ParseResult};
 use crate::zone::{Map, Zone};
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Marker {
     Elms(ElmMarker),
     M0r(M0rMarker),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ElmMarker {
     pub position: Position3D,
     pub icon: ElmsIcon,
@@ -43,7 +44,7 @@ impl Hash for ElmMarker {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct M0rMarker {
     pub position: Position3D,
     pub background_texture: M0rTexture,
@@ -90,7 +91,7 @@ impl Hash for M0rMarker {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum M0rTexture {
     Known(M0rIcon),
     Unknown(String),
@@ -111,6 +112,41 @@ pub fn get_marker_position(m: &Marker) -> Position3D {
     }
 }
 
+pub fn set_marker_position(m: &mut Marker, position: Position3D) {
+    match m {
+        Marker::Elms(marker) => {marker.position = position},
+        Marker::M0r(marker) => {marker.position = position}
+    }
+}
+
+pub fn get_marker_map_id(m: &Marker) -> u16 {
+    match m {
+        Marker::Elms(marker) => {marker.map_id},
+        Marker::M0r(marker) => {marker.map_id}
+    }
+}
+
+pub fn set_marker_map_id(m: &mut Marker, map_id: u16) {
+    match m {
+        Marker::Elms(marker) => {marker.map_id = map_id},
+        Marker::M0r(marker) => {marker.map_id = map_id}
+    }
+}
+
+pub fn set_marker_id(m: &mut Marker, id: u16) {
+    match m {
+        Marker::Elms(marker) => {marker.id = id},
+        Marker::M0r(marker) => {marker.id = id}
+    }
+}
+
+pub fn get_marker_active(m: &Marker) -> bool {
+    match m {
+        Marker::Elms(marker) => {marker.active},
+        Marker::M0r(marker) => {marker.active}
+    }
+}
+
 pub fn set_marker_active(m: &mut Marker, a: bool) {
     match m {
         Marker::Elms(marker) => {marker.active = a},
@@ -118,12 +154,13 @@ pub fn set_marker_active(m: &mut Marker, a: bool) {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MarkerTypes {
     Elms,
     M0r,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BreadcrumbLine {
     pub position1: Position3D,
     pub position2: Position3D,
@@ -155,14 +192,14 @@ impl Hash for BreadcrumbLine {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Position3D {
     pub x: i32,
     pub y: i32,
     pub z: i32,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ElmsIcon {
     Num(u8), // 1–12
     Arrow,
@@ -202,7 +239,7 @@ pub enum ElmsIcon {
     Unknown,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum M0rIcon {
     Blank,
     Circle,
@@ -327,65 +364,153 @@ pub const ALL_M0R_ICONS: &[M0rTexture] = &[
     M0rTexture::Known(M0rIcon::ClassArcanist),
 ];
 
-fn find_best_map<'a>(x: i32, y: i32, z: i32, zone: &'a Zone) -> Option<&'a Map> {
+/// The map a position belongs to. Of the maps covering its X/Z, one whose
+/// height range holds its Y wins, then the nearest known floor, then a map
+/// with no height data, and only then a map whose range misses it. Ties go to
+/// the smallest map.
+pub fn find_best_map(x: i32, y: i32, z: i32, zone: &Zone) -> Option<&Map> {
     let matching_maps: Vec<&Map> = zone.maps.iter().filter(|map| {
         x >= map.scale_data.min_x as i32 && x <= map.scale_data.max_x as i32
         && z >= map.scale_data.min_z as i32 && z <= map.scale_data.max_z as i32
     }).collect();
 
     matching_maps.into_iter().min_by_key(|map| {
-        let width  = (map.scale_data.max_x - map.scale_data.min_x) as u32;
-        let depth  = (map.scale_data.max_z - map.scale_data.min_z) as u32;
+        let scale  = &map.scale_data;
+        let width  = (scale.max_x - scale.min_x) as u32;
+        let depth  = (scale.max_z - scale.min_z) as u32;
         let area   = width.saturating_mul(depth);
 
-        let (unknown_flag, y_offset) = match map.scale_data.y {
-            Some(by) => {
-                let dy = (y as f32 - by).abs() as u32;
-                (0u32, dy)
+        let (fit, y_offset) = if scale.has_y_range() {
+            if scale.contains_y(y as f64) {
+                (0u32, 0u32)
+            } else {
+                let below = scale.min_y.map_or(0.0, |min| min - y as f32);
+                let above = scale.max_y.map_or(0.0, |max| y as f32 - max);
+                (3, below.max(above) as u32)
+            }
+        } else {
+            match scale.y {
+                Some(by) => (1, (y as f32 - by).abs() as u32),
+                None => (2, 0),
             }
-            None => (1u32, u32::MAX),
         };
 
-        (unknown_flag, y_offset, area)
+        (fit, y_offset, area)
+    })
+}
+
+/// Where to show world `x`, `z`, as indexes into `zones` for the zone and the
+/// map. The `current` map is kept if it holds the point without a `y` saying
+/// otherwise, then the current zone is tried before the rest.
+pub fn locate(x: i32, y: Option<i32>, z: i32, zones: &[Zone], current: (usize, usize)) -> Option<(usize, usize)> {
+    let current_map = zones.get(current.0).and_then(|zone| zone.maps.get(current.1));
+    if let Some(map) = current_map
+        && map.scale_data.contains(x as f64, z as f64)
+        && y.is_none_or(|y| map.scale_data.contains_y(y as f64))
+    {
+        return Some(current);
+    }
+    let y = y.unwrap_or_else(|| current_map.and_then(|m| m.scale_data.floor_y()).map_or(0, |f| f.round() as i32));
+    let others = (0..zones.len()).filter(|&i| i != current.0);
+    std::iter::once(current.0).chain(others).find_map(|zone_index| {
+        let zone = zones.get(zone_index)?;
+        let map = find_best_map(x, y, z, zone)?;
+        Some((zone_index, zone.maps.iter().position(|m| m.map_id == map.map_id)?))
     })
 }
 
-pub fn parse_elms_string(elms_string: &str, zones: Vec<Zone>) -> HashMap<u16, Vec<Marker>> {
-    let re = Regex::new(r"/(?P<zone>\d+)//(?P<x>\d+),(?P<y>\d+),(?P<z>\d+),(?P<icon>\d+)/").unwrap();
+/// Reads "X, Z" or "X, Y, Z" as typed or pasted from `/loc` output. Labels
+/// and brackets around the numbers are ignored.
+pub fn parse_location(text: &str) -> Option<(i32, Option<i32>, i32)> {
+    let numbers = text.split(|c: char| c == ',' || c.is_whitespace())
+        .map(|s| s.trim_matches(|c: char| !(c.is_ascii_digit() || c == '-' || c == '.')))
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse::<f64>().ok().filter(|n| n.is_finite()).map(|n| n.round() as i32))
+        .collect::<Option<Vec<i32>>>()?;
+    match numbers[..] {
+        [x, z] => Some((x, None, z)),
+        [x, y, z] => Some((x, Some(y), z)),
+        _ => None,
+    }
+}
+
+/// The Y for a new marker on `map`: the map's floor if known, otherwise 0.
+pub fn placement_y(map: &Map) -> i32 {
+    map.scale_data.floor_y().map_or(0, |y| y.round() as i32)
+}
+
+/// The warning for a marker that was put on `map` even though its Y is
+/// outside the map's height range, if it is.
+fn y_range_warning(map: Option<&Map>, x: i32, y: i32, z: i32, zone: &Zone) -> Option<String> {
+    map.filter(|m| !m.scale_data.contains_y(y as f64))
+        .map(|m| format!("marker at {}, {}, {} is outside the height range of every map there in {}, so it was put on {}", x, y, z, zone.name, m.name))
+}
+
+const ELMS_PATTERN: &str = r"/(?P<zone>\d+)//(?P<x>-?\d+),(?P<y>-?\d+),(?P<z>-?\d+),(?P<icon>\d+)/";
+
+/// Byte ranges of every Elms marker in `s`.
+fn elms_spans(s: &str) -> Vec<Range<usize>> {
+    let re = Regex::new(ELMS_PATTERN).unwrap();
+    re.find_iter(s).map(|m| m.range()).collect()
+}
+
+pub fn parse_elms_string(elms_string: &str, zones: Vec<Zone>) -> ParseResult<HashMap<u16, Vec<Marker>>> {
+    let re = Regex::new(ELMS_PATTERN).unwrap();
     let mut result: HashMap<u16, Vec<Marker>> = HashMap::new();
+    let mut diagnostics = Vec::new();
     let mut seen: HashMap<u16, HashSet<Marker>> = HashMap::new();
     let mut id_counter = 0;
 
     for caps in re.captures_iter(elms_string) {
-        let zone_id: u16 = caps["zone"].parse().unwrap();
-        let x: i32 = caps["x"].parse().unwrap();
-        let y: i32 = caps["y"].parse().unwrap();
-        let z: i32 = caps["z"].parse().unwrap();
-        let icon_number: u16 = caps["icon"].parse().unwrap();
-        let icon_enum = ElmsIcon::try_from(icon_number).unwrap_or(ElmsIcon::Unknown);
-        let icon = icon_enum.into();
-
-        if let Some(zone_obj) = zones.iter().find(|zone| zone.id == zone_id) {
-            let best_map = find_best_map(x, y, z, zone_obj);
-
-            let marker = ElmMarker {
-                position: Position3D { x, y, z },
-                icon,
-                size: 1,
-                active: true,
-                id: id_counter,
-                map_id: best_map.map_or(0, |m| m.map_id),
-            };
+        let span = caps.get(0).unwrap().range();
+        let Ok(zone_id) = caps["zone"].parse::<u16>() else {
+            diagnostics.push(Diagnostic::error(caps.name("zone").unwrap().range(), format!("zone {} is out of range", &caps["zone"])));
+            continue;
+        };
+        let (Ok(x), Ok(y), Ok(z)) = (caps["x"].parse::<i32>(), caps["y"].parse::<i32>(), caps["z"].parse::<i32>()) else {
+            diagnostics.push(Diagnostic::error(span, "coordinate is out of range"));
+            continue;
+        };
+        let icon_span = caps.name("icon").unwrap().range();
+        let icon = match caps["icon"].parse::<u16>() {
+            Ok(n) => ElmsIcon::from(n),
+            Err(_) => ElmsIcon::Unknown,
+        };
+        if icon == ElmsIcon::Unknown {
+            diagnostics.push(Diagnostic::warning(icon_span, format!("icon {} unknown", &caps["icon"])));
+        }
 
-            let entry_set = seen.entry(zone_id).or_default();
-            if entry_set.insert(Marker::Elms(marker.clone())) {
-                result.entry(zone_id).or_default().push(Marker::Elms(marker));
-                id_counter += 1;
-            }
+        let Some(zone_obj) = zones.iter().find(|zone| zone.id == zone_id) else {
+            diagnostics.push(Diagnostic::error(caps.name("zone").unwrap().range(), format!("zone {} unknown", zone_id)));
+            continue;
+        };
+        let best_map = find_best_map(x, y, z, zone_obj);
+        if best_map.is_none() {
+            diagnostics.push(Diagnostic::warning(span.clone(), format!("marker at {}, {} is outside every map in {}", x, z, zone_obj.name)));
+        }
+        if let Some(warning) = y_range_warning(best_map, x, y, z, zone_obj) {
+            diagnostics.push(Diagnostic::warning(span.clone(), warning));
+        }
+
+        let marker = ElmMarker {
+            position: Position3D { x, y, z },
+            icon,
+            size: 1,
+            active: true,
+            id: id_counter,
+            map_id: best_map.map_or(0, |m| m.map_id),
+        };
+
+        let entry_set = seen.entry(zone_id).or_default();
+        if entry_set.insert(Marker::Elms(marker.clone())) {
+            result.entry(zone_id).or_default().push(Marker::Elms(marker));
+            id_counter += 1;
+        } else {
+            diagnostics.push(Diagnostic::warning(span, "duplicate marker ignored"));
         }
     }
 
-    result
+    ParseResult { data: result, diagnostics }
 }
 
 pub fn build_elms_string(markers_by_zone: &HashMap<u16, Vec<Marker>>) -> String {
@@ -396,20 +521,15 @@ pub fn build_elms_string(markers_by_zone: &HashMap<u16, Vec<Marker>>) -> String
     for zone in all_zones {
         if let Some(markers) = markers_by_zone.get(&zone) {
             for m in markers {
-                match m {
-                    Marker::Elms(elms_marker) => {
-                        if elms_marker.active {
-                                result.push_str(&format!(
-                                "/{}//{},{},{},{}/",
-                                zone,
-                                elms_marker.position.x,
-                                elms_marker.position.y,
-                                elms_marker.position.z,
-                                u16::from(&elms_marker.icon),
-                            ));
-                        }
-                    },
-                    _ => {},
+                if let Marker::Elms(elms_marker) = m && elms_marker.active {
+                    result.push_str(&format!(
+                        "/{}//{},{},{},{}/",
+                        zone,
+                        elms_marker.position.x,
+                        elms_marker.position.y,
+                        elms_marker.position.z,
+                        u16::from(&elms_marker.icon),
+                    ));
                 }
             }
 
@@ -450,6 +570,18 @@ pub fn hex_to_argb(hex: u32) -> (u8, u8, u8, u8) {
 }
 
 
+/// Like [`hex_to_argb`], but eight digits always carry alpha, so `00ff0000`
+/// is a fully transparent red rather than an opaque one.
+pub fn parse_argb_hex(s: &str) -> Option<(u8, u8, u8, u8)> {
+    let s = s.trim().trim_start_matches("0x");
+    let hex = u32::from_str_radix(s, 16).ok()?;
+    if s.len() > 6 {
+        Some(((hex >> 24) as u8, (hex >> 16) as u8, (hex >> 8) as u8, hex as u8))
+    } else {
+        Some(hex_to_argb(hex))
+    }
+}
+
 pub fn rgba_to_hex_string(rgba: (u8, u8, u8, u8)) -> String {
     let (r, g, b, a) = rgba;
     if a == 255 {
@@ -469,13 +601,85 @@ pub fn argb_to_hex_string(argb: (u8, u8, u8, u8)) -> String {
 }
 
 
-pub fn parse_lines_string(lines_string: &str, zones: Vec<Zone>) -> HashMap<u16, Vec<BreadcrumbLine>> {
+/// Reads the `;` separated hex tokens of a Breadcrumbs string.
+struct HexTokens<'a> {
+    tokens: Vec<(Range<usize>, &'a str)>,
+    cursor: usize,
+}
+
+impl<'a> HexTokens<'a> {
+    fn new(s: &'a str) -> Self {
+        let tokens = split_spans(s, 0, ';').filter(|(_, t)| !t.is_empty()).collect();
+        HexTokens { tokens, cursor: 0 }
+    }
+
+    fn has_more(&self) -> bool {
+        self.cursor < self.tokens.len()
+    }
+
+    fn next_raw(&mut self) -> Option<(Range<usize>, &'a str)> {
+        let token = self.tokens.get(self.cursor).cloned();
+        self.cursor += 1;
+        token
+    }
+
+    /// Reads the next token as hex. Missing tokens are reported against the end
+    /// of the last token read; invalid ones are reported and read as `None`.
+    fn next_hex(&mut self, what: &str, diagnostics: &mut Vec<Diagnostic>) -> Option<i64> {
+        let Some((span, token)) = self.next_raw() else {
+            let end = self.tokens.last().map_or(0, |(span, _)| span.end);
+            diagnostics.push(Diagnostic::error(end..end, format!("breadcrumb string ends before {}", what)));
+            return None;
+        };
+        match i64::from_str_radix(token, 16) {
+            Ok(v) => Some(v),
+            Err(_) => {
+                diagnostics.push(Diagnostic::error(span, format!("invalid hex '{}' for {}", token, what)));
+                None
+            }
+        }
+    }
+
+    /// Reads a count of items that each take `width` tokens, rejecting counts
+    /// that run past the end of the string.
+    fn next_count(&mut self, what: &str, width: usize, diagnostics: &mut Vec<Diagnostic>) -> Option<usize> {
+        let count = self.next_hex(what, diagnostics)?;
+        let remaining = self.tokens.len().saturating_sub(self.cursor);
+        if count < 0 || count as usize > remaining / width {
+            diagnostics.push(Diagnostic::error(self.span_of_previous(), format!("{} {} is more than the string holds", what, count)));
+            return None;
+        }
+        Some(count as usize)
+    }
+
+    fn span_of_previous(&self) -> Range<usize> {
+        self.tokens.get(self.cursor.saturating_sub(1)).map_or(0..0, |(span, _)| span.clone())
+    }
+}
+
+/// Blanks out m0r blocks and Elms markers so that their `;` don't get read as
+/// Breadcrumbs tokens. Byte offsets are preserved.
+fn mask_marker_strings(s: &str) -> String {
+    let mut bytes = s.as_bytes().to_vec();
+    let m0r = m0r_blocks(s).into_iter().map(|block| match block {
+        Ok(inner) => inner.start - 1..inner.end + 1,
+        Err(start) => start..s.len(),
+    });
+    for span in m0r.chain(elms_spans(s)) {
+        bytes[span].fill(b' ');
+    }
+    String::from_utf8(bytes).unwrap_or_else(|_| s.to_string())
+}
+
+pub fn parse_lines_string(lines_string: &str, zones: Vec<Zone>) -> ParseResult<HashMap<u16, Vec<BreadcrumbLine>>> {
     let mut result: HashMap<u16, Vec<BreadcrumbLine>> = HashMap::new();
+    let mut diagnostics = Vec::new();
 
-    let mut parts = lines_string.split(';').filter(|s| !s.trim().is_empty()).peekable();
+    let masked = mask_marker_strings(lines_string);
+    let mut parts = HexTokens::new(&masked);
 
-    while parts.peek().is_some() {
-        let zone_id_hex_raw = parts.next().unwrap_or("0").trim();
+    while parts.has_more() {
+        let (zone_span, zone_id_hex_raw) = parts.next_raw().unwrap_or((0..0, "0"));
         let zone_id_hex: String = zone_id_hex_raw
             .chars()
             .rev()
@@ -485,56 +689,86 @@ pub fn parse_lines_string(lines_string: &str, zones: Vec<Zone>) -> HashMap<u16,
             .rev()
             .collect();
         let Ok(zone_id) = u16::from_str_radix(&zone_id_hex, 16) else {
+            diagnostics.push(Diagnostic::error(zone_span, format!("expected a breadcrumb zone id, found '{}'", zone_id_hex_raw)));
             continue;
         };
+        let zone = zones.iter().find(|zone| zone.id == zone_id);
+        if zone.is_none() {
+            diagnostics.push(Diagnostic::warning(zone_span, format!("zone {} unknown", zone_id)));
+        }
+
+        let coordinate = |what: &str, parts: &mut HexTokens, diagnostics: &mut Vec<Diagnostic>| {
+            let value = parts.next_hex(what, diagnostics)?;
+            let fitted = i32::try_from(value).ok();
+            if fitted.is_none() {
+                diagnostics.push(Diagnostic::error(parts.span_of_previous(), format!("{} {} is out of range", what, value)));
+            }
+            fitted
+        };
 
-        let min_x = i32::from_str_radix(parts.next().unwrap_or("0").trim(), 16).unwrap_or(0);
-        let min_y = i32::from_str_radix(parts.next().unwrap_or("0").trim(), 16).unwrap_or(0);
-        let min_z = i32::from_str_radix(parts.next().unwrap_or("0").trim(), 16).unwrap_or(0);
+        let min_x = coordinate("minimum x", &mut parts, &mut diagnostics).unwrap_or(0);
+        let min_y = coordinate("minimum y", &mut parts, &mut diagnostics).unwrap_or(0);
+        let min_z = coordinate("minimum z", &mut parts, &mut diagnostics).unwrap_or(0);
 
-        let colour_count = usize::from_str_radix(parts.next().unwrap_or("0").trim(), 16).unwrap_or(0);
+        let Some(colour_count) = parts.next_count("colour count", 1, &mut diagnostics) else { break };
         let mut colours: Vec<(u8, u8, u8, u8)> = Vec::new();
         for _ in 0..colour_count {
-            if let Some(hex_str) = parts.next() {
-                if let Ok(hex_val) = u32::from_str_radix(hex_str.trim(), 16) {
-                    colours.push(hex_to_rgba(hex_val));
-                }
-            }
+            let hex_val = parts.next_hex("colour", &mut diagnostics).unwrap_or(0xFFFFFF);
+            colours.push(hex_to_rgba(hex_val as u32));
         }
 
-        let point_count = usize::from_str_radix(parts.next().unwrap_or("0").trim(), 16).unwrap_or(0);
-        let mut points: Vec<Position3D> = Vec::new();
+        let Some(point_count) = parts.next_count("point count", 3, &mut diagnostics) else { break };
+        let mut points: Vec<Option<Position3D>> = Vec::new();
         for _ in 0..point_count {
-            let x = i32::from_str_radix(parts.next().unwrap_or("0").trim(), 16).unwrap_or(0);
-            let y = i32::from_str_radix(parts.next().unwrap_or("0").trim(), 16).unwrap_or(0);
-            let z = i32::from_str_radix(parts.next().unwrap_or("0").trim(), 16).unwrap_or(0);
-            points.push(Position3D { x, y, z });
+            let x = coordinate("point x", &mut parts, &mut diagnostics);
+            let y = coordinate("point y", &mut parts, &mut diagnostics);
+            let z = coordinate("point z", &mut parts, &mut diagnostics);
+            let absolute = |v: Option<i32>, min: i32| v.and_then(|v| v.checked_add(min));
+            points.push(match (absolute(x, min_x), absolute(y, min_y), absolute(z, min_z)) {
+                (Some(x), Some(y), Some(z)) => Some(Position3D { x, y, z }),
+                _ => {
+                    if x.is_some() && y.is_some() && z.is_some() {
+                        diagnostics.push(Diagnostic::error(parts.span_of_previous(), "point is out of range once the minimum is added".to_string()));
+                    }
+                    None
+                }
+            });
         }
 
-        let line_count = usize::from_str_radix(parts.next().unwrap_or("0").trim(), 16).unwrap_or(0);
-        let mut lines: Vec<BreadcrumbLine> = Vec::new();
-        let mut id_counter = 0;
+        let Some(line_count) = parts.next_count("line count", 3, &mut diagnostics) else { break };
+        let lines = result.entry(zone_id).or_default();
+        let mut id_counter = lines.len() as u16;
 
         for _ in 0..line_count {
-            let colour_index = usize::from_str_radix(parts.next().unwrap_or("0").trim(), 16).unwrap_or(1);
-            let p1_index = usize::from_str_radix(parts.next().unwrap_or("0").trim(), 16).unwrap_or(1);
-            let p2_index = usize::from_str_radix(parts.next().unwrap_or("0").trim(), 16).unwrap_or(1);
-
-            if let (Some(&c), Some(p1), Some(p2)) = (
-                colours.get(colour_index.saturating_sub(1)),
-                points.get(p1_index.saturating_sub(1)),
-                points.get(p2_index.saturating_sub(1)),
-            ) {
-                let pos1 = Position3D { x: p1.x + min_x, y: p1.y + min_y, z: p1.z + min_z };
-                let pos2 = Position3D { x: p2.x + min_x, y: p2.y + min_y, z: p2.z + min_z };
-
-                let mid_x = (pos1.x + pos2.x) / 2;
-                let mid_y = (pos1.y + pos2.y) / 2;
-                let mid_z = (pos1.z + pos2.z) / 2;
-
-                let map_id = zones
-                    .iter()
-                    .find(|zone| zone.id == zone_id)
+            let colour_index = parts.next_hex("line colour", &mut diagnostics);
+            let colour_span = parts.span_of_previous();
+            let p1_index = parts.next_hex("line start point", &mut diagnostics);
+            let p1_span = parts.span_of_previous();
+            let p2_index = parts.next_hex("line end point", &mut diagnostics);
+            let p2_span = parts.span_of_previous();
+            let (Some(colour_index), Some(p1_index), Some(p2_index)) = (colour_index, p1_index, p2_index) else {
+                continue;
+            };
+
+            let colour = colours.get((colour_index as usize).wrapping_sub(1));
+            if colour.is_none() {
+                diagnostics.push(Diagnostic::error(colour_span, format!("line references colour {} but only {} defined", colour_index, colours.len())));
+            }
+            let p1 = points.get((p1_index as usize).wrapping_sub(1));
+            if p1.is_none() {
+                diagnostics.push(Diagnostic::error(p1_span, format!("line references point {} but only {} defined", p1_index, points.len())));
+            }
+            let p2 = points.get((p2_index as usize).wrapping_sub(1));
+            if p2.is_none() {
+                diagnostics.push(Diagnostic::error(p2_span, format!("line references point {} but only {} defined", p2_index, points.len())));
+            }
+
+            // Points out of range have been reported already.
+            if let (Some(&c), Some(&Some(pos1)), Some(&Some(pos2))) = (colour, p1, p2) {
+                let mid = |a: i32, b: i32| ((a as i64 + b as i64) / 2) as i32;
+                let (mid_x, mid_y, mid_z) = (mid(pos1.x, pos2.x), mid(pos1.y, pos2.y), mid(pos1.z, pos2.z));
+
+                let map_id = zone
                     .and_then(|zone| find_best_map(mid_x, mid_y, mid_z, zone))
                     .map_or(0, |m| m.map_id);
 
@@ -550,19 +784,31 @@ pub fn parse_lines_string(lines_string: &str, zones: Vec<Zone>) -> HashMap<u16,
                 id_counter += 1;
             }
         }
-
-        if !lines.is_empty() {
-            result.insert(zone_id, lines);
-        }
     }
 
-    result
+    result.retain(|_, lines| !lines.is_empty());
+    ParseResult { data: result, diagnostics }
 }
 
+/// Hex with a leading `-` for negative values rather than two's complement.
+fn signed_hex(v: i32) -> String {
+    if v < 0 {
+        format!("-{:X}", v.unsigned_abs())
+    } else {
+        format!("{:X}", v)
+    }
+}
+
+/// Lines that aren't active are left out, like markers in the Elms and m0r
+/// strings. Colours are written as six-digit RGB, so alpha is left out.
 pub fn lines_to_string(lines_by_zone: &HashMap<u16, Vec<BreadcrumbLine>>) -> String {
     let mut result = String::new();
 
-    for (zone_id, lines) in lines_by_zone {
+    let mut all_zones: Vec<u16> = lines_by_zone.keys().cloned().collect();
+    all_zones.sort();
+
+    for zone_id in all_zones {
+        let lines: Vec<&BreadcrumbLine> = lines_by_zone[&zone_id].iter().filter(|l| l.active).collect();
         if lines.is_empty() {
             continue;
         }
@@ -574,10 +820,10 @@ pub fn lines_to_string(lines_by_zone: &HashMap<u16, Vec<BreadcrumbLine>>) -> Str
                 (mx.min(pos.x), my.min(pos.y), mz.min(pos.z))
             });
 
-        result.push_str(&format!("{:X};{:X};{:X};", min_x, min_y, min_z));
+        result.push_str(&format!("{};{};{};", signed_hex(min_x), signed_hex(min_y), signed_hex(min_z)));
 
         let mut colours: Vec<(u8, u8, u8, u8)> = Vec::new();
-        for line in lines {
+        for line in &lines {
             if !colours.contains(&line.colour) {
                 colours.push(line.colour);
             }
@@ -604,7 +850,7 @@ pub fn lines_to_string(lines_by_zone: &HashMap<u16, Vec<BreadcrumbLine>>) -> Str
             }
         };
 
-        for line in lines {
+        for line in &lines {
             add_point(line.position1, &mut points, &mut point_index, &mut next_idx);
             add_point(line.position2, &mut points, &mut point_index, &mut next_idx);
         }
@@ -615,7 +861,7 @@ pub fn lines_to_string(lines_by_zone: &HashMap<u16, Vec<BreadcrumbLine>>) -> Str
         }
 
         result.push_str(&format!("{:X};", lines.len()));
-        for line in lines {
+        for line in &lines {
             let colour_idx = colours.iter().position(|&c| c == line.colour).unwrap_or(0) + 1;
             let p1_idx = point_index[&Position3D { x: line.position1.x - min_x, y: line.position1.y - min_y, z: line.position1.z - min_z }];
             let p2_idx = point_index[&Position3D { x: line.position2.x - min_x, y: line.position2.y - min_y, z: line.position2.z - min_z }];
@@ -632,318 +878,366 @@ const MOR_SQUAREBRACKET: &[u8] = b"\xee\x80\x82";
 const MOR_SEMICOLON: &[u8] = b"\xee\x80\x83";
 const MOR_GREATERTHAN: &[u8] = b"\xee\x80\x84";
 
-fn parse_hex_i32(s: &str) -> i32 {
+fn parse_hex_i32(s: &str) -> Option<i32> {
     let s = s.trim().trim_start_matches("0x");
     if s.is_empty() {
-        return 0;
-    }
-    match i32::from_str_radix(s, 16) {
-        Ok(v) => v,
-        Err(_) => 0,
+        return Some(0);
     }
+    i32::from_str_radix(s, 16).ok()
 }
 
-pub fn parse_m0r_string(m0r_string: &str, zones: Vec<Zone>) -> HashMap<u16, Vec<Marker>> {
-    let mut result: HashMap<u16, Vec<Marker>> = HashMap::new();
-
-    let trimmed = m0r_string.trim();
-    let inner = match (trimmed.find('<'), trimmed.rfind('>')) {
-        (Some(start), Some(end)) if end > start => &trimmed[start + 1..end],
-        _ => {
-            eprintln!("Malformed m0r marker string: no enclosing <>");
-            return result;
+/// Finds every `<...>` block in `s`. `Ok` holds the range between the angle
+/// brackets, `Err` the position of a `<` that is never closed.
+fn m0r_blocks(s: &str) -> Vec<Result<Range<usize>, usize>> {
+    let mut blocks = Vec::new();
+    let mut offset = 0;
+    while let Some(start) = s[offset..].find('<').map(|i| i + offset) {
+        match s[start..].find('>') {
+            Some(len) => {
+                blocks.push(Ok(start + 1..start + len));
+                offset = start + len + 1;
+            }
+            None => {
+                blocks.push(Err(start));
+                break;
+            }
         }
-    };
-
-    let fields: Vec<&str> = inner.split(']').collect();
-    if fields.len() < 9 {
-        eprintln!(
-            "Malformed m0r marker string: expected >=9 ']' separated fields, got {}",
-            fields.len()
-        );
-        return result;
     }
+    blocks
+}
 
-    let zone_str = fields[0].trim();
-    let mins = fields[2].trim();
-    let sizes = fields[3].trim();
-    let pitch_field = fields[4].trim();
-    let yaw_field = fields[5].trim();
-    let colour_field = fields[6].trim();
-    let texture_field = fields[7].trim();
-    let positions_field = fields[8].trim();
-
-    let zone = match zones.iter().find(|z| z.id.to_string() == zone_str) {
-        Some(z) => z,
-        None => return result,
-    };
-
-    let mins_parts: Vec<&str> = mins.split(':').collect();
-    let min_x = parse_hex_i32(mins_parts.get(0).unwrap_or(&"0"));
-    let min_y = parse_hex_i32(mins_parts.get(1).unwrap_or(&"0"));
-    let min_z = parse_hex_i32(mins_parts.get(2).unwrap_or(&"0"));
-
-    let unescape_text = |s: &str| {
-        let mut t = s.to_string();
-        t = t.replace(std::str::from_utf8(MOR_COLON).unwrap_or("::"), ":");
-        t = t.replace(std::str::from_utf8(MOR_COMMA).unwrap_or(","), ",");
-        t = t.replace(std::str::from_utf8(MOR_SQUAREBRACKET).unwrap_or("]"), "]");
-        t = t.replace(std::str::from_utf8(MOR_SEMICOLON).unwrap_or(";"), ";");
-        t = t.replace(std::str::from_utf8(MOR_GREATERTHAN).unwrap_or(">"), ">");
-        t = t.replace(r#"\\n"#, "\n");
-        t
-    };
-
-    let mut markers: Vec<M0rMarker> = Vec::new();
-    for chunk in positions_field.split(',') {
-        let parts: Vec<&str> = chunk.trim().split(':').collect();
-        if parts.len() < 3 {
+/// Applies one `value:index,index;value:index` attribute field to the markers
+/// it references.
+fn apply_m0r_groups<T>(
+    field: &str,
+    base: usize,
+    what: &str,
+    markers: &mut [Option<M0rMarker>],
+    diagnostics: &mut Vec<Diagnostic>,
+    parse: impl Fn(&str) -> Option<T>,
+    apply: impl Fn(&mut M0rMarker, &T),
+) {
+    for (span, segment) in split_spans(field, base, ';').filter(|(_, s)| !s.is_empty()) {
+        let Some((value_str, idx_str)) = segment.split_once(':') else {
+            diagnostics.push(Diagnostic::warning(span, format!("{} group '{}' has no marker list", what, segment)));
             continue;
-        }
-
-        let cx = parse_hex_i32(parts[0]) + min_x;
-        let cy = parse_hex_i32(parts[1]) + min_y;
-        let cz = parse_hex_i32(parts[2]) + min_z;
-
-        let map = match find_best_map(cx, cy, cz, zone) {
-            Some(m) => m,
-            None => continue,
         };
-
-        let text_raw = parts.get(3).copied().unwrap_or("");
-        let mut unescaped_text = unescape_text(text_raw);
-        if unescaped_text.is_empty() {
-            unescaped_text = String::new();
-        }
-
-        let new_marker = M0rMarker {
-            id: markers.len() as u16,
-            map_id: map.map_id,
-            active: true,
-            position: Position3D { x: cx, y: cy, z: cz },
-            background_texture: M0rTexture::None,
-            text: unescaped_text.into(),
-            size: 1.0,
-            colour: (255, 255, 255, 255),
-            orientation: None,
+        let Some(value) = parse(value_str) else {
+            diagnostics.push(Diagnostic::warning(span.start..span.start + value_str.len(), format!("invalid {} '{}'", what, value_str)));
+            continue;
         };
-
-        markers.push(new_marker);
-    }
-
-    let parse_index = |s: &str| s.parse::<usize>().ok().and_then(|i| i.checked_sub(1));
-
-    for segment in sizes.split(';').map(str::trim).filter(|s| !s.is_empty()) {
-        if let Some((size_str, idx_str)) = segment.split_once(':') {
-            if let Ok(size) = size_str.parse::<f32>() {
-                for idx in idx_str.split(',').map(str::trim) {
-                    if let Some(i) = parse_index(idx) {
-                        if let Some(marker) = markers.get_mut(i) {
-                            marker.size = size;
-                        }
+        for (idx_span, idx) in split_spans(idx_str, span.start + value_str.len() + 1, ',') {
+            match idx.parse::<usize>().ok().and_then(|i| i.checked_sub(1)) {
+                Some(i) if i < markers.len() => {
+                    if let Some(marker) = markers[i].as_mut() {
+                        apply(marker, &value);
                     }
                 }
+                Some(i) => diagnostics.push(Diagnostic::warning(idx_span, format!("{} references marker {} but only {} defined", what, i + 1, markers.len()))),
+                None => diagnostics.push(Diagnostic::warning(idx_span, format!("{} references invalid marker '{}'", what, idx))),
             }
         }
     }
+}
 
-    for segment in colour_field.split(';').map(str::trim).filter(|s| !s.is_empty()) {
-        if let Some((hex, idx_str)) = segment.split_once(':') {
-            if let Ok(hex_val) = u32::from_str_radix(hex.trim_start_matches("0x"), 16) {
-                let (a, r, g, b) = hex_to_argb(hex_val);
-                for idx in idx_str.split(',').map(str::trim) {
-                    if let Some(i) = parse_index(idx) {
-                        if let Some(marker) = markers.get_mut(i) {
-                            marker.colour = (r, g, b, a);
-                        }
-                    }
-                }
+pub fn parse_m0r_string(m0r_string: &str, zones: Vec<Zone>) -> ParseResult<HashMap<u16, Vec<Marker>>> {
+    let mut result: HashMap<u16, Vec<Marker>> = HashMap::new();
+    let mut diagnostics = Vec::new();
+
+    for block in m0r_blocks(m0r_string) {
+        let inner_span = match block {
+            Ok(span) => span,
+            Err(start) => {
+                diagnostics.push(Diagnostic::error(start..m0r_string.len(), "m0r string has no closing '>'"));
+                continue;
             }
+        };
+        let inner = &m0r_string[inner_span.clone()];
+
+        // Marker text runs to the end of the block, so only the leading edge of
+        // each field is trimmed.
+        let fields: Vec<(Range<usize>, &str)> = split_spans_raw(inner, inner_span.start, ']')
+            .map(|(span, field)| (span.end - field.trim_start().len()..span.end, field.trim_start()))
+            .collect();
+        if fields.len() < 9 {
+            diagnostics.push(Diagnostic::error(
+                inner_span.start - 1..inner_span.end + 1,
+                format!("expected at least 9 ']' separated fields, got {}", fields.len()),
+            ));
+            continue;
         }
-    }
 
-    for segment in pitch_field.split(';').map(str::trim).filter(|s| !s.is_empty()) {
-        if let Some((p_str, idx_str)) = segment.split_once(':') {
-            if let Ok(pitch) = p_str.parse::<i8>() {
-                for idx in idx_str.split(',').map(str::trim) {
-                    if let Some(i) = parse_index(idx) {
-                        if let Some(marker) = markers.get_mut(i) {
-                            let yaw = marker.orientation.map(|(_, y)| y).unwrap_or(0);
-                            marker.orientation = Some((pitch, yaw));
-                        }
-                    }
-                }
-            }
+        let (zone_span, zone_str) = (fields[0].0.clone(), fields[0].1.trim());
+        let (mins_span, mins) = fields[2].clone();
+        let (sizes_span, sizes) = fields[3].clone();
+        let (pitch_span, pitch_field) = fields[4].clone();
+        let (yaw_span, yaw_field) = fields[5].clone();
+        let (colour_span, colour_field) = fields[6].clone();
+        let (texture_span, texture_field) = fields[7].clone();
+        let (positions_span, positions_field) = fields[8].clone();
+
+        let Ok(zone_id) = zone_str.parse::<u16>() else {
+            diagnostics.push(Diagnostic::error(zone_span, format!("zone '{}' is not a zone id", zone_str)));
+            continue;
+        };
+        let Some(zone) = zones.iter().find(|z| z.id == zone_id) else {
+            diagnostics.push(Diagnostic::error(zone_span, format!("zone {} unknown", zone_id)));
+            continue;
+        };
+
+        let mut mins_parts: Vec<i32> = Vec::new();
+        for (span, s) in split_spans(mins, mins_span.start, ':') {
+            mins_parts.push(parse_hex_i32(s).unwrap_or_else(|| {
+                diagnostics.push(Diagnostic::error(span, format!("invalid hex '{}' in minimum position", s)));
+                0
+            }));
         }
-    }
+        let min_x = mins_parts.first().copied().unwrap_or(0);
+        let min_y = mins_parts.get(1).copied().unwrap_or(0);
+        let min_z = mins_parts.get(2).copied().unwrap_or(0);
+
+        let unescape_text = |s: &str| {
+            let mut t = s.to_string();
+            t = t.replace(std::str::from_utf8(MOR_COLON).unwrap_or("::"), ":");
+            t = t.replace(std::str::from_utf8(MOR_COMMA).unwrap_or(","), ",");
+            t = t.replace(std::str::from_utf8(MOR_SQUAREBRACKET).unwrap_or("]"), "]");
+            t = t.replace(std::str::from_utf8(MOR_SEMICOLON).unwrap_or(";"), ";");
+            t = t.replace(std::str::from_utf8(MOR_GREATERTHAN).unwrap_or(">"), ">");
+            t = t.replace(r#"\\n"#, "\n");
+            t
+        };
 
-    for segment in yaw_field.split(';').map(str::trim).filter(|s| !s.is_empty()) {
-        if let Some((y_str, idx_str)) = segment.split_once(':') {
-            if let Ok(yaw) = y_str.parse::<i16>() {
-                for idx in idx_str.split(',').map(str::trim) {
-                    if let Some(i) = parse_index(idx) {
-                        if let Some(marker) = markers.get_mut(i) {
-                            let pitch = marker.orientation.map(|(p, _)| p).unwrap_or(0);
-                            marker.orientation = Some((pitch, yaw));
-                        }
-                    }
-                }
+        // Kept aligned with the position list so attribute indices stay valid
+        // when a position can't be read.
+        let mut markers: Vec<Option<M0rMarker>> = Vec::new();
+        let mut next_id = result.get(&zone_id).map_or(0, |v| v.len() as u16);
+        let chunks = split_spans_raw(positions_field, positions_span.start, ',')
+            .map(|(span, chunk)| (span.end - chunk.trim_start().len()..span.end, chunk.trim_start()))
+            .filter(|_| !positions_field.is_empty());
+        for (span, chunk) in chunks {
+            let parts: Vec<&str> = chunk.split(':').collect();
+            if parts.len() < 3 {
+                diagnostics.push(Diagnostic::error(span, format!("position '{}' needs x:y:z", chunk)));
+                markers.push(None);
+                continue;
             }
-        }
-    }
 
-    for segment in texture_field.split(';').map(str::trim).filter(|s| !s.is_empty()) {
-        if let Some((tex_str, idx_str)) = segment.split_once(':') {
-            for idx in idx_str.split(',').map(str::trim) {
-                if let Some(i) = parse_index(idx) {
-                    if let Some(marker) = markers.get_mut(i) {
-                        marker.background_texture = M0rTexture::from(tex_str);
-                    }
-                }
+            let (Some(x), Some(y), Some(z)) = (parse_hex_i32(parts[0]), parse_hex_i32(parts[1]), parse_hex_i32(parts[2])) else {
+                diagnostics.push(Diagnostic::error(span, format!("invalid hex in position '{}'", chunk)));
+                markers.push(None);
+                continue;
+            };
+            let cx = x + min_x;
+            let cy = y + min_y;
+            let cz = z + min_z;
+
+            let map = find_best_map(cx, cy, cz, zone);
+            if map.is_none() {
+                diagnostics.push(Diagnostic::warning(span.clone(), format!("marker at {}, {} is outside every map in {}", cx, cz, zone.name)));
             }
+            if let Some(warning) = y_range_warning(map, cx, cy, cz, zone) {
+                diagnostics.push(Diagnostic::warning(span.clone(), warning));
+            }
+
+            let text_raw = parts.get(3).copied().unwrap_or("");
+            let unescaped_text = Some(unescape_text(text_raw)).filter(|t| !t.is_empty());
+
+            markers.push(Some(M0rMarker {
+                id: next_id,
+                map_id: map.map_or(0, |m| m.map_id),
+                active: true,
+                position: Position3D { x: cx, y: cy, z: cz },
+                background_texture: M0rTexture::None,
+                text: unescaped_text,
+                size: 1.0,
+                colour: (255, 255, 255, 255),
+                orientation: None,
+            }));
+            next_id = next_id.saturating_add(1);
         }
+
+        apply_m0r_groups(sizes, sizes_span.start, "size", &mut markers, &mut diagnostics,
+            |s| s.parse::<f32>().ok(),
+            |marker, &size| marker.size = size,
+        );
+        apply_m0r_groups(colour_field, colour_span.start, "colour", &mut markers, &mut diagnostics,
+            parse_argb_hex,
+            |marker, &(a, r, g, b)| marker.colour = (r, g, b, a),
+        );
+        apply_m0r_groups(pitch_field, pitch_span.start, "pitch", &mut markers, &mut diagnostics,
+            |s| s.parse::<i8>().ok(),
+            |marker, &pitch| {
+                let yaw = marker.orientation.map(|(_, y)| y).unwrap_or(0);
+                marker.orientation = Some((pitch, yaw));
+            },
+        );
+        apply_m0r_groups(yaw_field, yaw_span.start, "yaw", &mut markers, &mut diagnostics,
+            |s| s.parse::<i16>().ok(),
+            |marker, &yaw| {
+                let pitch = marker.orientation.map(|(p, _)| p).unwrap_or(0);
+                marker.orientation = Some((pitch, yaw));
+            },
+        );
+        apply_m0r_groups(texture_field, texture_span.start, "texture", &mut markers, &mut diagnostics,
+            |s| Some(M0rTexture::from(s)),
+            |marker, tex| marker.background_texture = tex.clone(),
+        );
+
+        result.entry(zone_id).or_default().extend(markers.into_iter().flatten().map(Marker::M0r));
     }
 
-    let returned_markers = markers.into_iter().map(Marker::M0r).collect::<Vec<_>>();
-    result.insert(zone.id, returned_markers);
+    result.retain(|_, markers| !markers.is_empty());
 
-    result
+    ParseResult { data: result, diagnostics }
 }
 
-pub fn get_timestamp() -> String {
-    let timestamp = Date::now() / 1000.0;
-    timestamp.floor().to_string()
+/// Groups 1-based marker indices by key, in order of first appearance, and
+/// formats them as `key:index,index;key:index`.
+fn format_index_groups<K: PartialEq>(entries: Vec<(K, usize)>, format_key: impl Fn(&K) -> String) -> String {
+    let mut groups: Vec<(K, Vec<usize>)> = Vec::new();
+    for (key, index) in entries {
+        match groups.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, idxs)) => idxs.push(index),
+            None => groups.push((key, vec![index])),
+        }
+    }
+    groups
+        .iter()
+        .map(|(key, idxs)| {
+            let idx_str = idxs.iter().map(|i| i.to_string()).collect::<Vec<_>>().join(",");
+            format!("{}:{}", format_key(key), idx_str)
+        })
+        .collect::<Vec<_>>()
+        .join(";")
 }
 
-pub fn build_m0r_string(markers_by_zone: &HashMap<u16, Vec<Marker>>) -> String {
+/// `timestamp` is the unix time (in seconds) written into each zone block.
+pub fn build_m0r_string(markers_by_zone: &HashMap<u16, Vec<Marker>>, timestamp: u64) -> String {
     let mut result = String::new();
 
-    for (zone_id, markers) in markers_by_zone {
+    let mut all_zones: Vec<u16> = markers_by_zone.keys().cloned().collect();
+    all_zones.sort();
+
+    for zone_id in all_zones {
+        let markers: Vec<&M0rMarker> = markers_by_zone[&zone_id]
+            .iter()
+            .filter_map(|m| match m {
+                Marker::M0r(marker) if marker.active => Some(marker),
+                _ => None,
+            })
+            .collect();
         if markers.is_empty() {
             continue;
         }
 
-        let min_x = markers.iter().map(|m| get_marker_position(m).x).min().unwrap_or(0);
-        let min_y = markers.iter().map(|m| get_marker_position(m).y).min().unwrap_or(0);
-        let min_z = markers.iter().map(|m| get_marker_position(m).z).min().unwrap_or(0);
-
-        let mins = format!(
-            "{:X}:{:X}:{:X}",
-            min_x.max(0) as u32,
-            min_y.max(0) as u32,
-            min_z.max(0) as u32
-        );
+        let min_x = markers.iter().map(|m| m.position.x).min().unwrap_or(0);
+        let min_y = markers.iter().map(|m| m.position.y).min().unwrap_or(0);
+        let min_z = markers.iter().map(|m| m.position.z).min().unwrap_or(0);
 
-        let timestamp = get_timestamp();
+        let mins = format!("{}:{}:{}", signed_hex(min_x), signed_hex(min_y), signed_hex(min_z)).to_lowercase();
 
-        let mut size_groups: HashMap<String, Vec<usize>> = HashMap::new();
-        let mut pitch_groups: HashMap<i8, Vec<usize>> = HashMap::new();
-        let mut yaw_groups: HashMap<i16, Vec<usize>> = HashMap::new();
-        let mut colour_groups: HashMap<String, Vec<usize>> = HashMap::new();
-        let mut texture_groups: HashMap<M0rTexture, Vec<usize>> = HashMap::new();
+        let mut sizes: Vec<(String, usize)> = Vec::new();
+        let mut pitches: Vec<(i8, usize)> = Vec::new();
+        let mut yaws: Vec<(i16, usize)> = Vec::new();
+        let mut colours: Vec<(String, usize)> = Vec::new();
+        let mut textures: Vec<(M0rTexture, usize)> = Vec::new();
 
         let mut positions: Vec<String> = Vec::new();
 
-        for (i, marker_enum) in markers.iter().enumerate() {
-            if let Marker::M0r(marker) = marker_enum {
-                if !marker.active {continue;}
-                let pos = marker.position;
-                let cx = (pos.x - min_x) as u32;
-                let cy = (pos.y - min_y) as u32;
-                let cz = (pos.z - min_z) as u32;
-
-                let mut text = marker.text.clone().unwrap_or_else(|| "".to_string());
-                text = text
-                    .replace(":", from_utf8(MOR_COLON).unwrap_or("::"))
-                    .replace(",", from_utf8(MOR_COMMA).unwrap_or(","))
-                    .replace("]", from_utf8(MOR_SQUAREBRACKET).unwrap_or("]"))
-                    .replace(";", from_utf8(MOR_SEMICOLON).unwrap_or(";"))
-                    .replace(">", from_utf8(MOR_GREATERTHAN).unwrap_or(">"))
-                    .replace("\n", r#"\\n"#);
-
-                let pos_string = format!("{:X}:{:X}:{:X}", cx, cy, cz).to_lowercase();
-                positions.push(format!("{}:{}", pos_string, text));
-
-                size_groups.entry(marker.size.to_string()).or_default().push(i + 1);
-
-                if let Some((p, y)) = marker.orientation {
-                    pitch_groups.entry(p).or_default().push(i + 1);
-                    yaw_groups.entry(y).or_default().push(i + 1);
-                }
-
-                let (r, g, b, a) = marker.colour;
-                let rgba_hex = argb_to_hex_string((a, r, g, b));
-                colour_groups.entry(rgba_hex).or_default().push(i + 1);
-
-                texture_groups.entry(marker.background_texture.clone()).or_default().push(i + 1);
+        for (i, marker) in markers.iter().enumerate() {
+            let index = i + 1;
+            let pos = marker.position;
+            let cx = (pos.x - min_x) as u32;
+            let cy = (pos.y - min_y) as u32;
+            let cz = (pos.z - min_z) as u32;
+
+            let mut text = marker.text.clone().unwrap_or_default();
+            text = text
+                .replace(":", from_utf8(MOR_COLON).unwrap_or("::"))
+                .replace(",", from_utf8(MOR_COMMA).unwrap_or(","))
+                .replace("]", from_utf8(MOR_SQUAREBRACKET).unwrap_or("]"))
+                .replace(";", from_utf8(MOR_SEMICOLON).unwrap_or(";"))
+                .replace(">", from_utf8(MOR_GREATERTHAN).unwrap_or(">"))
+                .replace("\n", r#"\\n"#);
+
+            let pos_string = format!("{:x}:{:x}:{:x}", cx, cy, cz);
+            positions.push(format!("{}:{}", pos_string, text));
+
+            let size = marker.size.to_string();
+            if size != "1" {
+                sizes.push((size, index));
             }
-        }
 
-        let sizes_str = {
-            let mut parts: Vec<String> = Vec::new();
-            for (size, idxs) in size_groups {
-                if size == "1" {continue;}
-                let idx_str = idxs.iter().map(|i| i.to_string()).collect::<Vec<_>>().join(",");
-                parts.push(format!("{}:{}", size, idx_str));
+            if let Some((p, y)) = marker.orientation {
+                pitches.push((p, index));
+                yaws.push((y, index));
             }
-            parts.join(";")
-        };
 
-        let pitches_str = {
-            let mut parts: Vec<String> = Vec::new();
-            for (p, idxs) in pitch_groups {
-                let idx_str = idxs.iter().map(|i| i.to_string()).collect::<Vec<_>>().join(",");
-                parts.push(format!("{}:{}", p, idx_str));
-            }
-            parts.join(";")
-        };
-
-        let yaws_str = {
-            let mut parts: Vec<String> = Vec::new();
-            for (y, idxs) in yaw_groups {
-                let idx_str = idxs.iter().map(|i| i.to_string()).collect::<Vec<_>>().join(",");
-                parts.push(format!("{}:{}", y, idx_str));
-            }
-            parts.join(";")
-        };
-
-        let colours_str = {
-            let mut parts: Vec<String> = Vec::new();
-            for (hex, idxs) in colour_groups {
-                let idx_str = idxs.iter().map(|i| i.to_string()).collect::<Vec<_>>().join(",");
-                parts.push(format!("{}:{}", hex, idx_str));
-            }
-            parts.join(";")
-        };
-
-        let textures_str = {
-            let mut parts: Vec<String> = Vec::new();
-            for (tex, idxs) in texture_groups {
-                let idx_str = idxs.iter().map(|i| i.to_string()).collect::<Vec<_>>().join(",");
-                parts.push(format!("{}:{}", m0r_texture_to_og(&tex), idx_str));
-            }
-            parts.join(";")
-        };
+            let (r, g, b, a) = marker.colour;
+            colours.push((argb_to_hex_string((a, r, g, b)).to_lowercase(), index));
 
-        let positions_str = positions.join(",");
+            textures.push((marker.background_texture.clone(), index));
+        }
 
         result.push_str(&format!(
             "<{}]{}]{}]{}]{}]{}]{}]{}]{}>",
             zone_id,
             timestamp,
-            mins.to_lowercase(),
-            sizes_str.to_lowercase(),
-            pitches_str.to_lowercase(),
-            yaws_str.to_lowercase(),
-            colours_str.to_lowercase(),
-            textures_str,
-            positions_str
+            mins,
+            format_index_groups(sizes, |s| s.clone()),
+            format_index_groups(pitches, |p| p.to_string()),
+            format_index_groups(yaws, |y| y.to_string()),
+            format_index_groups(colours, |c| c.clone()),
+            format_index_groups(textures, m0r_texture_to_og),
+            positions.join(",")
         ));
     }
 
     result
 }
 
+/// Markers and Breadcrumbs lines, both keyed by zone id.
+pub type MarkersAndLines = (HashMap<u16, Vec<Marker>>, HashMap<u16, Vec<BreadcrumbLine>>);
+
+/// Parses a string holding any mix of Breadcrumbs, m0r and Elms data, as
+/// pasted into the editor. Marker ids are numbered per zone, m0r first.
+pub fn parse_combined_string(s: &str, zones: Vec<Zone>) -> ParseResult<MarkersAndLines> {
+    let m0r = parse_m0r_string(s, zones.clone());
+    let elms = parse_elms_string(s, zones.clone());
+    let lines = parse_lines_string(s, zones);
+
+    let mut markers = m0r.data;
+    for (zone_id, mut zone_markers) in elms.data {
+        markers.entry(zone_id).or_default().append(&mut zone_markers);
+    }
+    for zone_markers in markers.values_mut() {
+        for (id, m) in zone_markers.iter_mut().enumerate() {
+            set_marker_id(m, id as u16);
+        }
+    }
+
+    let mut diagnostics: Vec<Diagnostic> = m0r.diagnostics.into_iter()
+        .chain(elms.diagnostics)
+        .chain(lines.diagnostics)
+        .collect();
+    diagnostics.sort_by_key(|d| (d.span.start, d.span.end));
+
+    ParseResult { data: (markers, lines.data), diagnostics }
+}
+
+/// Builds the editor's combined string: Breadcrumbs, m0r and Elms, one per line.
+pub fn build_combined_string(markers_by_zone: &HashMap<u16, Vec<Marker>>, lines_by_zone: &HashMap<u16, Vec<BreadcrumbLine>>, timestamp: u64) -> String {
+    let lines_str = lines_to_string(lines_by_zone);
+    let m0r_markers_str = build_m0r_string(markers_by_zone, timestamp);
+    let elms_markers_str = build_elms_string(markers_by_zone);
+
+    [lines_str, m0r_markers_str, elms_markers_str]
+        .into_iter()
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 impl From<u16> for ElmsIcon {
     fn from(n: u16) -> Self {
         match n {
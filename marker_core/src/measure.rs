@@ -0,0 +1,63 @@
+//! Distances, bearings and height differences between points in the world.
+//!
+//! World units are centimetres. Bearings are in degrees clockwise from north
+//! (-Z), as seen on the map.
+
+use crate::marker::Position3D;
+
+/// One end of a measurement.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Point {
+    pub x: f64,
+    pub z: f64,
+    /// Height, when the point is a marker or line end
+    pub y: Option<i32>,
+}
+
+impl From<Position3D> for Point {
+    fn from(p: Position3D) -> Self {
+        Point { x: p.x as f64, z: p.z as f64, y: Some(p.y) }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Measurement {
+    pub from: Point,
+    pub to: Point,
+}
+
+impl Measurement {
+    /// Distance across the map, ignoring height, in metres.
+    pub fn metres(&self) -> f64 {
+        (self.to.x - self.from.x).hypot(self.to.z - self.from.z) / 100.0
+    }
+
+    /// Direction from `from` to `to`, from 0 up to 360.
+    pub fn bearing(&self) -> f64 {
+        let degrees = (self.to.x - self.from.x).atan2(self.from.z - self.to.z).to_degrees();
+        degrees.rem_euclid(360.0)
+    }
+
+    /// How far `to` is above `from` in metres, if both heights are known.
+    pub fn rise(&self) -> Option<f64> {
+        Some((self.to.y? - self.from.y?) as f64 / 100.0)
+    }
+
+    /// e.g. "7.9 m, 045°, 2.1 m up"
+    pub fn label(&self) -> String {
+        let mut label = format!("{:.1} m, {:03.0}°", self.metres(), self.bearing().round() % 360.0);
+        match self.rise() {
+            Some(rise) if rise.abs() >= 0.05 => label.push_str(&format!(", {:.1} m {}", rise.abs(), if rise > 0.0 { "up" } else { "down" })),
+            Some(_) => label.push_str(", level"),
+            None => {}
+        }
+        label
+    }
+}
+
+/// A measurement between each pair of `points`, in order.
+pub fn pairwise(points: &[Position3D]) -> Vec<Measurement> {
+    points.iter().enumerate()
+        .flat_map(|(i, a)| points[i + 1..].iter().map(move |b| Measurement { from: (*a).into(), to: (*b).into() }))
+        .collect()
+}
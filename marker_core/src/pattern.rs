@@ -0,0 +1,117 @@
+//! Sets of markers laid out from a few parameters: rings, grids, points along
+//! a path and clock faces.
+//!
+//! Angles are in degrees clockwise from north (-Z), as seen on the map.
+
+use crate::marker::{ElmMarker, ElmsIcon, Marker, Position3D};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    /// `count` points evenly around a circle, the first at `start_degrees`
+    Ring { centre: (f64, f64), radius: f64, count: usize, start_degrees: f64 },
+    /// `rows` by `columns` points centred on `centre`, row by row from the
+    /// north-west corner. Rows run east-west
+    Grid { centre: (f64, f64), rows: usize, columns: usize, spacing: (f64, f64) },
+    /// `count` points evenly spaced along the path, including both ends
+    Polyline { points: Vec<(f64, f64)>, count: usize },
+    /// The twelve hours of a clock face with 12 at north, from one o'clock
+    /// round to twelve so numbered icons match the hour
+    Clock { centre: (f64, f64), radius: f64 },
+}
+
+/// The icons given to each generated marker, in order.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Labels {
+    Same(ElmsIcon),
+    /// 1 to 12, starting again at 1 after 12
+    Numbers,
+    /// a to z, starting again at a after z
+    Letters,
+}
+
+impl Labels {
+    fn icon(&self, index: usize) -> ElmsIcon {
+        match *self {
+            Labels::Same(icon) => icon,
+            Labels::Numbers => ElmsIcon::Num((index % 12) as u8 + 1),
+            Labels::Letters => ElmsIcon::Letter((b'a' + (index % 26) as u8) as char),
+        }
+    }
+}
+
+fn on_circle(centre: (f64, f64), radius: f64, degrees: f64) -> (f64, f64) {
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    (centre.0 + radius * sin, centre.1 - radius * cos)
+}
+
+impl Shape {
+    /// World X/Z of every point, in label order.
+    pub fn points(&self) -> Vec<(f64, f64)> {
+        match self {
+            Shape::Ring { centre, radius, count, start_degrees } => (0..*count)
+                .map(|i| on_circle(*centre, *radius, start_degrees + 360.0 * i as f64 / *count as f64))
+                .collect(),
+            Shape::Grid { centre, rows, columns, spacing } => {
+                let width = spacing.0 * columns.saturating_sub(1) as f64;
+                let height = spacing.1 * rows.saturating_sub(1) as f64;
+                (0..*rows)
+                    .flat_map(|row| (0..*columns).map(move |column| (row, column)))
+                    .map(|(row, column)| {
+                        (centre.0 - width / 2.0 + spacing.0 * column as f64, centre.1 - height / 2.0 + spacing.1 * row as f64)
+                    })
+                    .collect()
+            }
+            Shape::Polyline { points, count } => along(points, *count),
+            Shape::Clock { centre, radius } => (1..=12).map(|hour| on_circle(*centre, *radius, 30.0 * hour as f64)).collect(),
+        }
+    }
+}
+
+/// `count` points evenly spaced by distance along a path.
+fn along(path: &[(f64, f64)], count: usize) -> Vec<(f64, f64)> {
+    let lengths: Vec<f64> = path.windows(2).map(|w| (w[1].0 - w[0].0).hypot(w[1].1 - w[0].1)).collect();
+    let total: f64 = lengths.iter().sum();
+    match (path.first(), count) {
+        (None, _) | (_, 0) => return Vec::new(),
+        (Some(&start), 1) => return vec![start],
+        _ => {}
+    }
+    (0..count)
+        .map(|i| {
+            let mut remaining = total * i as f64 / (count - 1) as f64;
+            for (segment, length) in path.windows(2).zip(&lengths) {
+                if remaining <= *length && *length > 0.0 {
+                    let t = remaining / length;
+                    return (segment[0].0 + (segment[1].0 - segment[0].0) * t, segment[0].1 + (segment[1].1 - segment[0].1) * t);
+                }
+                remaining -= length;
+            }
+            path[path.len() - 1]
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Generator {
+    pub shape: Shape,
+    pub labels: Labels,
+}
+
+impl Generator {
+    /// Elms markers at height `y` on `map_id`, numbered on from `first_id`.
+    /// Stops early rather than run out of ids.
+    pub fn markers(&self, y: i32, map_id: u16, first_id: u16) -> Vec<Marker> {
+        self.shape.points().into_iter()
+            .zip(first_id..=u16::MAX)
+            .enumerate()
+            .map(|(i, ((x, z), id))| Marker::Elms(ElmMarker {
+                position: Position3D { x: x.round() as i32, y, z: z.round() as i32 },
+                icon: self.labels.icon(i),
+                size: 1,
+                active: true,
+                id,
+                map_id,
+            }))
+            .collect()
+    }
+}
@@ -0,0 +1,416 @@
+//! The editor's own project file: every marker and line exactly as the editor
+//! holds them, with names, notes, groups, colour tags and layers that only
+//! the editor knows about. Strings built for the game never include any of
+//! that.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::history::Snapshot;
+use crate::marker::{get_marker_id, BreadcrumbLine, Marker, MarkersAndLines};
+
+/// Version written into every project. Projects from a newer editor are
+/// refused rather than half-read.
+pub const PROJECT_VERSION: u32 = 1;
+
+/// What the editor keeps about a marker or line beyond what the game sees.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct Metadata {
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub name: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub notes: String,
+    /// The group or layer it belongs to, such as "Tank stack"
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub group: String,
+    /// Red, green and blue, to pick it out on the map
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<(u8, u8, u8)>,
+}
+
+impl Metadata {
+    pub fn is_empty(&self) -> bool {
+        *self == Metadata::default()
+    }
+}
+
+/// Metadata keyed by zone id, then by marker or line id.
+pub type MetadataByZone = HashMap<u16, HashMap<u16, Metadata>>;
+
+/// One change to the metadata of everything selected.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetadataEdit {
+    Name(String),
+    Notes(String),
+    Group(String),
+    Tag(Option<(u8, u8, u8)>),
+}
+
+impl MetadataEdit {
+    /// What the edit is called in the undo history.
+    pub fn label(&self) -> &'static str {
+        match self {
+            MetadataEdit::Name(_) => "Rename",
+            MetadataEdit::Notes(_) => "Edit notes",
+            MetadataEdit::Group(_) => "Set group",
+            MetadataEdit::Tag(_) => "Set colour tag",
+        }
+    }
+
+    fn apply(&self, meta: &mut Metadata) {
+        match self {
+            MetadataEdit::Name(name) => meta.name = name.trim().to_string(),
+            MetadataEdit::Notes(notes) => meta.notes = notes.trim_end().to_string(),
+            MetadataEdit::Group(group) => meta.group = group.trim().to_string(),
+            MetadataEdit::Tag(tag) => meta.tag = *tag,
+        }
+    }
+}
+
+/// How one layer shows on the map. Markers and lines are on the layer named
+/// by their group, or on the unnamed layer if they have none.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Layer {
+    pub name: String,
+    pub visible: bool,
+    /// Whether its markers and lines are kept from being picked or dragged
+    pub locked: bool,
+    /// From 0 to 1
+    pub opacity: f64,
+}
+
+impl Default for Layer {
+    fn default() -> Self {
+        Layer { name: String::new(), visible: true, locked: false, opacity: 1.0 }
+    }
+}
+
+impl Layer {
+    pub fn named(name: &str) -> Self {
+        Layer { name: name.to_string(), ..Layer::default() }
+    }
+
+    /// Whether what's on it can be picked or dragged on the map.
+    pub fn editable(&self) -> bool {
+        self.visible && !self.locked
+    }
+}
+
+/// One change to a layer.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LayerEdit {
+    Visible(bool),
+    Locked(bool),
+    Opacity(f64),
+    /// Moves it one place towards the top
+    Raise,
+    /// Moves it one place towards the bottom
+    Lower,
+}
+
+impl LayerEdit {
+    /// What the edit is called in the undo history.
+    pub fn label(&self) -> &'static str {
+        match self {
+            LayerEdit::Visible(true) => "Show layer",
+            LayerEdit::Visible(false) => "Hide layer",
+            LayerEdit::Locked(true) => "Lock layer",
+            LayerEdit::Locked(false) => "Unlock layer",
+            LayerEdit::Opacity(_) => "Change layer opacity",
+            LayerEdit::Raise => "Raise layer",
+            LayerEdit::Lower => "Lower layer",
+        }
+    }
+}
+
+/// The metadata of every marker and line the editor has open.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Annotations {
+    pub markers: MetadataByZone,
+    pub lines: MetadataByZone,
+    /// Layers that have been changed from how they start out, top first.
+    /// Groups not listed here go above them, showing and unlocked.
+    pub layers: Vec<Layer>,
+}
+
+fn edit_ids(by_zone: &mut MetadataByZone, zone_id: u16, ids: &HashSet<u16>, edit: &MetadataEdit) {
+    let zone = by_zone.entry(zone_id).or_default();
+    for id in ids {
+        let meta = zone.entry(*id).or_default();
+        edit.apply(meta);
+        if meta.is_empty() {
+            zone.remove(id);
+        }
+    }
+    if zone.is_empty() {
+        by_zone.remove(&zone_id);
+    }
+}
+
+/// Drops metadata for ids in `after` that weren't in `before`.
+fn forget_ids<T>(by_zone: &mut MetadataByZone, before: &HashMap<u16, Vec<T>>, after: &HashMap<u16, Vec<T>>, id: impl Fn(&T) -> u16) {
+    for (zone_id, items) in after {
+        let Some(zone) = by_zone.get_mut(zone_id) else { continue };
+        let old: HashSet<u16> = before.get(zone_id).into_iter().flatten().map(&id).collect();
+        for item in items.iter().filter(|item| !old.contains(&id(item))) {
+            zone.remove(&id(item));
+        }
+    }
+}
+
+/// The metadata in `by_zone` for the items of `before`, keyed by the ids of
+/// the matching items in `after`. An item matches one equal to it with the
+/// same id, then any equal one left over, then one with the same id that was
+/// changed.
+fn remap_ids<T: PartialEq>(by_zone: &MetadataByZone, before: &HashMap<u16, Vec<T>>, after: &HashMap<u16, Vec<T>>, id: impl Fn(&T) -> u16) -> MetadataByZone {
+    let mut result = MetadataByZone::new();
+    for (zone_id, items) in after {
+        let (Some(meta), Some(old)) = (by_zone.get(zone_id), before.get(zone_id)) else { continue };
+        let mut used = vec![false; old.len()];
+        let mut matched: Vec<Option<usize>> = vec![None; items.len()];
+        let same = |pass: usize, a: &T, b: &T| match pass {
+            0 => a == b && id(a) == id(b),
+            1 => a == b,
+            _ => id(a) == id(b),
+        };
+        for pass in 0..3 {
+            for (item, matched) in items.iter().zip(matched.iter_mut()).filter(|(_, m)| m.is_none()) {
+                *matched = (0..old.len()).find(|&j| !used[j] && same(pass, &old[j], item));
+                if let Some(j) = *matched {
+                    used[j] = true;
+                }
+            }
+        }
+        let zone: HashMap<u16, Metadata> = items.iter().zip(matched)
+            .filter_map(|(item, j)| Some((id(item), meta.get(&id(&old[j?]))?.clone())))
+            .collect();
+        if !zone.is_empty() {
+            result.insert(*zone_id, zone);
+        }
+    }
+    result
+}
+
+impl Annotations {
+    pub fn marker(&self, zone_id: u16, id: u16) -> Option<&Metadata> {
+        self.markers.get(&zone_id)?.get(&id)
+    }
+
+    pub fn line(&self, zone_id: u16, id: u16) -> Option<&Metadata> {
+        self.lines.get(&zone_id)?.get(&id)
+    }
+
+    /// Applies `edit` to the selected markers and lines in `zone_id`.
+    pub fn apply(&mut self, zone_id: u16, edit: &MetadataEdit, selected_markers: &HashSet<u16>, selected_lines: &HashSet<u16>) {
+        edit_ids(&mut self.markers, zone_id, selected_markers, edit);
+        edit_ids(&mut self.lines, zone_id, selected_lines, edit);
+    }
+
+    /// Forgets what was kept about ids that an edit has just brought into
+    /// use, so a new marker given a deleted one's id starts out blank.
+    /// Deleted items keep theirs, in case the delete is undone.
+    pub fn forget_new(&mut self, before: Snapshot, after: Snapshot) {
+        forget_ids(&mut self.markers, before.0, after.0, get_marker_id);
+        forget_ids(&mut self.lines, before.1, after.1, |l| l.id);
+    }
+
+    /// The metadata carried over to `after` from the markers and lines of
+    /// `before` they match, for when the same markers and lines come back
+    /// under new ids, as they do when a string is parsed again. Metadata for
+    /// anything gone is dropped; layers are kept as they are.
+    pub fn remap(&self, before: Snapshot, after: Snapshot) -> Annotations {
+        Annotations {
+            markers: remap_ids(&self.markers, before.0, after.0, get_marker_id),
+            lines: remap_ids(&self.lines, before.1, after.1, |l| l.id),
+            layers: self.layers.clone(),
+        }
+    }
+
+    /// Every group name in use, sorted.
+    pub fn groups(&self) -> Vec<String> {
+        let names: BTreeSet<&String> = self.markers.values().chain(self.lines.values())
+            .flat_map(|zone| zone.values())
+            .map(|meta| &meta.group)
+            .filter(|group| !group.is_empty())
+            .collect();
+        names.into_iter().cloned().collect()
+    }
+
+    /// Every layer in use, top first: one for each group, and the unnamed
+    /// layer for everything without one.
+    pub fn layers(&self) -> Vec<Layer> {
+        let groups = self.groups();
+        let listed = |name: &str| self.layers.iter().any(|l| l.name == name);
+        let mut layers: Vec<Layer> = groups.iter().filter(|g| !listed(g)).map(|g| Layer::named(g)).collect();
+        layers.extend(self.layers.iter().filter(|l| l.name.is_empty() || groups.contains(&l.name)).cloned());
+        if !listed("") {
+            layers.push(Layer::default());
+        }
+        layers
+    }
+
+    /// The layer called `name`, as it is now.
+    pub fn layer(&self, name: &str) -> Layer {
+        self.layers.iter().find(|l| l.name == name).cloned().unwrap_or_else(|| Layer::named(name))
+    }
+
+    pub fn marker_layer(&self, zone_id: u16, id: u16) -> Layer {
+        self.layer(self.marker(zone_id, id).map_or("", |meta| &meta.group))
+    }
+
+    pub fn line_layer(&self, zone_id: u16, id: u16) -> Layer {
+        self.layer(self.line(zone_id, id).map_or("", |meta| &meta.group))
+    }
+
+    /// Applies `edit` to the layer called `name`. Settings for groups no
+    /// longer in use are kept, in case the group comes back.
+    pub fn edit_layer(&mut self, name: &str, edit: &LayerEdit) {
+        let mut layers = self.layers();
+        let Some(i) = layers.iter().position(|l| l.name == name) else { return };
+        match edit {
+            LayerEdit::Visible(visible) => layers[i].visible = *visible,
+            LayerEdit::Locked(locked) => layers[i].locked = *locked,
+            LayerEdit::Opacity(opacity) => layers[i].opacity = opacity.clamp(0.0, 1.0),
+            LayerEdit::Raise if i > 0 => layers.swap(i, i - 1),
+            LayerEdit::Lower if i + 1 < layers.len() => layers.swap(i, i + 1),
+            LayerEdit::Raise | LayerEdit::Lower => {}
+        }
+        let unused: Vec<Layer> = self.layers.drain(..).filter(|old| !layers.iter().any(|l| l.name == old.name)).collect();
+        layers.extend(unused);
+        self.layers = layers;
+    }
+
+    /// Drops the markers and lines of `zone_id` on hidden layers and puts
+    /// the rest in layer order, bottom first, so the top layer draws last.
+    pub fn arrange(&self, zone_id: u16, markers: &mut Vec<Marker>, lines: &mut Vec<BreadcrumbLine>) {
+        let layers = self.layers();
+        let rank = |meta: Option<&Metadata>| {
+            let name = meta.map_or("", |meta| meta.group.as_str());
+            let i = layers.iter().position(|l| l.name == name).unwrap_or(layers.len() - 1);
+            layers[i].visible.then_some(layers.len() - i)
+        };
+        markers.retain(|m| rank(self.marker(zone_id, get_marker_id(m))).is_some());
+        markers.sort_by_key(|m| rank(self.marker(zone_id, get_marker_id(m))));
+        lines.retain(|l| rank(self.line(zone_id, l.id)).is_some());
+        lines.sort_by_key(|l| rank(self.line(zone_id, l.id)));
+    }
+
+    /// Keeps only the markers and lines of `zone_id` that can be picked or
+    /// dragged on the map.
+    pub fn retain_editable(&self, zone_id: u16, markers: &mut Vec<Marker>, lines: &mut Vec<BreadcrumbLine>) {
+        markers.retain(|m| self.marker_layer(zone_id, get_marker_id(m)).editable());
+        lines.retain(|l| self.line_layer(zone_id, l.id).editable());
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ProjectMarker {
+    pub marker: Marker,
+    #[serde(flatten)]
+    pub meta: Metadata,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ProjectLine {
+    pub line: BreadcrumbLine,
+    #[serde(flatten)]
+    pub meta: Metadata,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ProjectZone {
+    pub zone_id: u16,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub markers: Vec<ProjectMarker>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub lines: Vec<ProjectLine>,
+}
+
+/// Everything open in the editor, as saved to a project file.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Project {
+    pub version: u32,
+    pub zones: Vec<ProjectZone>,
+    /// Top first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub layers: Vec<Layer>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProjectError {
+    UnsupportedVersion(u32),
+    Json(String),
+}
+
+impl fmt::Display for ProjectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProjectError::UnsupportedVersion(v) => write!(f, "project version {} is newer than this editor understands", v),
+            ProjectError::Json(e) => write!(f, "invalid project: {}", e),
+        }
+    }
+}
+
+impl Project {
+    /// Gathers `markers`, `lines` and their metadata, zone by zone in id
+    /// order, and the layers if any have been changed.
+    pub fn new(markers: &HashMap<u16, Vec<Marker>>, lines: &HashMap<u16, Vec<BreadcrumbLine>>, annotations: &Annotations) -> Self {
+        let zone_ids: BTreeSet<u16> = markers.keys().chain(lines.keys()).copied().collect();
+        let zones = zone_ids.into_iter()
+            .map(|zone_id| ProjectZone {
+                zone_id,
+                markers: markers.get(&zone_id).into_iter().flatten()
+                    .map(|m| ProjectMarker {
+                        marker: m.clone(),
+                        meta: annotations.marker(zone_id, get_marker_id(m)).cloned().unwrap_or_default(),
+                    })
+                    .collect(),
+                lines: lines.get(&zone_id).into_iter().flatten()
+                    .map(|l| ProjectLine { line: l.clone(), meta: annotations.line(zone_id, l.id).cloned().unwrap_or_default() })
+                    .collect(),
+            })
+            .filter(|zone| !zone.markers.is_empty() || !zone.lines.is_empty())
+            .collect();
+        let layers = if annotations.layers.is_empty() { Vec::new() } else { annotations.layers() };
+        Project { version: PROJECT_VERSION, zones, layers }
+    }
+
+    /// Splits the project back into the editor's markers and lines, keyed by
+    /// zone, and their metadata.
+    pub fn into_parts(self) -> (MarkersAndLines, Annotations) {
+        let (mut markers, mut lines) = (HashMap::new(), HashMap::new());
+        let mut annotations = Annotations { layers: self.layers, ..Annotations::default() };
+        for zone in self.zones {
+            let zone_id = zone.zone_id;
+            for entry in zone.markers {
+                if !entry.meta.is_empty() {
+                    annotations.markers.entry(zone_id).or_default().insert(get_marker_id(&entry.marker), entry.meta);
+                }
+                markers.entry(zone_id).or_insert_with(Vec::new).push(entry.marker);
+            }
+            for entry in zone.lines {
+                if !entry.meta.is_empty() {
+                    annotations.lines.entry(zone_id).or_default().insert(entry.line.id, entry.meta);
+                }
+                lines.entry(zone_id).or_insert_with(Vec::new).push(entry.line);
+            }
+        }
+        ((markers, lines), annotations)
+    }
+
+    pub fn from_json(s: &str) -> Result<Self, ProjectError> {
+        let project: Project = serde_json::from_str(s).map_err(|e| ProjectError::Json(e.to_string()))?;
+        if project.version > PROJECT_VERSION {
+            return Err(ProjectError::UnsupportedVersion(project.version));
+        }
+        Ok(project)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("projects are always serialisable")
+    }
+}
@@ -0,0 +1,238 @@
+//! Drawing a map view straight to pixels, for making images without a
+//! browser. The layout matches the SVG export, but there is no font to draw
+//! with, so titles, legends and m0r text are left out.
+//!
+//! Images are read from and written to PNG with the `png` crate.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use crate::export::{elms_icon_path, icon_rect, m0r_texture_path, tile_path, view_box, ICON_FRACTION, MAP_UNITS};
+use crate::marker::{get_marker_active, get_marker_map_id, get_marker_position, BreadcrumbLine, M0rTexture, Marker};
+use crate::selection::WorldRect;
+use crate::zone::Map;
+
+const BACKGROUND: (u8, u8, u8, u8) = (0x33, 0x33, 0x33, 255);
+const LINE_OPACITY: f64 = 0.9;
+/// Most samples taken across a pixel along each side when shrinking an image.
+const MAX_SAMPLES: usize = 4;
+
+/// An image as 8-bit red, green, blue and alpha, row by row from the top.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    /// An image filled with one colour.
+    pub fn new(width: u32, height: u32, colour: (u8, u8, u8, u8)) -> Self {
+        let pixel = [colour.0, colour.1, colour.2, colour.3];
+        Self { width, height, pixels: pixel.repeat(width as usize * height as usize) }
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> (u8, u8, u8, u8) {
+        let i = (y as usize * self.width as usize + x as usize) * 4;
+        (self.pixels[i], self.pixels[i + 1], self.pixels[i + 2], self.pixels[i + 3])
+    }
+
+    /// Reads a PNG of any colour type and bit depth.
+    pub fn from_png(bytes: &[u8]) -> Result<Image, png::DecodingError> {
+        let mut decoder = png::Decoder::new(bytes);
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info()?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let frame = reader.next_frame(&mut buffer)?;
+        buffer.truncate(frame.buffer_size());
+        let pixels = match frame.color_type {
+            png::ColorType::Rgba => buffer,
+            png::ColorType::Rgb => buffer.chunks_exact(3).flat_map(|p| [p[0], p[1], p[2], 255]).collect(),
+            png::ColorType::GrayscaleAlpha => buffer.chunks_exact(2).flat_map(|p| [p[0], p[0], p[0], p[1]]).collect(),
+            // Palettes are expanded to RGB or RGBA by the transformations.
+            png::ColorType::Grayscale | png::ColorType::Indexed => buffer.iter().flat_map(|&v| [v, v, v, 255]).collect(),
+        };
+        Ok(Image { width: frame.width, height: frame.height, pixels })
+    }
+
+    /// The image as an RGBA PNG.
+    pub fn to_png(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut encoder = png::Encoder::new(&mut out, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&self.pixels))
+            .expect("writing to memory only fails if the pixels don't match the size");
+        out
+    }
+}
+
+/// The path under `static/` of a bitmap for `texture`. The basic shapes are
+/// drawn from SVG paths elsewhere, but each has a PNG beside it.
+pub fn m0r_bitmap_path(texture: &M0rTexture) -> String {
+    let path = m0r_texture_path(texture);
+    match path.strip_suffix(".svg") {
+        Some(stem) => format!("{}.png", stem),
+        None => path,
+    }
+}
+
+/// Lays `colour` over the pixel at `x, y` with `alpha` from 0 to 1.
+fn blend(image: &mut Image, x: i64, y: i64, colour: (f64, f64, f64), alpha: f64) {
+    if alpha <= 0.0 || x < 0 || y < 0 || x >= image.width as i64 || y >= image.height as i64 {
+        return;
+    }
+    let i = (y as usize * image.width as usize + x as usize) * 4;
+    let below = image.pixels[i + 3] as f64 / 255.0 * (1.0 - alpha);
+    let out = alpha + below;
+    for (c, value) in [colour.0, colour.1, colour.2].into_iter().enumerate() {
+        image.pixels[i + c] = ((value * alpha + image.pixels[i + c] as f64 * below) / out).round() as u8;
+    }
+    image.pixels[i + 3] = (out * 255.0).round() as u8;
+}
+
+/// The colour of `source` at `u, v`, from 0 to 1 across it, blended between
+/// the nearest four pixels. Colour is weighted by alpha, so clear pixels
+/// don't darken the edges of what they border.
+fn sample(source: &Image, u: f64, v: f64) -> [f64; 4] {
+    let (x, y) = (u * source.width as f64 - 0.5, v * source.height as f64 - 0.5);
+    let (fx, fy) = (x - x.floor(), y - y.floor());
+    let column = |x: f64| x.clamp(0.0, source.width as f64 - 1.0) as u32;
+    let row = |y: f64| y.clamp(0.0, source.height as f64 - 1.0) as u32;
+    let mut sum = [0.0; 4];
+    for (px, py, weight) in [
+        (x.floor(), y.floor(), (1.0 - fx) * (1.0 - fy)),
+        (x.floor() + 1.0, y.floor(), fx * (1.0 - fy)),
+        (x.floor(), y.floor() + 1.0, (1.0 - fx) * fy),
+        (x.floor() + 1.0, y.floor() + 1.0, fx * fy),
+    ] {
+        let (r, g, b, a) = source.pixel(column(px), row(py));
+        let a = a as f64 / 255.0 * weight;
+        sum[0] += r as f64 * a;
+        sum[1] += g as f64 * a;
+        sum[2] += b as f64 * a;
+        sum[3] += a;
+    }
+    sum
+}
+
+/// Draws `source` stretched over `left, top, width, height`, filled with
+/// `tint` where given, as the map does with m0r textures.
+fn draw_image(image: &mut Image, source: &Image, (left, top, width, height): (f64, f64, f64, f64), tint: Option<(u8, u8, u8, u8)>) {
+    if width <= 0.0 || height <= 0.0 {
+        return;
+    }
+    let shrink = (source.width as f64 / width).max(source.height as f64 / height);
+    let samples = (shrink.ceil() as usize).clamp(1, MAX_SAMPLES);
+    let step = 1.0 / samples as f64;
+    let x_range = (left.floor().max(0.0) as i64)..((left + width).ceil().min(image.width as f64) as i64);
+    let y_range = (top.floor().max(0.0) as i64)..((top + height).ceil().min(image.height as f64) as i64);
+
+    for y in y_range {
+        for x in x_range.clone() {
+            // Colour weighted by alpha, and alpha, summed over the samples.
+            let mut sum = [0.0; 4];
+            for j in 0..samples {
+                for i in 0..samples {
+                    let u = (x as f64 + (i as f64 + 0.5) * step - left) / width;
+                    let v = (y as f64 + (j as f64 + 0.5) * step - top) / height;
+                    if !(0.0..1.0).contains(&u) || !(0.0..1.0).contains(&v) {
+                        continue;
+                    }
+                    for (total, value) in sum.iter_mut().zip(sample(source, u, v)) {
+                        *total += value;
+                    }
+                }
+            }
+            if sum[3] <= 0.0 {
+                continue;
+            }
+            let alpha = sum[3] / (samples * samples) as f64;
+            match tint {
+                Some((r, g, b, a)) => blend(image, x, y, (r as f64, g as f64, b as f64), alpha * a as f64 / 255.0),
+                None => blend(image, x, y, (sum[0] / sum[3], sum[1] / sum[3], sum[2] / sum[3]), alpha),
+            }
+        }
+    }
+}
+
+/// Draws a line from `a` to `b` with round ends, smoothing its edges.
+fn draw_line(image: &mut Image, a: (f64, f64), b: (f64, f64), width: f64, colour: (u8, u8, u8), alpha: f64) {
+    let reach = width / 2.0 + 1.0;
+    let (dx, dz) = (b.0 - a.0, b.1 - a.1);
+    let length = dx * dx + dz * dz;
+    let colour = (colour.0 as f64, colour.1 as f64, colour.2 as f64);
+    for y in (a.1.min(b.1) - reach).floor() as i64..=(a.1.max(b.1) + reach).ceil() as i64 {
+        for x in (a.0.min(b.0) - reach).floor() as i64..=(a.0.max(b.0) + reach).ceil() as i64 {
+            let (px, pz) = (x as f64 + 0.5, y as f64 + 0.5);
+            let t = if length > 0.0 { (((px - a.0) * dx + (pz - a.1) * dz) / length).clamp(0.0, 1.0) } else { 0.0 };
+            let distance = (px - a.0 - t * dx).hypot(pz - a.1 - t * dz);
+            let coverage = (width / 2.0 + 0.5 - distance).clamp(0.0, 1.0);
+            blend(image, x, y, colour, alpha * coverage);
+        }
+    }
+}
+
+/// Renders `map` with the enabled `markers` and `lines` on it, `size` pixels
+/// across its longer side, showing only `crop` if given. `load` reads the
+/// image at a path under `static/`; each is asked for once, and anything it
+/// can't load is left out.
+pub fn render(map: &Map, markers: &[Marker], lines: &[BreadcrumbLine], size: u32, crop: Option<WorldRect>, load: &dyn Fn(&str) -> Option<Image>) -> Image {
+    let images: RefCell<HashMap<String, Option<Image>>> = RefCell::default();
+    let with_image = |path: String, draw: &mut dyn FnMut(&Image)| {
+        let mut images = images.borrow_mut();
+        if let Some(image) = images.entry(path).or_insert_with_key(|path| load(path)) {
+            draw(image);
+        }
+    };
+
+    let (x0, z0, x1, z1) = view_box(map, crop);
+    let (view_w, view_h) = (x1 - x0, z1 - z0);
+    let pixels = size.max(1) as f64 / view_w.max(view_h);
+    let icon = view_w.max(view_h) * ICON_FRACTION;
+    // Map units to pixels in the image.
+    let to_image = |(x, z): (f64, f64)| ((x - x0) * pixels, (z - z0) * pixels);
+    let project = |x: f64, z: f64| {
+        let (nx, nz) = map.scale_data.normalise(x, z);
+        (nx * MAP_UNITS, nz * MAP_UNITS)
+    };
+
+    let (width, height) = ((view_w * pixels).round().max(1.0) as u32, (view_h * pixels).round().max(1.0) as u32);
+    let mut image = Image::new(width, height, BACKGROUND);
+
+    let count = map.count.max(1) as usize;
+    let tile_size = MAP_UNITS / count as f64;
+    for (i, tile) in map.tiles.iter().enumerate() {
+        let (tx, tz) = ((i % count) as f64 * tile_size, (i / count) as f64 * tile_size);
+        if tx >= x1 || tz >= z1 || tx + tile_size <= x0 || tz + tile_size <= z0 {
+            continue;
+        }
+        // Whole pixels, so neighbouring tiles meet without a seam.
+        let (left, top) = to_image((tx, tz));
+        let (right, bottom) = to_image((tx + tile_size, tz + tile_size));
+        let (left, top, right, bottom) = (left.round(), top.round(), right.round(), bottom.round());
+        with_image(tile_path(tile), &mut |tile| draw_image(&mut image, tile, (left, top, right - left, bottom - top), None));
+    }
+
+    for line in lines.iter().filter(|l| l.active && l.map_id == map.map_id) {
+        let from = to_image(project(line.position1.x as f64, line.position1.z as f64));
+        let to = to_image(project(line.position2.x as f64, line.position2.z as f64));
+        let (r, g, b, _) = line.colour;
+        draw_line(&mut image, from, to, (icon * 0.06 * pixels).max(1.0), (r, g, b), LINE_OPACITY);
+    }
+
+    for marker in markers.iter().filter(|m| get_marker_active(m) && get_marker_map_id(m) == map.map_id) {
+        let p = get_marker_position(marker);
+        let (left, top, side) = icon_rect(marker, project(p.x as f64, p.z as f64), icon);
+        let (left, top) = to_image((left, top));
+        let rect = (left, top, side * pixels, side * pixels);
+        match marker {
+            Marker::Elms(m) => with_image(elms_icon_path(m.icon), &mut |icon| draw_image(&mut image, icon, rect, None)),
+            Marker::M0r(m) => with_image(m0r_bitmap_path(&m.background_texture), &mut |texture| draw_image(&mut image, texture, rect, Some(m.colour))),
+        }
+    }
+
+    image
+}
@@ -0,0 +1,323 @@
+//! Reading and writing the SavedVariables files Elms Markers and M0R Markers
+//! keep under `SavedVariables/`, so a whole account's markers can be edited
+//! at once rather than one zone's string at a time. The markers sit in a
+//! table of zones, inside the usual per-account layout:
+//!
+//! ```lua
+//! ElmsMarkers_SavedVariables =
+//! {
+//!     ["Default"] =
+//!     {
+//!         ["@Account"] =
+//!         {
+//!             ["$AccountWide"] =
+//!             {
+//!                 ["positions"] =
+//!                 {
+//!                     [1263] =
+//!                     {
+//!                         [1] = { ["x"] = 92500, ["y"] = 35000, ["z"] = 95000, ["iconKey"] = 2 },
+//!                     },
+//!                 },
+//!             },
+//!         },
+//!     },
+//! }
+//! M0RMarkers_SavedVariables = { ... ["zones"] = { [1263] = "<1263]...>" } ... }
+//! ```
+//!
+//! Only the first account's `$AccountWide` zone table is read and written.
+//! Addons can also keep markers per character, or a file can hold several
+//! accounts, but those tables would each need their own markers kept apart,
+//! so they are reported and left as they are.
+//!
+//! The layout of Elms Markers positions hasn't been checked against a file
+//! the addon wrote, so each is read either by name, with the icon under
+//! `iconKey` or `icon`, or as a list in x, y, z, icon order, and written by
+//! name with `iconKey`. OdySupportIcons keeps its own SavedVariables and isn't
+//! read or written here.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Range;
+
+use crate::diagnostic::{Diagnostic, ParseResult};
+use crate::lua::{self, LuaError, Table, Value};
+use crate::marker::{build_m0r_string, parse_elms_string, parse_m0r_string, set_marker_id, Marker};
+use crate::zone::Zone;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Addon {
+    Elms,
+    M0r,
+}
+
+pub const ALL_ADDONS: &[Addon] = &[Addon::Elms, Addon::M0r];
+
+impl Addon {
+    /// The variable the addon's data is assigned to.
+    pub fn variable(self) -> &'static str {
+        match self {
+            Addon::Elms => "ElmsMarkers_SavedVariables",
+            Addon::M0r => "M0RMarkers_SavedVariables",
+        }
+    }
+
+    /// The addon's file under `SavedVariables/`.
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Addon::Elms => "ElmsMarkers.lua",
+            Addon::M0r => "M0RMarkers.lua",
+        }
+    }
+
+    /// The name of the table of zones holding the markers.
+    fn zones_key(self) -> &'static str {
+        match self {
+            Addon::Elms => "positions",
+            Addon::M0r => "zones",
+        }
+    }
+
+    /// Whether `marker` is one this addon draws.
+    pub fn holds(self, marker: &Marker) -> bool {
+        matches!((self, marker), (Addon::Elms, Marker::Elms(_)) | (Addon::M0r, Marker::M0r(_)))
+    }
+
+    fn of(variable: &str) -> Option<Addon> {
+        ALL_ADDONS.iter().copied().find(|addon| addon.variable() == variable)
+    }
+}
+
+impl fmt::Display for Addon {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Addon::Elms => write!(f, "Elms Markers"),
+            Addon::M0r => write!(f, "M0R Markers"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SavedVariablesError {
+    Lua(LuaError),
+    NoAddon,
+    NoZones(Addon),
+}
+
+impl fmt::Display for SavedVariablesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SavedVariablesError::Lua(e) => write!(f, "invalid SavedVariables file: {}", e),
+            SavedVariablesError::NoAddon => write!(f, "file has no Elms Markers or M0R Markers data"),
+            SavedVariablesError::NoZones(addon) => write!(f, "{} data in the file has no account-wide '{}' table", addon, addon.zones_key()),
+        }
+    }
+}
+
+/// Every table under the key `key` in `table`, however deep, and whether
+/// it's inside an `$AccountWide` table.
+fn find_tables<'a>(table: &'a Table, key: &str, account_wide: bool, found: &mut Vec<(&'a Table, bool)>) {
+    for entry in &table.entries {
+        let Some(inner) = entry.value.as_table() else { continue };
+        if entry.key.as_str() == Some(key) {
+            found.push((inner, account_wide));
+        } else {
+            find_tables(inner, key, account_wide || entry.key.as_str() == Some("$AccountWide"), found);
+        }
+    }
+}
+
+/// An addon's zone tables in a file: the account-wide one that's read and
+/// written, if there is one, and the others, which are left alone.
+struct ZoneTables<'a> {
+    addon: Addon,
+    used: Option<&'a Table>,
+    skipped: Vec<&'a Table>,
+}
+
+/// Each addon's data in the file, with its zone tables.
+fn zone_tables(assignments: &[lua::Assignment]) -> Vec<ZoneTables<'_>> {
+    assignments.iter()
+        .filter_map(|assignment| {
+            let addon = Addon::of(&assignment.name)?;
+            let mut found = Vec::new();
+            if let Some(root) = assignment.value.as_table() {
+                find_tables(root, addon.zones_key(), false, &mut found);
+            }
+            let used = found.iter().position(|(_, account_wide)| *account_wide).map(|i| found.remove(i).0);
+            Some(ZoneTables { addon, used, skipped: found.into_iter().map(|(table, _)| table).collect() })
+        })
+        .collect()
+}
+
+/// Which addons' data `text` holds. Empty if it isn't valid Lua.
+pub fn addons_in(text: &str) -> Vec<Addon> {
+    lua::parse(text).map_or_else(|_| Vec::new(), |assignments| zone_tables(&assignments).into_iter().map(|found| found.addon).collect())
+}
+
+/// x, y, z and icon of an Elms Markers position, kept either by name or as
+/// a list in that order.
+fn elms_position(entry: &Table) -> Option<(f64, f64, f64, f64)> {
+    let number = |key: &str, index: i64| entry.get(key).or_else(|| entry.index(index))?.as_number();
+    let icon = entry.get("iconKey").or_else(|| entry.get("icon")).or_else(|| entry.index(4))?.as_number()?;
+    Some((number("x", 1)?, number("y", 2)?, number("z", 3)?, icon))
+}
+
+fn zone_id(key: &Value) -> Option<i64> {
+    key.as_number().filter(|n| n.fract() == 0.0 && *n >= 0.0).map(|n| n as i64)
+}
+
+fn error_span(error: &LuaError, text: &str) -> Range<usize> {
+    match error {
+        LuaError::Truncated => text.len()..text.len(),
+        LuaError::Unexpected(at, found, _) => *at..*at + found.len(),
+        LuaError::BadNumber(at, number) => *at..*at + number.len(),
+    }
+}
+
+/// Reads the account-wide Elms Markers and M0R Markers markers in a
+/// SavedVariables file, with a warning for each zone table left out. Marker ids are numbered per zone, M0R first, as for pasted strings, and
+/// diagnostics point at the entry in the file they came from.
+pub fn parse_saved_variables(text: &str, zones: Vec<Zone>) -> ParseResult<HashMap<u16, Vec<Marker>>> {
+    let assignments = match lua::parse(text) {
+        Ok(assignments) => assignments,
+        Err(e) => return ParseResult { data: HashMap::new(), diagnostics: vec![Diagnostic::error(error_span(&e, text), e.to_string())] },
+    };
+    let mut markers: HashMap<u16, Vec<Marker>> = HashMap::new();
+    let mut diagnostics = Vec::new();
+    // Elms positions are read as one string, remembering where each came from.
+    let mut elms = String::new();
+    let mut elms_sources: Vec<(Range<usize>, Range<usize>)> = Vec::new();
+
+    for ZoneTables { addon, used, skipped } in zone_tables(&assignments) {
+        for table in skipped {
+            diagnostics.push(Diagnostic::warning(table.span.clone(), format!("only account-wide {} markers are read, so these are left as they are", addon)));
+        }
+        for zone in used.into_iter().flat_map(|table| &table.entries) {
+            let Some(zone_id) = zone_id(&zone.key) else {
+                diagnostics.push(Diagnostic::error(zone.span.clone(), format!("{} zone key isn't a zone id", addon)));
+                continue;
+            };
+            match (addon, &zone.value) {
+                (Addon::Elms, Value::Table(positions)) => {
+                    for entry in &positions.entries {
+                        match entry.value.as_table().and_then(elms_position) {
+                            Some((x, y, z, icon)) => {
+                                let start = elms.len();
+                                elms.push_str(&format!("/{}//{},{},{},{}/", zone_id, x.round(), y.round(), z.round(), icon.max(0.0).round()));
+                                elms_sources.push((start..elms.len(), entry.span.clone()));
+                            }
+                            None => diagnostics.push(Diagnostic::error(entry.span.clone(), "not an Elms Markers position")),
+                        }
+                    }
+                }
+                (Addon::M0r, Value::String(string)) => {
+                    let parsed = parse_m0r_string(string, zones.clone());
+                    diagnostics.extend(parsed.diagnostics.into_iter().map(|d| Diagnostic { span: zone.span.clone(), ..d }));
+                    for (zone_id, mut zone_markers) in parsed.data {
+                        markers.entry(zone_id).or_default().append(&mut zone_markers);
+                    }
+                }
+                (Addon::Elms, _) => diagnostics.push(Diagnostic::error(zone.span.clone(), format!("zone {} isn't a table of positions", zone_id))),
+                (Addon::M0r, _) => diagnostics.push(Diagnostic::error(zone.span.clone(), format!("zone {} isn't an M0R Markers string", zone_id))),
+            }
+        }
+    }
+
+    let parsed = parse_elms_string(&elms, zones);
+    for d in parsed.diagnostics {
+        let span = elms_sources.iter().find(|(range, _)| range.contains(&d.span.start)).map_or(0..0, |(_, span)| span.clone());
+        diagnostics.push(Diagnostic { span, ..d });
+    }
+    for (zone_id, mut zone_markers) in parsed.data {
+        markers.entry(zone_id).or_default().append(&mut zone_markers);
+    }
+    for zone_markers in markers.values_mut() {
+        for (id, m) in zone_markers.iter_mut().enumerate() {
+            set_marker_id(m, id as u16);
+        }
+    }
+    diagnostics.sort_by_key(|d| (d.span.start, d.span.end));
+
+    ParseResult { data: markers, diagnostics }
+}
+
+/// The zone table `addon` keeps for the enabled markers it draws, in zone
+/// order. `timestamp` is written into each M0R string.
+fn zones_table(addon: Addon, markers: &HashMap<u16, Vec<Marker>>, timestamp: u64) -> Table {
+    let mut zone_ids: Vec<u16> = markers.keys().copied().collect();
+    zone_ids.sort();
+    let pairs = zone_ids.into_iter()
+        .filter_map(|zone_id| {
+            let zone_markers: Vec<Marker> = markers[&zone_id].iter().filter(|m| addon.holds(m)).cloned().collect();
+            let value = match addon {
+                Addon::Elms => {
+                    let positions: Vec<(Value, Value)> = zone_markers.iter()
+                        .filter_map(|m| match m {
+                            Marker::Elms(m) if m.active => Some(m),
+                            _ => None,
+                        })
+                        .enumerate()
+                        .map(|(i, m)| {
+                            let position = vec![
+                                ("x".into(), (m.position.x as i64).into()),
+                                ("y".into(), (m.position.y as i64).into()),
+                                ("z".into(), (m.position.z as i64).into()),
+                                ("iconKey".into(), (u16::from(&m.icon) as i64).into()),
+                            ];
+                            ((i as i64 + 1).into(), position.into())
+                        })
+                        .collect();
+                    (!positions.is_empty()).then(|| positions.into())
+                }
+                Addon::M0r => {
+                    let string = build_m0r_string(&HashMap::from([(zone_id, zone_markers)]), timestamp);
+                    (!string.is_empty()).then(|| string.as_str().into())
+                }
+            };
+            Some(((zone_id as i64).into(), value?))
+        })
+        .collect();
+    Table::from_pairs(pairs)
+}
+
+/// `text` with the account-wide zone table of each addon it holds data for
+/// rewritten to hold `markers`. Everything else in the file, including any
+/// per-character zone tables, is left as it was.
+pub fn patch_saved_variables(text: &str, markers: &HashMap<u16, Vec<Marker>>, timestamp: u64) -> Result<String, SavedVariablesError> {
+    let assignments = lua::parse(text).map_err(SavedVariablesError::Lua)?;
+    let found = zone_tables(&assignments);
+    if found.is_empty() {
+        return Err(SavedVariablesError::NoAddon);
+    }
+    let mut edits: Vec<(Range<usize>, String)> = Vec::new();
+    for ZoneTables { addon, used, .. } in found {
+        let Some(table) = used else {
+            return Err(SavedVariablesError::NoZones(addon));
+        };
+        let zones = zones_table(addon, markers, timestamp);
+        let line_start = text[..table.span.start].rfind('\n').map_or(0, |i| i + 1);
+        let line = &text[line_start..table.span.start];
+        let indent = &line[..line.len() - line.trim_start().len()];
+        edits.push((table.span.start + 1..table.span.end - 1, lua::table_body(&zones, indent)));
+    }
+
+    let mut patched = text.to_string();
+    edits.sort_by_key(|(span, _)| std::cmp::Reverse(span.start));
+    for (span, body) in edits {
+        patched.replace_range(span, &body);
+    }
+    Ok(patched)
+}
+
+/// A new SavedVariables file for `addon` holding `markers`, shared by every
+/// character on `account` (such as "@name").
+pub fn build_saved_variables(addon: Addon, account: &str, markers: &HashMap<u16, Vec<Marker>>, timestamp: u64) -> String {
+    let data: Value = vec![
+        (addon.zones_key().into(), Value::Table(zones_table(addon, markers, timestamp))),
+        ("version".into(), 1.into()),
+    ].into();
+    let accounts: Value = vec![(account.into(), vec![("$AccountWide".into(), data)].into())].into();
+    lua::write(addon.variable(), &vec![("Default".into(), accounts)].into())
+}
@@ -0,0 +1,152 @@
+use std::fmt;
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::project::{Project, PROJECT_VERSION};
+
+/// Version written into every bundle. Bundles from a newer editor are refused
+/// rather than half-read.
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// A named layout, stored as the editor's combined string and, since
+/// projects were added, as a project keeping names, notes and layers too.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SavedLayout {
+    pub name: String,
+    pub data: String,
+    /// Missing from layouts saved before projects were kept
+    #[serde(default, deserialize_with = "readable_project", skip_serializing_if = "Option::is_none")]
+    pub project: Option<Project>,
+    /// Unix time in seconds
+    pub saved_at: u64,
+}
+
+/// The working string and project, written after every change so they survive
+/// a reload or crash.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Autosave {
+    pub data: String,
+    #[serde(default, deserialize_with = "readable_project", skip_serializing_if = "Option::is_none")]
+    pub project: Option<Project>,
+    pub saved_at: u64,
+}
+
+/// Every named layout. This is both what is kept in storage and what gets exported.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SaveBundle {
+    pub version: u32,
+    pub saves: Vec<SavedLayout>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SaveError {
+    EmptyName,
+    NotFound(String),
+    NameTaken(String),
+    UnsupportedVersion(u32),
+    Json(String),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::EmptyName => write!(f, "layouts need a name"),
+            SaveError::NotFound(name) => write!(f, "no layout called '{}'", name),
+            SaveError::NameTaken(name) => write!(f, "a layout called '{}' already exists", name),
+            SaveError::UnsupportedVersion(v) => write!(f, "bundle version {} is newer than this editor understands", v),
+            SaveError::Json(e) => write!(f, "invalid bundle: {}", e),
+        }
+    }
+}
+
+/// Reads a stored project, or nothing if it's missing, broken or from a newer
+/// editor, so `data` is loaded instead and the rest of the entry still reads.
+fn readable_project<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Project>, D::Error> {
+    let value = Option::<serde_json::Value>::deserialize(deserializer)?;
+    Ok(value
+        .and_then(|v| serde_json::from_value::<Project>(v).ok())
+        .filter(|project| project.version <= PROJECT_VERSION))
+}
+
+impl Autosave {
+    pub fn from_json(s: &str) -> Option<Self> {
+        serde_json::from_str(s).ok()
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("autosave is always serialisable")
+    }
+}
+
+impl Default for SaveBundle {
+    fn default() -> Self {
+        SaveBundle { version: BUNDLE_VERSION, saves: Vec::new() }
+    }
+}
+
+impl SaveBundle {
+    pub fn from_json(s: &str) -> Result<Self, SaveError> {
+        let bundle: SaveBundle = serde_json::from_str(s).map_err(|e| SaveError::Json(e.to_string()))?;
+        if bundle.version > BUNDLE_VERSION {
+            return Err(SaveError::UnsupportedVersion(bundle.version));
+        }
+        Ok(bundle)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("bundle is always serialisable")
+    }
+
+    pub fn get(&self, name: &str) -> Option<&SavedLayout> {
+        self.saves.iter().find(|s| s.name == name)
+    }
+
+    /// Saves `data` and `project` under `name`, replacing any layout already
+    /// called that.
+    pub fn save(&mut self, name: &str, data: &str, project: Option<&Project>, now: u64) -> Result<(), SaveError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(SaveError::EmptyName);
+        }
+        let layout = SavedLayout { name: name.to_string(), data: data.to_string(), project: project.cloned(), saved_at: now };
+        match self.saves.iter_mut().find(|s| s.name == name) {
+            Some(existing) => *existing = layout,
+            None => self.saves.push(layout),
+        }
+        Ok(())
+    }
+
+    pub fn rename(&mut self, from: &str, to: &str) -> Result<(), SaveError> {
+        let to = to.trim();
+        if to.is_empty() {
+            return Err(SaveError::EmptyName);
+        }
+        if to != from && self.get(to).is_some() {
+            return Err(SaveError::NameTaken(to.to_string()));
+        }
+        let layout = self.saves.iter_mut().find(|s| s.name == from).ok_or_else(|| SaveError::NotFound(from.to_string()))?;
+        layout.name = to.to_string();
+        Ok(())
+    }
+
+    /// Copies a layout to "name (copy)", "name (copy 2)" and so on, returning the new name.
+    pub fn duplicate(&mut self, name: &str, now: u64) -> Result<String, SaveError> {
+        let original = self.get(name).ok_or_else(|| SaveError::NotFound(name.to_string()))?;
+        let (data, project) = (original.data.clone(), original.project.clone());
+        let new_name = std::iter::once(format!("{} (copy)", name))
+            .chain((2..).map(|n| format!("{} (copy {})", name, n)))
+            .find(|candidate| self.get(candidate).is_none())
+            .expect("some copy name is free");
+        self.saves.push(SavedLayout { name: new_name.clone(), data, project, saved_at: now });
+        Ok(new_name)
+    }
+
+    pub fn delete(&mut self, name: &str) -> Result<(), SaveError> {
+        let before = self.saves.len();
+        self.saves.retain(|s| s.name != name);
+        if self.saves.len() == before {
+            return Err(SaveError::NotFound(name.to_string()));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,111 @@
+//! Picking out groups of markers and lines, and editing them all at once.
+
+use std::collections::HashSet;
+
+use crate::marker::{get_marker_active, get_marker_id, get_marker_map_id, get_marker_position, set_marker_active, BreadcrumbLine, ElmsIcon, M0rTexture, Marker};
+
+/// An axis-aligned rectangle in world X/Z.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WorldRect {
+    pub min_x: f64,
+    pub max_x: f64,
+    pub min_z: f64,
+    pub max_z: f64,
+}
+
+impl WorldRect {
+    /// The rectangle between two opposite corners, in either order.
+    pub fn from_corners(a: (f64, f64), b: (f64, f64)) -> Self {
+        Self { min_x: a.0.min(b.0), max_x: a.0.max(b.0), min_z: a.1.min(b.1), max_z: a.1.max(b.1) }
+    }
+
+    pub fn contains(&self, x: f64, z: f64) -> bool {
+        x >= self.min_x && x <= self.max_x && z >= self.min_z && z <= self.max_z
+    }
+}
+
+/// Ids of the enabled markers on `map_id` inside `rect`. Disabled markers
+/// aren't drawn, so a box can't pick them up.
+pub fn markers_in(markers: &[Marker], map_id: u16, rect: &WorldRect) -> HashSet<u16> {
+    markers.iter()
+        .filter(|m| {
+            let p = get_marker_position(m);
+            get_marker_active(m) && get_marker_map_id(m) == map_id && rect.contains(p.x as f64, p.z as f64)
+        })
+        .map(get_marker_id)
+        .collect()
+}
+
+/// Ids of the enabled lines on `map_id` with both ends inside `rect`.
+pub fn lines_in(lines: &[BreadcrumbLine], map_id: u16, rect: &WorldRect) -> HashSet<u16> {
+    lines.iter()
+        .filter(|l| {
+            l.active
+                && l.map_id == map_id
+                && rect.contains(l.position1.x as f64, l.position1.z as f64)
+                && rect.contains(l.position2.x as f64, l.position2.z as f64)
+        })
+        .map(|l| l.id)
+        .collect()
+}
+
+/// One change applied to every selected marker and line it makes sense for.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BulkEdit {
+    Delete,
+    SetActive(bool),
+    /// Only changes Elms markers
+    ElmsIcon(ElmsIcon),
+    /// Only changes m0r markers
+    M0rTexture(M0rTexture),
+    /// Red, green and blue for m0r markers and lines. Each keeps its own alpha
+    Colour((u8, u8, u8)),
+    /// Only changes m0r markers; Elms markers are always drawn at one size
+    Size(f32),
+}
+
+impl BulkEdit {
+    /// Applies the edit to the markers and lines whose ids are selected.
+    /// Returns how many of them it applied to, so callers can say which were
+    /// left alone.
+    pub fn apply(
+        &self,
+        markers: &mut Vec<Marker>,
+        lines: &mut Vec<BreadcrumbLine>,
+        selected_markers: &HashSet<u16>,
+        selected_lines: &HashSet<u16>,
+    ) -> usize {
+        if *self == BulkEdit::Delete {
+            let before = markers.len() + lines.len();
+            markers.retain(|m| !selected_markers.contains(&get_marker_id(m)));
+            lines.retain(|l| !selected_lines.contains(&l.id));
+            return before - markers.len() - lines.len();
+        }
+
+        let mut applied = 0;
+        for marker in markers.iter_mut().filter(|m| selected_markers.contains(&get_marker_id(m))) {
+            if let BulkEdit::SetActive(active) = self {
+                set_marker_active(marker, *active);
+                applied += 1;
+                continue;
+            }
+            let changed = match (self, marker) {
+                (BulkEdit::ElmsIcon(icon), Marker::Elms(m)) => { m.icon = *icon; true }
+                (BulkEdit::M0rTexture(texture), Marker::M0r(m)) => { m.background_texture = texture.clone(); true }
+                (BulkEdit::Colour((r, g, b)), Marker::M0r(m)) => { m.colour = (*r, *g, *b, m.colour.3); true }
+                (BulkEdit::Size(size), Marker::M0r(m)) => { m.size = *size; true }
+                _ => false,
+            };
+            applied += changed as usize;
+        }
+        for line in lines.iter_mut().filter(|l| selected_lines.contains(&l.id)) {
+            let changed = match self {
+                BulkEdit::SetActive(active) => { line.active = *active; true }
+                BulkEdit::Colour((r, g, b)) => { line.colour = (*r, *g, *b, line.colour.3); true }
+                _ => false,
+            };
+            applied += changed as usize;
+        }
+        applied
+    }
+}
@@ -0,0 +1,59 @@
+use std::fmt;
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use serde::{Deserialize, Serialize};
+
+/// Prefix of every share fragment. Bump it if the payload changes shape.
+const SHARE_PREFIX: &str = "v1.";
+
+/// Everything needed to reopen a layout exactly as it was shared.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ShareState {
+    /// The editor's combined string
+    #[serde(rename = "d")]
+    pub data: String,
+    #[serde(rename = "z")]
+    pub zone_id: u16,
+    #[serde(rename = "m")]
+    pub map_id: u16,
+    pub zoom: f64,
+    /// Pan as a fraction of the canvas size, so it holds on any screen.
+    pub pan: (f64, f64),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShareError {
+    UnknownVersion,
+    Base64,
+    Compression,
+    Payload(String),
+}
+
+impl fmt::Display for ShareError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShareError::UnknownVersion => write!(f, "share link is from a different version of the editor"),
+            ShareError::Base64 => write!(f, "share link is not valid base64"),
+            ShareError::Compression => write!(f, "share link data is corrupt"),
+            ShareError::Payload(e) => write!(f, "share link payload is invalid: {}", e),
+        }
+    }
+}
+
+/// Deflates and base64url-encodes a layout for a URL fragment (without the `#`).
+pub fn encode_share(state: &ShareState) -> String {
+    let json = serde_json::to_vec(state).expect("share state is always serialisable");
+    let compressed = miniz_oxide::deflate::compress_to_vec(&json, 9);
+    format!("{}{}", SHARE_PREFIX, URL_SAFE_NO_PAD.encode(compressed))
+}
+
+/// Reads a fragment written by `encode_share`. A leading `#` is ignored.
+pub fn decode_share(fragment: &str) -> Result<ShareState, ShareError> {
+    let fragment = fragment.strip_prefix('#').unwrap_or(fragment);
+    let encoded = fragment.strip_prefix(SHARE_PREFIX).ok_or(ShareError::UnknownVersion)?;
+    let compressed = URL_SAFE_NO_PAD.decode(encoded).map_err(|_| ShareError::Base64)?;
+    // Marker strings are small; anything that inflates past this is not ours.
+    let json = miniz_oxide::inflate::decompress_to_vec_with_limit(&compressed, 4 << 20).map_err(|_| ShareError::Compression)?;
+    serde_json::from_slice(&json).map_err(|e| ShareError::Payload(e.to_string()))
+}
@@ -0,0 +1,53 @@
+//! Snapping placed and moved points to a world grid, or to markers and line
+//! ends already on the map.
+
+use crate::breadcrumbs::snap_point;
+use crate::marker::{BreadcrumbLine, Marker};
+
+/// Grids wider apart than this on screen aren't worth drawing.
+const MAX_GRID_LINES: usize = 400;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Snap {
+    /// Grid spacing in world units (100 to a metre), if snapping to a grid
+    pub grid: Option<f64>,
+    /// Whether to snap to nearby markers and line ends first
+    pub magnet: bool,
+}
+
+/// Where a point ended up after snapping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Snapped {
+    pub x: i32,
+    pub z: i32,
+    /// Height of the marker or line end the magnet pulled it to
+    pub y: Option<i32>,
+}
+
+impl Snap {
+    /// Snaps world `x`, `z`. The magnet looks `radius` world units around the
+    /// point for markers and line ends on `map_id`, and wins over the grid.
+    pub fn point(&self, x: f64, z: f64, markers: &[Marker], lines: &[BreadcrumbLine], map_id: u16, radius: f64) -> Snapped {
+        if self.magnet
+            && let Some(p) = snap_point(x, z, markers, lines, map_id, radius)
+        {
+            return Snapped { x: p.x, z: p.z, y: Some(p.y) };
+        }
+        let (x, z) = match self.grid {
+            Some(step) if step > 0.0 => ((x / step).round() * step, (z / step).round() * step),
+            _ => (x, z),
+        };
+        Snapped { x: x.round() as i32, z: z.round() as i32, y: None }
+    }
+}
+
+/// Multiples of `step` from `min` to `max`, for drawing a grid. Empty if there
+/// would be too many to be useful.
+pub fn grid_lines(min: f64, max: f64, step: f64) -> Vec<f64> {
+    if step <= 0.0 || max < min || (max - min) / step > MAX_GRID_LINES as f64 {
+        return Vec::new();
+    }
+    let first = (min / step).ceil() as i64;
+    let last = (max / step).floor() as i64;
+    (first..=last).map(|k| k as f64 * step).collect()
+}
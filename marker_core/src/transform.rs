@@ -0,0 +1,113 @@
+//! Moving, rotating, mirroring and scaling groups of markers and lines in the
+//! X/Z plane.
+//!
+//! Angles are in degrees. Rotations are clockwise as seen on the map, where X
+//! runs east and Z runs south. m0r yaw is read the way the game reports
+//! headings: 0 faces north (-Z) and it counts up anticlockwise.
+
+use std::collections::HashSet;
+
+use crate::marker::{get_marker_id, get_marker_position, BreadcrumbLine, Marker, Position3D};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Transform {
+    Translate { dx: f64, dz: f64 },
+    Rotate { pivot: (f64, f64), degrees: f64 },
+    /// Reflects across the line through `point` at `degrees` clockwise from
+    /// east, so 0 swaps north and south and 90 swaps east and west
+    Mirror { point: (f64, f64), degrees: f64 },
+    /// Multiplies every distance from `centre` by `factor`
+    Scale { centre: (f64, f64), factor: f64 },
+}
+
+impl Transform {
+    /// Where the point at world `x`, `z` ends up.
+    pub fn point(&self, x: f64, z: f64) -> (f64, f64) {
+        match *self {
+            Transform::Translate { dx, dz } => (x + dx, z + dz),
+            Transform::Rotate { pivot, .. } | Transform::Mirror { point: pivot, .. } => {
+                let (dx, dz) = self.direction(x - pivot.0, z - pivot.1);
+                (pivot.0 + dx, pivot.1 + dz)
+            }
+            Transform::Scale { centre, factor } => (centre.0 + (x - centre.0) * factor, centre.1 + (z - centre.1) * factor),
+        }
+    }
+
+    /// Turns a direction, ignoring any translation or scale.
+    fn direction(&self, dx: f64, dz: f64) -> (f64, f64) {
+        match *self {
+            Transform::Rotate { degrees, .. } => {
+                let (sin, cos) = degrees.to_radians().sin_cos();
+                (dx * cos - dz * sin, dx * sin + dz * cos)
+            }
+            Transform::Mirror { degrees, .. } => {
+                let (uz, ux) = degrees.to_radians().sin_cos();
+                let along = dx * ux + dz * uz;
+                (2.0 * along * ux - dx, 2.0 * along * uz - dz)
+            }
+            Transform::Translate { .. } | Transform::Scale { .. } => (dx, dz),
+        }
+    }
+
+    /// The yaw a m0r marker needs to keep facing the same way relative to the
+    /// markers around it.
+    pub fn yaw(&self, yaw: i16) -> i16 {
+        let (sin, cos) = (yaw as f64).to_radians().sin_cos();
+        let (dx, dz) = self.direction(-sin, -cos);
+        let turned = (-dx).atan2(-dz).to_degrees().round() as i16;
+        turned.rem_euclid(360)
+    }
+
+    fn position(&self, p: Position3D) -> Position3D {
+        let (x, z) = self.point(p.x as f64, p.z as f64);
+        Position3D { x: x.round() as i32, y: p.y, z: z.round() as i32 }
+    }
+
+    /// Moves the selected markers and lines, turning m0r markers that have
+    /// an orientation to match. Map ids are left for the caller to update.
+    pub fn apply(
+        &self,
+        markers: &mut [Marker],
+        lines: &mut [BreadcrumbLine],
+        selected_markers: &HashSet<u16>,
+        selected_lines: &HashSet<u16>,
+    ) {
+        for marker in markers.iter_mut().filter(|m| selected_markers.contains(&get_marker_id(m))) {
+            match marker {
+                Marker::Elms(m) => m.position = self.position(m.position),
+                Marker::M0r(m) => {
+                    m.position = self.position(m.position);
+                    if let Some((pitch, yaw)) = m.orientation {
+                        m.orientation = Some((pitch, self.yaw(yaw)));
+                    }
+                }
+            }
+        }
+        for line in lines.iter_mut().filter(|l| selected_lines.contains(&l.id)) {
+            line.position1 = self.position(line.position1);
+            line.position2 = self.position(line.position2);
+        }
+    }
+}
+
+/// The mean X/Z of the selected markers and line ends, or `None` if nothing
+/// is selected.
+pub fn centroid(
+    markers: &[Marker],
+    lines: &[BreadcrumbLine],
+    selected_markers: &HashSet<u16>,
+    selected_lines: &HashSet<u16>,
+) -> Option<(f64, f64)> {
+    let points: Vec<Position3D> = markers.iter()
+        .filter(|m| selected_markers.contains(&get_marker_id(m)))
+        .map(get_marker_position)
+        .chain(lines.iter().filter(|l| selected_lines.contains(&l.id)).flat_map(|l| [l.position1, l.position2]))
+        .collect();
+    if points.is_empty() {
+        return None;
+    }
+    let n = points.len() as f64;
+    let x = points.iter().map(|p| p.x as f64).sum::<f64>() / n;
+    let z = points.iter().map(|p| p.z as f64).sum::<f64>() / n;
+    Some((x, z))
+}
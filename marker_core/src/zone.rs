@@ -0,0 +1,92 @@
+use std::sync::OnceLock;
+
+use crate::catalogue::{Catalogue, ZoneCategory, BUNDLED_CATALOGUE};
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Zone {
+    pub id: u16,
+    pub maps: Vec<Map>,
+    pub name: String,
+    pub category: ZoneCategory,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Map {
+    pub name: String,
+    pub tiles: Vec<Tile>,
+    pub map_id: u16,
+    pub zone_id: u16,
+    pub count: u8,
+    pub scale_data: MapScaleData,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Tile {
+    pub path: String,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct MapScaleData {
+    pub scale_factor: f32, // unused
+    pub min_x: f32,
+    pub max_x: f32,
+    pub min_z: f32,
+    pub max_z: f32,
+    /// Floor height new markers are placed at
+    pub y: Option<f32>,
+    /// Lowest Y that belongs to this map, for maps stacked over one another
+    pub min_y: Option<f32>,
+    /// Highest Y that belongs to this map
+    pub max_y: Option<f32>,
+}
+
+impl MapScaleData {
+    /// Where a world X/Z falls on the map image, from 0.0 to 1.0 on each axis.
+    pub fn normalise(&self, x: f64, z: f64) -> (f64, f64) {
+        (
+            (x - self.min_x as f64) / (self.max_x as f64 - self.min_x as f64),
+            (z - self.min_z as f64) / (self.max_z as f64 - self.min_z as f64),
+        )
+    }
+
+    /// The world X/Z at a point on the map image. The inverse of `normalise`.
+    pub fn denormalise(&self, nx: f64, nz: f64) -> (f64, f64) {
+        (
+            self.min_x as f64 + nx * (self.max_x - self.min_x) as f64,
+            self.min_z as f64 + nz * (self.max_z - self.min_z) as f64,
+        )
+    }
+
+    pub fn contains(&self, x: f64, z: f64) -> bool {
+        x >= self.min_x as f64 && x <= self.max_x as f64 && z >= self.min_z as f64 && z <= self.max_z as f64
+    }
+
+    /// Whether `y` is within the map's height range. Maps without one hold every Y.
+    pub fn contains_y(&self, y: f64) -> bool {
+        self.min_y.is_none_or(|min| y >= min as f64) && self.max_y.is_none_or(|max| y <= max as f64)
+    }
+
+    pub fn has_y_range(&self) -> bool {
+        self.min_y.is_some() || self.max_y.is_some()
+    }
+
+    /// The height of the map's floor, if known.
+    pub fn floor_y(&self) -> Option<f32> {
+        self.y.or(self.min_y)
+    }
+}
+
+/// The zones shipped with this build, from `static/zones.json`, read once.
+pub fn bundled_zones() -> &'static [Zone] {
+    static ZONES: OnceLock<Vec<Zone>> = OnceLock::new();
+    ZONES.get_or_init(|| {
+        Catalogue::from_json(BUNDLED_CATALOGUE)
+            .expect("static/zones.json is valid")
+            .to_zones()
+    })
+}
+
+/// A copy of the zones shipped with this build, to own or change.
+pub fn populate_zone_data() -> Vec<Zone> {
+    bundled_zones().to_vec()
+}
@@ -0,0 +1,41 @@
+use marker_core::breadcrumbs::{endpoint_at, move_endpoint, polyline, snap_point, Endpoint};
+use marker_core::marker::{ElmMarker, ElmsIcon, Marker, Position3D};
+
+fn at(x: i32, y: i32, z: i32) -> Position3D {
+    Position3D { x, y, z }
+}
+
+#[test]
+fn polylines_join_points_in_order() {
+    let lines = polyline(&[at(0, 0, 0), at(100, 0, 0), at(100, 5, 100)], (255, 0, 0, 255), 1354, 4);
+    assert_eq!(lines.len(), 2);
+    assert_eq!((lines[0].id, lines[1].id), (4, 5));
+    assert_eq!(lines[0].position2, lines[1].position1);
+    assert!(polyline(&[at(0, 0, 0)], (0, 0, 0, 255), 1354, 0).is_empty());
+}
+
+#[test]
+fn snapping_prefers_the_nearest_point_in_reach() {
+    let lines = polyline(&[at(0, 10, 0), at(1000, 20, 0)], (255, 255, 255, 255), 1354, 0);
+    let marker = Marker::Elms(ElmMarker { position: at(1030, 30, 0), icon: ElmsIcon::Num(1), size: 1, active: true, id: 0, map_id: 1354 });
+    let markers = [marker];
+
+    assert_eq!(snap_point(1040.0, 0.0, &markers, &lines, 1354, 50.0), Some(at(1030, 30, 0)));
+    assert_eq!(snap_point(1010.0, 0.0, &markers, &lines, 1354, 50.0), Some(at(1000, 20, 0)));
+    assert_eq!(snap_point(500.0, 0.0, &markers, &lines, 1354, 50.0), None);
+    assert_eq!(snap_point(1010.0, 0.0, &markers, &lines, 1, 50.0), None);
+}
+
+#[test]
+fn moving_a_shared_end_keeps_lines_joined() {
+    let mut lines = polyline(&[at(0, 0, 0), at(100, 0, 0), at(200, 0, 0)], (255, 255, 255, 255), 1354, 0);
+    assert_eq!(endpoint_at(&lines, 1354, 5.0, 5.0, 10.0), Some((0, Endpoint::Start)));
+    assert_eq!(endpoint_at(&lines, 1354, 50.0, 0.0, 10.0), None);
+
+    let (id, end) = endpoint_at(&lines, 1354, 98.0, 0.0, 10.0).unwrap();
+    let from = lines.iter().find(|l| l.id == id).unwrap().endpoint(end);
+    move_endpoint(&mut lines, 1354, from, at(100, 0, 50));
+    assert_eq!(lines[0].position2, at(100, 0, 50));
+    assert_eq!(lines[1].position1, at(100, 0, 50));
+    assert_eq!(lines[1].position2, at(200, 0, 0));
+}
@@ -0,0 +1,96 @@
+use marker_core::catalogue::{load_zones, Catalogue, CatalogueError, ZoneCategory, BUNDLED_CATALOGUE};
+use marker_core::zone::{bundled_zones, populate_zone_data};
+
+const OVERRIDE: &str = r#"{
+  "version": 1,
+  "zones": [
+    {
+      "id": 1051,
+      "name": "Cloudrest (fixed)",
+      "category": "trial",
+      "maps": [
+        { "id": 1502, "name": "Cloudrest", "tiles": "summerset/ui_map_cloudresttrial_base_", "tile_count": 3, "min_x": 118000.0, "max_x": 196000.0, "min_z": 51000.0, "max_z": 129000.0 }
+      ]
+    },
+    {
+      "id": 1228,
+      "name": "Black Drake Villa",
+      "category": "dungeon",
+      "maps": [
+        { "id": 1921, "name": "Black Drake Villa", "tiles": "westernskyrim/blackdrakevilla_base_", "tile_count": 2, "min_x": 0.0, "max_x": 100.0, "min_z": 0.0, "max_z": 100.0, "y": 15000.0 }
+      ]
+    }
+  ]
+}"#;
+
+#[test]
+fn bundled_catalogue_is_valid() {
+    let catalogue = Catalogue::from_json(BUNDLED_CATALOGUE).unwrap();
+    assert!(catalogue.zones.iter().all(|z| z.category == ZoneCategory::Trial));
+    assert_eq!(Catalogue::from_json(&catalogue.to_json()).unwrap(), catalogue);
+}
+
+#[test]
+fn zones_and_maps_come_out_sorted_with_tiles() {
+    let zones = Catalogue::from_json(BUNDLED_CATALOGUE).unwrap().to_zones();
+    assert!(zones.windows(2).all(|w| w[0].id < w[1].id));
+
+    let aa = zones.iter().find(|z| z.id == 638).unwrap();
+    assert_eq!(aa.name, "Aetherian Archive");
+    assert!(aa.maps.windows(2).all(|w| w[0].map_id < w[1].map_id));
+    assert_eq!(aa.maps[0].tiles.len(), 9);
+    assert_eq!(aa.maps[0].tiles[8].path, "craglorn/aetherianarchivebottom_base_8.png");
+}
+
+#[test]
+fn bundled_zones_are_read_once() {
+    assert!(std::ptr::eq(bundled_zones(), bundled_zones()));
+    assert_eq!(populate_zone_data(), Catalogue::from_json(BUNDLED_CATALOGUE).unwrap().to_zones());
+}
+
+#[test]
+fn overrides_replace_and_add_zones() {
+    let zones = load_zones(BUNDLED_CATALOGUE, &[("local.json", OVERRIDE)]).unwrap();
+    let cloudrest = zones.iter().find(|z| z.id == 1051).unwrap();
+    assert_eq!(cloudrest.name, "Cloudrest (fixed)");
+    assert_eq!(cloudrest.maps[0].scale_data.min_x, 118000.0);
+
+    let villa = zones.iter().find(|z| z.id == 1228).unwrap();
+    assert_eq!(villa.category, ZoneCategory::Dungeon);
+    assert_eq!(villa.maps[0].scale_data.y, Some(15000.0));
+    assert_eq!(villa.maps[0].tiles.len(), 4);
+}
+
+#[test]
+fn validation_reports_every_problem() {
+    let json = r#"{"version": 1, "zones": [
+        {"id": 1, "name": "", "maps": []},
+        {"id": 2, "name": "Two", "maps": [
+            {"id": 5, "name": "A", "tiles": "x_", "tile_count": 0, "min_x": 1.0, "max_x": 2.0, "min_z": 1.0, "max_z": 2.0},
+            {"id": 5, "name": "B", "tiles": "y_", "tile_count": 3, "min_x": 2.0, "max_x": 1.0, "min_z": 1.0, "max_z": 2.0}
+        ]},
+        {"id": 2, "name": "Again", "maps": [
+            {"id": 6, "name": "C", "tiles": "z_", "tile_count": 3, "min_x": 1.0, "max_x": 2.0, "min_z": 1.0, "max_z": 2.0}
+        ]}
+    ]}"#;
+    assert_eq!(Catalogue::from_json(json), Err(vec![
+        CatalogueError::EmptyName { zone_id: 1, map_id: None },
+        CatalogueError::NoMaps(1),
+        CatalogueError::BadTiles { map_id: 5 },
+        CatalogueError::DuplicateMap(5),
+        CatalogueError::BadBounds { map_id: 5 },
+        CatalogueError::DuplicateZone(2),
+    ]));
+}
+
+#[test]
+fn newer_or_broken_files_are_refused() {
+    assert_eq!(Catalogue::from_json(r#"{"version": 2, "zones": []}"#), Err(vec![CatalogueError::UnsupportedVersion(2)]));
+    assert!(matches!(Catalogue::from_json("{").unwrap_err()[0], CatalogueError::Json(_)));
+
+    let clash = r#"{"version": 1, "zones": [{"id": 9999, "name": "Clash", "maps": [
+        {"id": 1502, "name": "Cloudrest again", "tiles": "x_", "tile_count": 1, "min_x": 0.0, "max_x": 1.0, "min_z": 0.0, "max_z": 1.0}
+    ]}]}"#;
+    let errors = load_zones(BUNDLED_CATALOGUE, &[("clash.json", clash)]).unwrap_err();
+    assert_eq!(errors, vec!["after overrides: map 1502 is listed more than once".to_string()]);
+}
@@ -0,0 +1,57 @@
+//! Builders shared by the integration tests. Each takes what tests tend to
+//! vary and fills in the rest; anything else is changed with struct update
+//! syntax on the `_marker` forms.
+
+#![allow(dead_code)]
+
+use marker_core::catalogue::Catalogue;
+use marker_core::marker::{BreadcrumbLine, ElmMarker, ElmsIcon, M0rMarker, M0rTexture, Marker, Position3D};
+use marker_core::zone::Map;
+
+/// An active Elms marker showing icon 1.
+pub fn elms_marker(id: u16, (x, y, z): (i32, i32, i32), map_id: u16) -> ElmMarker {
+    ElmMarker { position: Position3D { x, y, z }, icon: ElmsIcon::Num(1), size: 1, active: true, id, map_id }
+}
+
+/// An active, white m0r marker with no texture, text or facing.
+pub fn m0r_marker(id: u16, (x, y, z): (i32, i32, i32), map_id: u16) -> M0rMarker {
+    M0rMarker {
+        position: Position3D { x, y, z },
+        background_texture: M0rTexture::None,
+        text: None,
+        size: 1.0,
+        colour: (255, 255, 255, 255),
+        orientation: None,
+        active: true,
+        id,
+        map_id,
+    }
+}
+
+pub fn elms(id: u16, position: (i32, i32, i32), map_id: u16) -> Marker {
+    Marker::Elms(elms_marker(id, position, map_id))
+}
+
+pub fn m0r(id: u16, position: (i32, i32, i32), map_id: u16) -> Marker {
+    Marker::M0r(m0r_marker(id, position, map_id))
+}
+
+/// An active, white line between two (x, z) points at height `y`.
+pub fn line(id: u16, from: (i32, i32), to: (i32, i32), y: i32, map_id: u16) -> BreadcrumbLine {
+    BreadcrumbLine {
+        position1: Position3D { x: from.0, y, z: from.1 },
+        position2: Position3D { x: to.0, y, z: to.1 },
+        active: true,
+        colour: (255, 255, 255, 255),
+        id,
+        map_id,
+    }
+}
+
+/// Map 10, 10000 units across from the origin, in four tiles.
+pub fn square_map() -> Map {
+    let json = r#"{"version": 1, "zones": [{"id": 1, "name": "Test", "maps": [
+        {"id": 10, "name": "Square", "tiles": "test/t_", "tile_count": 2, "min_x": 0.0, "max_x": 10000.0, "min_z": 0.0, "max_z": 10000.0}
+    ]}]}"#;
+    Catalogue::from_json(json).unwrap().to_zones().remove(0).maps.remove(0)
+}
@@ -0,0 +1,76 @@
+use marker_core::convert::{convert_marker, elms_to_m0r, m0r_to_elms, Loss};
+use marker_core::marker::{ElmMarker, ElmsIcon, M0rIcon, M0rMarker, M0rTexture, Marker, MarkerTypes, Position3D, ALL_ELMS_ICONS};
+
+mod common;
+use common::{elms_marker, m0r_marker};
+
+const POSITION: (i32, i32, i32) = (92500, 35000, 95000);
+
+#[test]
+fn numbered_squares_become_squares_with_text() {
+    let converted = elms_to_m0r(&ElmMarker { icon: ElmsIcon::SquareTwoRedThree, ..elms_marker(3, POSITION, 1354) });
+    assert!(converted.is_lossless(), "{:?}", converted.losses);
+    assert_eq!(converted.marker.background_texture, M0rTexture::Known(M0rIcon::Square));
+    assert_eq!(converted.marker.text.as_deref(), Some("3"));
+    assert_eq!((converted.marker.colour.0, converted.marker.colour.3), (220, 255));
+}
+
+#[test]
+fn numbers_become_blank_markers_with_text() {
+    let converted = elms_to_m0r(&ElmMarker { icon: ElmsIcon::Num(7), ..elms_marker(3, POSITION, 1354) });
+    assert_eq!(converted.marker.background_texture, M0rTexture::Known(M0rIcon::Blank));
+    assert_eq!(converted.marker.text.as_deref(), Some("7"));
+    assert_eq!(converted.marker.position, Position3D { x: 92500, y: 35000, z: 95000 });
+    assert_eq!((converted.marker.id, converted.marker.map_id), (3, 1354));
+}
+
+#[test]
+fn lossless_elms_icons_survive_a_round_trip() {
+    for &icon in ALL_ELMS_ICONS {
+        let there = elms_to_m0r(&ElmMarker { icon, ..elms_marker(3, POSITION, 1354) });
+        let back = m0r_to_elms(&there.marker);
+        assert!(back.is_lossless(), "{icon:?}: {:?}", back.losses);
+        if there.is_lossless() {
+            assert_eq!(back.marker.icon, icon);
+        } else {
+            assert!(matches!(there.losses[..], [Loss::Icon { .. }]), "{icon:?}: {:?}", there.losses);
+        }
+    }
+}
+
+#[test]
+fn m0r_only_properties_are_reported() {
+    let marker = M0rMarker {
+        background_texture: M0rTexture::Known(M0rIcon::Diamond),
+        text: Some("Kite here".into()),
+        colour: (12, 34, 56, 128),
+        orientation: Some((-90, 45)),
+        size: 2.5,
+        ..m0r_marker(3, POSITION, 1354)
+    };
+
+    let converted = m0r_to_elms(&marker);
+    assert_eq!(converted.marker.icon, ElmsIcon::MarkerLightBlue);
+    assert_eq!(converted.losses, vec![
+        Loss::Texture(M0rTexture::Known(M0rIcon::Diamond)),
+        Loss::Text("Kite here".into()),
+        Loss::Colour((12, 34, 56, 128)),
+        Loss::Orientation((-90, 45)),
+        Loss::Size(2.5),
+    ]);
+}
+
+#[test]
+fn text_absorbed_by_the_icon_is_not_reported() {
+    let converted = m0r_to_elms(&M0rMarker { background_texture: M0rTexture::Known(M0rIcon::Blank), text: Some("b".into()), ..m0r_marker(3, POSITION, 1354) });
+    assert_eq!(converted.marker.icon, ElmsIcon::Letter('b'));
+    assert!(converted.is_lossless(), "{:?}", converted.losses);
+}
+
+#[test]
+fn converting_to_the_same_type_is_a_no_op() {
+    let marker = Marker::Elms(ElmMarker { icon: ElmsIcon::Arrow, ..elms_marker(3, POSITION, 1354) });
+    let converted = convert_marker(&marker, MarkerTypes::Elms);
+    assert_eq!(converted.marker, marker);
+    assert!(converted.is_lossless());
+}
@@ -0,0 +1,92 @@
+use marker_core::diagnostic::Severity;
+use marker_core::marker::{parse_elms_string, parse_lines_string, parse_m0r_string};
+use marker_core::zone::populate_zone_data;
+
+#[test]
+fn elms_out_of_range_zone_is_reported_not_panicked() {
+    let input = "/99999//92500,35000,95000,1//1263//92500,35000,95000,2/";
+    let parsed = parse_elms_string(input, populate_zone_data());
+
+    assert_eq!(parsed.data[&1263].len(), 1);
+    assert_eq!(parsed.diagnostics.len(), 1);
+    let d = &parsed.diagnostics[0];
+    assert_eq!(d.severity, Severity::Error);
+    assert_eq!(&input[d.span.clone()], "99999");
+}
+
+#[test]
+fn elms_unknown_zone_is_reported() {
+    let parsed = parse_elms_string("/9999//1,2,3,1/", populate_zone_data());
+
+    assert!(parsed.data.is_empty());
+    assert_eq!(parsed.diagnostics[0].message, "zone 9999 unknown");
+}
+
+#[test]
+fn m0r_without_closing_bracket_is_an_error() {
+    let parsed = parse_m0r_string("<1263]0]0:0:0]]]]]]1:2:3:", populate_zone_data());
+
+    assert!(parsed.data.is_empty());
+    assert!(parsed.has_errors());
+}
+
+#[test]
+fn m0r_bad_position_keeps_later_indices_aligned() {
+    let input = "<1263]0]16950:8868:17f9b]2:2]]]ff0000:2]]zz:0:0:,0:0:0:b>";
+    let parsed = parse_m0r_string(input, populate_zone_data());
+
+    let markers = &parsed.data[&1263];
+    assert_eq!(markers.len(), 1);
+    let marker_core::marker::Marker::M0r(m) = &markers[0] else { panic!("expected m0r marker") };
+    assert_eq!(m.size, 2.0);
+    assert_eq!(m.colour, (255, 0, 0, 255));
+    assert_eq!(&input[parsed.diagnostics[0].span.clone()], "zz:0:0:");
+}
+
+#[test]
+fn lines_report_missing_points() {
+    let input = "4EF;16950;8868;17F9B;1;FF0000;2;0;0;0;64;0;64;2;1;1;2;1;1;7;";
+    let parsed = parse_lines_string(input, populate_zone_data());
+
+    assert_eq!(parsed.data[&1263].len(), 1);
+    assert_eq!(parsed.diagnostics.len(), 1);
+    assert_eq!(parsed.diagnostics[0].message, "line references point 7 but only 2 defined");
+    assert_eq!(&input[parsed.diagnostics[0].span.clone()], "7");
+}
+
+#[test]
+fn lines_report_bad_hex() {
+    let parsed = parse_lines_string("4EF;16950;8868;17F9B;1;FF0000;1;0;xyz;0;0;", populate_zone_data());
+
+    assert!(parsed.diagnostics.iter().any(|d| d.message == "invalid hex 'xyz' for point y"));
+}
+
+#[test]
+fn repeated_line_zones_are_joined() {
+    let block = "4EF;16950;8868;17F9B;1;FF0000;2;0;0;0;64;0;64;1;1;1;2;";
+    let parsed = parse_lines_string(&format!("{block}{block}"), populate_zone_data());
+
+    assert!(parsed.diagnostics.is_empty(), "{:?}", parsed.diagnostics);
+    let ids: Vec<u16> = parsed.data[&1263].iter().map(|l| l.id).collect();
+    assert_eq!(ids, [0, 1]);
+}
+
+#[test]
+fn lines_report_out_of_range_coordinates() {
+    let input = "4EF;16950;8868;17F9B;1;FF0000;2;0;0;0;64;123456789;64;1;1;1;2;";
+    let parsed = parse_lines_string(input, populate_zone_data());
+
+    assert!(parsed.data.is_empty());
+    assert_eq!(parsed.diagnostics.len(), 1);
+    assert_eq!(parsed.diagnostics[0].message, "point y 4886718345 is out of range");
+    assert_eq!(&input[parsed.diagnostics[0].span.clone()], "123456789");
+}
+
+#[test]
+fn other_formats_are_not_read_as_lines() {
+    let input = "<1263]0]16950:8868:17f9b]2:1;3:2]]]]]0:0:0:,1:1:1:>\n/1263//92500,35000,95000,1/";
+    let parsed = parse_lines_string(input, populate_zone_data());
+
+    assert!(parsed.data.is_empty());
+    assert!(parsed.diagnostics.is_empty(), "{:?}", parsed.diagnostics);
+}
@@ -0,0 +1,63 @@
+use marker_core::export::{bounds, svg, ExportOptions};
+use marker_core::marker::{ElmMarker, ElmsIcon, M0rIcon, M0rMarker, M0rTexture, Marker};
+use marker_core::selection::WorldRect;
+
+mod common;
+use common::{elms, elms_marker, line, m0r_marker, square_map};
+
+const RED: (u8, u8, u8, u8) = (255, 0, 0, 255);
+
+fn href(path: &str) -> String {
+    format!("static/{}", path)
+}
+
+#[test]
+fn the_whole_map_is_drawn_with_shapes_tints_and_tiles() {
+    let markers = [
+        elms(0, (5000, 0, 5000), 10),
+        Marker::M0r(M0rMarker { background_texture: M0rTexture::Known(M0rIcon::Circle), text: Some("Tank & <heals>".into()), colour: RED, ..m0r_marker(1, (1000, 0, 1000), 10) }),
+        Marker::M0r(M0rMarker { background_texture: M0rTexture::Known(M0rIcon::RoleIconTank), colour: RED, ..m0r_marker(1, (2000, 0, 1000), 10) }),
+    ];
+    let image = svg(&square_map(), &markers, &[line(0, (0, 0), (10000, 10000), 0, 10)], &ExportOptions { size: 1000, ..Default::default() }, &href);
+
+    assert_eq!((image.width, image.height), (1000, 1000));
+    assert_eq!(image.svg.matches("static/maps/test/t_").count(), 4);
+    assert!(image.svg.contains(r#"href="static/icons/1.png""#));
+    assert!(image.svg.contains(r#"<path d="M127.5 252.5c69"#), "circles use their shape");
+    assert!(image.svg.contains(r#"filter="url(#tint-ff0000ff)""#), "other textures are tinted");
+    assert!(image.svg.contains(r#"<filter id="tint-ff0000ff">"#));
+    assert!(image.svg.contains("Tank &amp; &lt;heals&gt;"));
+    assert!(image.svg.contains(r#"<line x1="0.00" y1="0.00" x2="1000.00" y2="1000.00""#));
+    assert!(!image.svg.contains("Legend"));
+}
+
+#[test]
+fn crops_keep_only_the_tiles_they_touch() {
+    let markers = [elms(0, (1000, 0, 1000), 10), Marker::Elms(ElmMarker { icon: ElmsIcon::Num(2), ..elms_marker(0, (3000, 0, 2000), 10) })];
+    let crop = bounds(&markers, &[], 10, 500.0).unwrap();
+    assert_eq!(crop, WorldRect { min_x: 500.0, max_x: 3500.0, min_z: 500.0, max_z: 2500.0 });
+
+    let image = svg(&square_map(), &markers, &[], &ExportOptions { size: 600, crop: Some(crop), ..Default::default() }, &href);
+    assert_eq!((image.width, image.height), (600, 400));
+    assert!(image.svg.contains(r#"viewBox="50.00 50.00 300.00 200.00""#));
+    assert_eq!(image.svg.matches("static/maps/").count(), 1);
+
+    assert_eq!(bounds(&[], &[], 10, 500.0), None);
+}
+
+#[test]
+fn titles_and_legends_add_to_the_image() {
+    let markers = [
+        elms(0, (1000, 0, 1000), 10),
+        elms(0, (2000, 0, 1000), 10),
+        Marker::M0r(M0rMarker { background_texture: M0rTexture::Known(M0rIcon::Square), text: Some("Stack".into()), colour: RED, ..m0r_marker(1, (1000, 0, 1000), 10) }),
+    ];
+    let options = ExportOptions { size: 500, title: Some("Trial \"prog\"".to_string()), legend: true, crop: None };
+    let image = svg(&square_map(), &markers, &[line(0, (0, 0), (1, 1), 0, 10)], &options, &href);
+
+    assert_eq!((image.width, image.height), (500 + 280, 48 + 500));
+    assert!(image.svg.contains("Trial &quot;prog&quot;"));
+    assert!(image.svg.contains(">Line<"));
+    assert!(image.svg.contains(">1 ×2<"));
+    assert!(image.svg.contains(">Stack<"));
+}
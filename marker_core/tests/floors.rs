@@ -0,0 +1,97 @@
+use marker_core::catalogue::{Catalogue, CatalogueError};
+use marker_core::marker::{find_best_map, locate, parse_elms_string, parse_location, placement_y};
+use marker_core::zone::{populate_zone_data, Zone};
+
+/// Two floors sharing their X/Z bounds, like Aetherian Archive's lower levels,
+/// and a small room with no height data under the upper one.
+const STACKED: &str = r#"{"version": 1, "zones": [{"id": 1, "name": "Stacked", "maps": [
+    {"id": 10, "name": "Lower", "tiles": "a_", "tile_count": 1, "min_x": 0.0, "max_x": 1000.0, "min_z": 0.0, "max_z": 1000.0, "min_y": 0.0, "max_y": 5000.0},
+    {"id": 11, "name": "Upper", "tiles": "b_", "tile_count": 1, "min_x": 0.0, "max_x": 1000.0, "min_z": 0.0, "max_z": 1000.0, "y": 6000.0, "min_y": 5000.0, "max_y": 9000.0},
+    {"id": 12, "name": "Side room", "tiles": "c_", "tile_count": 1, "min_x": 2000.0, "max_x": 2500.0, "min_z": 0.0, "max_z": 500.0}
+]}]}"#;
+
+fn stacked() -> Vec<Zone> {
+    Catalogue::from_json(STACKED).unwrap().to_zones()
+}
+
+fn map_at(zones: &[Zone], x: i32, y: i32, z: i32) -> Option<u16> {
+    find_best_map(x, y, z, &zones[0]).map(|m| m.map_id)
+}
+
+#[test]
+fn stacked_maps_are_told_apart_by_height() {
+    let zones = stacked();
+    assert_eq!(map_at(&zones, 500, 1000, 500), Some(10));
+    assert_eq!(map_at(&zones, 500, 7000, 500), Some(11));
+    // Out of every range, the nearest floor still wins.
+    assert_eq!(map_at(&zones, 500, -200, 500), Some(10));
+    assert_eq!(map_at(&zones, 500, 12000, 500), Some(11));
+    assert_eq!(map_at(&zones, 2200, 12000, 200), Some(12));
+}
+
+#[test]
+fn kynes_aegis_floors_use_their_ranges() {
+    let zones = populate_zone_data();
+    let kynes = zones.iter().find(|z| z.id == 1196).unwrap();
+    let floor = |y| find_best_map(24975, y, 10075, kynes).map(|m| m.map_id);
+    assert_eq!(floor(21750), Some(1806));
+    assert_eq!(floor(16000), Some(1807));
+    assert_eq!(floor(12000), Some(1807));
+    assert_eq!(floor(7070), Some(1808));
+    assert_eq!(floor(-500), Some(1808));
+}
+
+#[test]
+fn markers_outside_every_height_range_are_warned_about() {
+    let parsed = parse_elms_string("/1//500,1000,500,1//1//500,20000,500,1/", stacked());
+    assert_eq!(parsed.data[&1].len(), 2);
+    assert_eq!(parsed.diagnostics.len(), 1);
+    assert!(parsed.diagnostics[0].message.contains("outside the height range"), "{}", parsed.diagnostics[0].message);
+    assert_eq!(parsed.diagnostics[0].span.start, "/1//500,1000,500,1/".len());
+}
+
+#[test]
+fn new_markers_sit_on_the_floor() {
+    let zones = stacked();
+    let (lower, upper, side) = (&zones[0].maps[0], &zones[0].maps[1], &zones[0].maps[2]);
+
+    assert_eq!(placement_y(upper), 6000);
+    assert_eq!(placement_y(lower), 0);
+    assert_eq!(placement_y(side), 0);
+}
+
+#[test]
+fn height_ranges_are_validated() {
+    let json = r#"{"version": 1, "zones": [{"id": 1, "name": "Bad", "maps": [
+        {"id": 10, "name": "Empty range", "tiles": "a_", "tile_count": 1, "min_x": 0.0, "max_x": 1.0, "min_z": 0.0, "max_z": 1.0, "min_y": 5.0, "max_y": 5.0},
+        {"id": 11, "name": "Floor outside", "tiles": "b_", "tile_count": 1, "min_x": 0.0, "max_x": 1.0, "min_z": 0.0, "max_z": 1.0, "y": 1.0, "min_y": 5.0}
+    ]}]}"#;
+    assert_eq!(Catalogue::from_json(json), Err(vec![
+        CatalogueError::BadYRange { map_id: 10 },
+        CatalogueError::BadYRange { map_id: 11 },
+    ]));
+}
+
+#[test]
+fn locations_keep_the_current_map_when_they_can() {
+    let mut zones = stacked();
+    zones.extend(Catalogue::from_json(r#"{"version": 1, "zones": [{"id": 2, "name": "Elsewhere", "maps": [
+        {"id": 20, "name": "Far", "tiles": "d_", "tile_count": 1, "min_x": 50000.0, "max_x": 60000.0, "min_z": 0.0, "max_z": 10000.0}
+    ]}]}"#).unwrap().to_zones());
+
+    assert_eq!(locate(500, None, 500, &zones, (0, 1)), Some((0, 1)));
+    assert_eq!(locate(500, Some(1000), 500, &zones, (0, 1)), Some((0, 0)));
+    assert_eq!(locate(2200, None, 200, &zones, (0, 0)), Some((0, 2)));
+    assert_eq!(locate(55000, None, 5000, &zones, (0, 0)), Some((1, 0)));
+    assert_eq!(locate(-99999, None, 5000, &zones, (0, 0)), None);
+}
+
+#[test]
+fn locations_are_read_from_loose_text() {
+    assert_eq!(parse_location("1234, 5678"), Some((1234, None, 5678)));
+    assert_eq!(parse_location("(1234.4, 20000, -56.6)"), Some((1234, Some(20000), -57)));
+    assert_eq!(parse_location("X: 10 Y: 20 Z: 30"), Some((10, Some(20), 30)));
+    assert_eq!(parse_location("12"), None);
+    assert_eq!(parse_location("1, 2, 3, 4"), None);
+    assert_eq!(parse_location("north, 5"), None);
+}
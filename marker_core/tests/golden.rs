@@ -0,0 +1,72 @@
+//! Hand-built fixture strings covering every zone in `static/zones.json`, kept
+//! under `fixtures/edge`. Each must parse without diagnostics and rebuild to
+//! exactly the same string.
+//!
+//! They cover the edge cases of each format (grouped m0r fields, m0r colours
+//! with alpha, several lines sharing points), so they only show that the
+//! writers agree with the parsers. Strings exported from Elms Markers, M0R
+//! Markers and Breadcrumbs are a separate set, each cited with a `# Zone name`
+//! line.
+
+use std::collections::BTreeSet;
+
+use marker_core::marker::{build_elms_string, build_m0r_string, lines_to_string, parse_elms_string, parse_lines_string, parse_m0r_string};
+use marker_core::zone::populate_zone_data;
+
+/// `(zone name, string)` pairs from a fixture file, where each string follows a
+/// `# Zone name` comment line.
+fn fixtures(contents: &str) -> Vec<(&str, &str)> {
+    let mut result = Vec::new();
+    let mut name = "";
+    for line in contents.lines() {
+        if let Some(n) = line.strip_prefix("# ") {
+            name = n;
+        } else if !line.is_empty() {
+            result.push((name, line));
+        }
+    }
+    result
+}
+
+fn assert_covers_every_zone<'a>(zone_ids: impl Iterator<Item = &'a u16>) {
+    let covered: BTreeSet<u16> = zone_ids.copied().collect();
+    let expected: BTreeSet<u16> = populate_zone_data().iter().map(|z| z.id).collect();
+    assert_eq!(covered, expected);
+}
+
+#[test]
+fn elms_edge_cases_round_trip() {
+    let mut zones_seen = Vec::new();
+    for (name, s) in fixtures(include_str!("fixtures/edge/elms.txt")) {
+        let parsed = parse_elms_string(s, populate_zone_data());
+        assert!(parsed.diagnostics.is_empty(), "{name}: {:?}", parsed.diagnostics);
+        assert_eq!(build_elms_string(&parsed.data), s, "{name}");
+        zones_seen.extend(parsed.data.keys().copied());
+    }
+    assert_covers_every_zone(zones_seen.iter());
+}
+
+#[test]
+fn m0r_edge_cases_round_trip() {
+    let mut zones_seen = Vec::new();
+    for (name, s) in fixtures(include_str!("fixtures/edge/m0r.txt")) {
+        let timestamp = s.split(']').nth(1).and_then(|t| t.parse().ok()).expect("fixture has a timestamp");
+        let parsed = parse_m0r_string(s, populate_zone_data());
+        assert!(parsed.diagnostics.is_empty(), "{name}: {:?}", parsed.diagnostics);
+        assert_eq!(build_m0r_string(&parsed.data, timestamp), s, "{name}");
+        zones_seen.extend(parsed.data.keys().copied());
+    }
+    assert_covers_every_zone(zones_seen.iter());
+}
+
+#[test]
+fn lines_edge_cases_round_trip() {
+    let mut zones_seen = Vec::new();
+    for (name, s) in fixtures(include_str!("fixtures/edge/lines.txt")) {
+        let parsed = parse_lines_string(s, populate_zone_data());
+        assert!(parsed.diagnostics.is_empty(), "{name}: {:?}", parsed.diagnostics);
+        assert_eq!(lines_to_string(&parsed.data), s, "{name}");
+        zones_seen.extend(parsed.data.keys().copied());
+    }
+    assert_covers_every_zone(zones_seen.iter());
+}
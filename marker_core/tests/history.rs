@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+
+use marker_core::history::{Command, History};
+use marker_core::marker::{set_marker_active, BreadcrumbLine, Marker};
+use marker_core::project::{Annotations, LayerEdit, MetadataEdit};
+
+mod common;
+use common::{elms, m0r};
+
+type Markers = HashMap<u16, Vec<Marker>>;
+type Lines = HashMap<u16, Vec<BreadcrumbLine>>;
+
+/// Applies `edit` to a copy of the state and records it.
+fn edit(history: &mut History, markers: &mut Markers, lines: &mut Lines, now_ms: f64, edit: impl FnOnce(&mut Markers, &mut Lines)) {
+    record(history, markers, lines, now_ms, false, edit);
+}
+
+/// Like `edit`, but as a keystroke in a field, which may merge with the last.
+fn type_into(history: &mut History, markers: &mut Markers, lines: &mut Lines, now_ms: f64, edit: impl FnOnce(&mut Markers, &mut Lines)) {
+    record(history, markers, lines, now_ms, true, edit);
+}
+
+fn record(history: &mut History, markers: &mut Markers, lines: &mut Lines, now_ms: f64, typed: bool, edit: impl FnOnce(&mut Markers, &mut Lines)) {
+    let (mut new_markers, mut new_lines) = (markers.clone(), lines.clone());
+    edit(&mut new_markers, &mut new_lines);
+    if let Some(command) = Command::between((markers, lines), (&new_markers, &new_lines)) {
+        history.push(if typed { command.merging() } else { command }, now_ms);
+    }
+    *markers = new_markers;
+    *lines = new_lines;
+}
+
+#[test]
+fn undo_and_redo_restore_each_state() {
+    let mut history = History::default();
+    let (mut markers, mut lines) = (Markers::new(), Lines::new());
+
+    edit(&mut history, &mut markers, &mut lines, 0.0, |m, _| m.entry(1263).or_default().push(elms(0, (92500, 35000, 95000), 1354)));
+    let placed = markers.clone();
+    edit(&mut history, &mut markers, &mut lines, 5000.0, |m, _| m.get_mut(&1263).unwrap()[0] = elms(0, (93000, 35000, 95000), 1354));
+
+    assert_eq!(history.entries(), (vec!["Add 1 marker", "Move 1 marker"], 2));
+
+    assert_eq!(history.undo(&mut markers, &mut lines, &mut Annotations::default()).as_deref(), Some("Move 1 marker"));
+    assert_eq!(markers, placed);
+    history.undo(&mut markers, &mut lines, &mut Annotations::default());
+    assert!(markers.is_empty());
+    assert!(!history.can_undo());
+
+    history.redo(&mut markers, &mut lines, &mut Annotations::default());
+    history.redo(&mut markers, &mut lines, &mut Annotations::default());
+    assert_eq!(markers[&1263], vec![elms(0, (93000, 35000, 95000), 1354)]);
+    assert!(!history.can_redo());
+}
+
+#[test]
+fn a_new_edit_discards_the_redo_stack() {
+    let mut history = History::default();
+    let (mut markers, mut lines) = (Markers::new(), Lines::new());
+
+    edit(&mut history, &mut markers, &mut lines, 0.0, |m, _| m.entry(1263).or_default().push(elms(0, (92500, 35000, 95000), 1354)));
+    history.undo(&mut markers, &mut lines, &mut Annotations::default());
+    edit(&mut history, &mut markers, &mut lines, 5000.0, |m, _| m.entry(1344).or_default().push(elms(0, (92500, 35000, 95000), 1354)));
+
+    assert!(!history.can_redo());
+    assert_eq!(history.entries().0.len(), 1);
+}
+
+#[test]
+fn rapid_typed_edits_are_merged() {
+    let mut history = History::default();
+    let mut markers = HashMap::from([(1263, vec![elms(0, (92500, 35000, 95000), 1354)])]);
+    let mut lines = Lines::new();
+    let original = markers.clone();
+
+    for (i, x) in [92510, 92520, 92530].into_iter().enumerate() {
+        type_into(&mut history, &mut markers, &mut lines, i as f64 * 100.0, |m, _| m.get_mut(&1263).unwrap()[0] = elms(0, (x, 35000, 95000), 1354));
+    }
+    assert_eq!(history.entries(), (vec!["Move 1 marker"], 1));
+
+    history.undo(&mut markers, &mut lines, &mut Annotations::default());
+    assert_eq!(markers, original);
+}
+
+#[test]
+fn quick_placements_are_separate_steps() {
+    let mut history = History::default();
+    let (mut markers, mut lines) = (Markers::new(), Lines::new());
+
+    // Two right-click placements a moment apart.
+    edit(&mut history, &mut markers, &mut lines, 0.0, |m, _| m.entry(1263).or_default().push(elms(0, (92500, 35000, 95000), 1354)));
+    edit(&mut history, &mut markers, &mut lines, 100.0, |m, _| m.entry(1263).or_default().push(elms(1, (93000, 35000, 95000), 1354)));
+    assert_eq!(history.entries(), (vec!["Add 1 marker", "Add 1 marker"], 2));
+
+    history.undo(&mut markers, &mut lines, &mut Annotations::default());
+    assert_eq!(markers[&1263], vec![elms(0, (92500, 35000, 95000), 1354)]);
+}
+
+#[test]
+fn typing_after_an_undo_starts_a_new_step() {
+    let mut history = History::default();
+    let mut markers = HashMap::from([(1263, vec![elms(0, (92500, 35000, 95000), 1354)])]);
+    let mut lines = Lines::new();
+
+    type_into(&mut history, &mut markers, &mut lines, 0.0, |m, _| m.get_mut(&1263).unwrap()[0] = elms(0, (92510, 35000, 95000), 1354));
+    type_into(&mut history, &mut markers, &mut lines, 100.0, |m, _| m.get_mut(&1263).unwrap()[0] = elms(0, (92520, 35000, 95000), 1354));
+    history.jump_to(1, &mut markers, &mut lines, &mut Annotations::default());
+    type_into(&mut history, &mut markers, &mut lines, 200.0, |m, _| m.get_mut(&1263).unwrap()[0] = elms(0, (92530, 35000, 95000), 1354));
+    assert_eq!(history.entries(), (vec!["Move 1 marker", "Move 1 marker"], 2));
+
+    history.undo(&mut markers, &mut lines, &mut Annotations::default());
+    assert_eq!(markers[&1263], vec![elms(0, (92520, 35000, 95000), 1354)]);
+}
+
+#[test]
+fn toggling_a_m0r_marker_is_recorded() {
+    // M0rMarker's PartialEq ignores `active`, but undo must not.
+    let mut history = History::default();
+    let mut markers = HashMap::from([(1263, vec![m0r(0, (92500, 35000, 95000), 1354)])]);
+    let mut lines = Lines::new();
+
+    edit(&mut history, &mut markers, &mut lines, 0.0, |m, _| set_marker_active(&mut m.get_mut(&1263).unwrap()[0], false));
+    assert_eq!(history.entries(), (vec!["Edit 1 marker"], 1));
+
+    history.undo(&mut markers, &mut lines, &mut Annotations::default());
+    assert!(matches!(&markers[&1263][0], Marker::M0r(m) if m.active));
+}
+
+#[test]
+fn jump_to_moves_through_several_entries() {
+    let mut history = History::default();
+    let (mut markers, mut lines) = (Markers::new(), Lines::new());
+    for (i, zone) in [1263, 1344, 1427].into_iter().enumerate() {
+        edit(&mut history, &mut markers, &mut lines, i as f64 * 5000.0, |m, _| m.entry(zone).or_default().push(elms(0, (92500, 35000, 95000), 1354)));
+    }
+
+    history.jump_to(1, &mut markers, &mut lines, &mut Annotations::default());
+    assert_eq!(markers.keys().copied().collect::<Vec<_>>(), vec![1263]);
+    assert_eq!(history.entries().1, 1);
+
+    history.jump_to(3, &mut markers, &mut lines, &mut Annotations::default());
+    assert_eq!(markers.len(), 3);
+}
+
+#[test]
+fn pastes_across_zones_can_be_relabelled() {
+    let markers = HashMap::from([(1263, vec![elms(0, (92500, 35000, 95000), 1354)]), (1344, vec![m0r(0, (92500, 35000, 95000), 1354)])]);
+    let command = Command::between((&Markers::new(), &Lines::new()), (&markers, &Lines::new())).unwrap();
+    assert_eq!(command.label, "Edit 2 zones");
+    assert_eq!(command.with_label("Paste").label, "Paste");
+}
+
+#[test]
+fn notes_and_layers_are_undone_with_everything_else() {
+    let mut history = History::default();
+    let mut markers = HashMap::from([(1263, vec![elms(0, (92500, 35000, 95000), 1354)])]);
+    let mut lines = Lines::new();
+    let mut annotations = Annotations::default();
+    let unchanged = (markers.clone(), lines.clone());
+    let snapshot = (&unchanged.0, &unchanged.1);
+
+    annotations.apply(1263, &MetadataEdit::Name("Tank".to_string()), &[0].into(), &Default::default());
+    let named = annotations.clone();
+    history.push(Command::annotated(snapshot, snapshot, &Annotations::default(), &named).unwrap().with_label("Rename"), 0.0);
+    annotations.edit_layer("", &LayerEdit::Visible(false));
+    history.push(Command::annotated(snapshot, snapshot, &named, &annotations).unwrap(), 5000.0);
+    assert_eq!(history.entries(), (vec!["Rename", "Edit notes"], 2));
+    assert!(Command::annotated(snapshot, snapshot, &annotations, &annotations).is_none());
+
+    history.undo(&mut markers, &mut lines, &mut annotations);
+    assert_eq!(annotations, named);
+    history.undo(&mut markers, &mut lines, &mut annotations);
+    assert_eq!(annotations, Annotations::default());
+    history.jump_to(2, &mut markers, &mut lines, &mut annotations);
+    assert!(!annotations.layer("").visible);
+    assert_eq!(annotations.marker(1263, 0).unwrap().name, "Tank");
+    assert_eq!(markers, unchanged.0);
+}
+
+#[test]
+fn deletes_bring_their_notes_back_on_undo() {
+    let mut history = History::default();
+    let placed = HashMap::from([(1263, vec![elms(0, (92500, 35000, 95000), 1354)])]);
+    let mut lines = Lines::new();
+    let mut annotations = Annotations::default();
+    annotations.apply(1263, &MetadataEdit::Notes("Stack here".to_string()), &[0].into(), &Default::default());
+    let noted = annotations.clone();
+
+    // What re-parsing the string leaves once the marker is gone from it.
+    let mut markers = Markers::new();
+    let cleared = annotations.remap((&placed, &lines), (&markers, &lines));
+    history.push(Command::annotated((&placed, &lines), (&markers, &lines), &annotations, &cleared).unwrap(), 0.0);
+    annotations = cleared;
+    assert_eq!(annotations.marker(1263, 0), None);
+
+    history.undo(&mut markers, &mut lines, &mut annotations);
+    assert_eq!(markers, placed);
+    assert_eq!(annotations, noted);
+}
@@ -0,0 +1,70 @@
+use marker_core::lua::{parse, table_body, write, LuaError, Table, Value};
+
+const GAME_FILE: &str = r#"ElmsMarkers_SavedVariables =
+{
+    ["Default"] = 
+    {
+        ["@Someone"] = 
+        {
+            ["$AccountWide"] = 
+            {
+                ["version"] = 1,
+                ["size"] = 1.2999999523,
+                ["enabled"] = true,
+                ["label"] = "Say \"hi\"\n\226\128\148 then \\ go",
+            },
+        },
+    },
+}
+Other_SavedVariables = { 4, -5, x = 0x10, [2.5] = nil } -- trailing comment
+"#;
+
+#[test]
+fn game_files_are_read() {
+    let assignments = parse(GAME_FILE).unwrap();
+    assert_eq!(assignments.iter().map(|a| a.name.as_str()).collect::<Vec<_>>(), ["ElmsMarkers_SavedVariables", "Other_SavedVariables"]);
+
+    let account = assignments[0].value.as_table().unwrap()
+        .get("Default").and_then(Value::as_table).unwrap()
+        .get("@Someone").and_then(Value::as_table).unwrap()
+        .get("$AccountWide").and_then(Value::as_table).unwrap();
+    assert_eq!(account.get("version"), Some(&Value::Number(1.0)));
+    assert_eq!(account.get("size"), Some(&Value::Number(1.2999999523)));
+    assert_eq!(account.get("enabled"), Some(&Value::Bool(true)));
+    assert_eq!(account.get("label").and_then(Value::as_str), Some("Say \"hi\"\n\u{2014} then \\ go"));
+    assert_eq!(&GAME_FILE[account.span.clone()][..1], "{");
+
+    let other = assignments[1].value.as_table().unwrap();
+    assert_eq!(other.index(1), Some(&Value::Number(4.0)));
+    assert_eq!(other.index(2), Some(&Value::Number(-5.0)), "positional entries count from 1");
+    assert_eq!(other.get("x"), Some(&Value::Number(16.0)));
+}
+
+/// `value` as if built to be written, for comparing with one that was.
+fn without_spans(value: &Value) -> Value {
+    match value {
+        Value::Table(table) => Value::Table(Table::from_pairs(table.entries.iter().map(|e| (e.key.clone(), without_spans(&e.value))).collect())),
+        other => other.clone(),
+    }
+}
+
+#[test]
+fn written_tables_read_back_the_same() {
+    let value: Value = vec![
+        ("name".into(), "quote \" and\nnewline".into()),
+        (3.into(), vec![(1.into(), (-7).into()), ("on".into(), Value::Bool(false))].into()),
+    ].into();
+    let text = write("Test_SavedVariables", &value);
+    assert!(text.starts_with("Test_SavedVariables =\n{\n    [\"name\"] = "), "{text}");
+
+    assert_eq!(without_spans(&parse(&text).unwrap()[0].value), value);
+    assert_eq!(table_body(&Table::default(), "  "), "\n  ");
+}
+
+#[test]
+fn broken_files_say_where() {
+    assert_eq!(parse("A = { 1, 2"), Err(LuaError::Truncated));
+    assert_eq!(parse("A = { 1 2 }"), Err(LuaError::Unexpected(8, "2".to_string(), "',' or '}'")));
+    assert_eq!(parse("A = { 1.2.3 }"), Err(LuaError::BadNumber(6, "1.2.3".to_string())));
+    assert!(matches!(parse("A = \"open\nB = 1"), Err(LuaError::Unexpected(9, _, _))));
+}
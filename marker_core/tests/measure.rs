@@ -0,0 +1,34 @@
+use marker_core::marker::Position3D;
+use marker_core::measure::{pairwise, Measurement, Point};
+
+fn point(x: f64, z: f64, y: Option<i32>) -> Point {
+    Point { x, z, y }
+}
+
+#[test]
+fn distances_are_in_metres_with_bearings_from_north() {
+    let east = Measurement { from: point(1000.0, 1000.0, None), to: point(1800.0, 1000.0, None) };
+    assert_eq!(east.metres(), 8.0);
+    assert_eq!(east.bearing(), 90.0);
+    assert_eq!(east.rise(), None);
+    assert_eq!(east.label(), "8.0 m, 090°");
+
+    let north = Measurement { from: point(0.0, 0.0, Some(100)), to: point(0.0, -300.0, Some(310)) };
+    assert_eq!(north.bearing(), 0.0);
+    assert_eq!(north.label(), "3.0 m, 000°, 2.1 m up");
+
+    let south_west = Measurement { from: point(0.0, 0.0, Some(500)), to: point(-100.0, 100.0, Some(0)) };
+    assert_eq!(south_west.bearing(), 225.0);
+    assert_eq!(south_west.label(), "1.4 m, 225°, 5.0 m down");
+
+    let level = Measurement { from: point(0.0, 0.0, Some(5)), to: point(0.0, 100.0, Some(7)) };
+    assert_eq!(level.label(), "1.0 m, 180°, level");
+}
+
+#[test]
+fn every_pair_is_measured_once() {
+    let at = |x, z| Position3D { x, y: 0, z };
+    let pairs = pairwise(&[at(0, 0), at(300, 400), at(0, 800)]);
+    assert_eq!(pairs.iter().map(|m| m.metres()).collect::<Vec<_>>(), [5.0, 8.0, 5.0]);
+    assert!(pairwise(&[at(0, 0)]).is_empty());
+}
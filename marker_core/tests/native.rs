@@ -0,0 +1,48 @@
+use std::collections::HashMap;
+
+use marker_core::marker::{build_m0r_string, parse_elms_string, M0rMarker, M0rTexture, Marker, Position3D};
+use marker_core::zone::populate_zone_data;
+
+#[test]
+fn m0r_string_uses_injected_timestamp() {
+    let marker = M0rMarker {
+        position: Position3D { x: 92500, y: 35000, z: 95000 },
+        background_texture: M0rTexture::None,
+        text: None,
+        size: 1.0,
+        colour: (255, 255, 255, 255),
+        orientation: None,
+        active: true,
+        id: 0,
+        map_id: 1354,
+    };
+    let markers = HashMap::from([(1263, vec![Marker::M0r(marker)])]);
+
+    let built = build_m0r_string(&markers, 1_700_000_000);
+    assert!(built.starts_with("<1263]1700000000]"), "{built}");
+}
+
+#[test]
+fn elms_markers_outside_known_zones_are_dropped() {
+    let zones = populate_zone_data();
+    let markers = parse_elms_string("/1263//92500,35000,95000,1//1//0,0,0,1/", zones).data;
+    assert_eq!(markers.len(), 1);
+    assert_eq!(markers[&1263].len(), 1);
+}
+
+#[test]
+fn map_projection_round_trips() {
+    for zone in populate_zone_data() {
+        for map in &zone.maps {
+            let s = &map.scale_data;
+            let (x, z) = ((s.min_x + s.max_x) as f64 / 2.0 + 123.0, s.min_z as f64 + 456.0);
+            let (nx, nz) = s.normalise(x, z);
+            assert!((0.0..=1.0).contains(&nx) && (0.0..=1.0).contains(&nz), "{}", map.name);
+
+            let (bx, bz) = s.denormalise(nx, nz);
+            assert!((bx - x).abs() < 1e-6 && (bz - z).abs() < 1e-6, "{}", map.name);
+            assert!(s.contains(bx, bz));
+            assert!(!s.contains(s.max_x as f64 + 1.0, bz));
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use marker_core::marker::{ElmsIcon, Marker};
+use marker_core::pattern::{Generator, Labels, Shape};
+
+fn rounded(points: Vec<(f64, f64)>) -> Vec<(i32, i32)> {
+    points.into_iter().map(|(x, z)| (x.round() as i32, z.round() as i32)).collect()
+}
+
+#[test]
+fn rings_start_at_the_given_bearing_and_go_clockwise() {
+    let ring = Shape::Ring { centre: (1000.0, 1000.0), radius: 500.0, count: 4, start_degrees: 0.0 };
+    assert_eq!(rounded(ring.points()), [(1000, 500), (1500, 1000), (1000, 1500), (500, 1000)]);
+
+    let clock = Shape::Clock { centre: (0.0, 0.0), radius: 100.0 }.points();
+    assert_eq!(clock.len(), 12);
+    assert_eq!(rounded(vec![clock[2], clock[11]]), [(100, 0), (0, -100)]);
+}
+
+#[test]
+fn grids_are_centred_and_go_row_by_row() {
+    let grid = Shape::Grid { centre: (0.0, 0.0), rows: 2, columns: 3, spacing: (100.0, 50.0) };
+    assert_eq!(rounded(grid.points()), [(-100, -25), (0, -25), (100, -25), (-100, 25), (0, 25), (100, 25)]);
+}
+
+#[test]
+fn polylines_are_spaced_by_distance() {
+    let path = vec![(0.0, 0.0), (300.0, 0.0), (300.0, 100.0)];
+    let line = Shape::Polyline { points: path.clone(), count: 5 };
+    assert_eq!(rounded(line.points()), [(0, 0), (100, 0), (200, 0), (300, 0), (300, 100)]);
+    assert_eq!(rounded(Shape::Polyline { points: path, count: 1 }.points()), [(0, 0)]);
+    assert!(Shape::Polyline { points: Vec::new(), count: 3 }.points().is_empty());
+}
+
+#[test]
+fn generated_markers_are_labelled_in_order() {
+    let icons = |labels, count| {
+        let generator = Generator { shape: Shape::Ring { centre: (0.0, 0.0), radius: 10.0, count, start_degrees: 0.0 }, labels };
+        generator.markers(1200, 1354, 7).into_iter().map(|m| match m {
+            Marker::Elms(m) => (m.id, m.icon, m.position.y, m.map_id),
+            Marker::M0r(_) => unreachable!(),
+        }).collect::<Vec<_>>()
+    };
+    let numbered = icons(Labels::Numbers, 13);
+    assert_eq!(numbered[0], (7, ElmsIcon::Num(1), 1200, 1354));
+    assert_eq!(numbered[11].1, ElmsIcon::Num(12));
+    assert_eq!(numbered[12], (19, ElmsIcon::Num(1), 1200, 1354));
+    assert_eq!(icons(Labels::Letters, 3).iter().map(|m| m.1).collect::<Vec<_>>(), [ElmsIcon::Letter('a'), ElmsIcon::Letter('b'), ElmsIcon::Letter('c')]);
+
+    let generator = Generator { shape: Shape::Clock { centre: (0.0, 0.0), radius: 10.0 }, labels: Labels::Same(ElmsIcon::Arrow) };
+    assert_eq!(generator.markers(0, 1, u16::MAX - 1).len(), 2);
+}
@@ -0,0 +1,195 @@
+use std::collections::{HashMap, HashSet};
+
+use marker_core::marker::{build_combined_string, parse_combined_string, BreadcrumbLine, ElmMarker, ElmsIcon, M0rMarker, M0rTexture, Marker, Position3D};
+use marker_core::marker::get_marker_id;
+use marker_core::project::{Annotations, Layer, LayerEdit, Metadata, MetadataEdit, Project, ProjectError, PROJECT_VERSION};
+use marker_core::zone::populate_zone_data;
+
+fn markers() -> HashMap<u16, Vec<Marker>> {
+    let elms = Marker::Elms(ElmMarker { position: Position3D { x: 92500, y: 35000, z: 95000 }, icon: ElmsIcon::Letter('b'), size: 1, active: false, id: 7, map_id: 2004 });
+    let m0r = Marker::M0r(M0rMarker {
+        position: Position3D { x: -10, y: 0, z: 20 },
+        background_texture: M0rTexture::Unknown("/esoui/art/custom.dds".to_string()),
+        text: Some("Tank \"stack\"".to_string()),
+        size: 1.3,
+        colour: (255, 128, 0, 200),
+        orientation: Some((-45, 270)),
+        active: true,
+        id: 3,
+        map_id: 2004,
+    });
+    HashMap::from([(1263, vec![elms, m0r])])
+}
+
+fn lines() -> HashMap<u16, Vec<BreadcrumbLine>> {
+    let line = BreadcrumbLine { position1: Position3D { x: 0, y: 1, z: 2 }, position2: Position3D { x: 3, y: 4, z: 5 }, active: true, colour: (1, 2, 3, 4), id: 9, map_id: 2004 };
+    HashMap::from([(1263, vec![line])])
+}
+
+fn annotations() -> Annotations {
+    let mut annotations = Annotations::default();
+    annotations.apply(1263, &MetadataEdit::Name("Left portal".to_string()), &HashSet::from([3]), &HashSet::new());
+    annotations.apply(1263, &MetadataEdit::Notes("Stack here\nafter the first portal".to_string()), &HashSet::from([3]), &HashSet::new());
+    annotations.apply(1263, &MetadataEdit::Group("Portal group 1".to_string()), &HashSet::from([3, 7]), &HashSet::from([9]));
+    annotations.apply(1263, &MetadataEdit::Tag(Some((0, 200, 255))), &HashSet::new(), &HashSet::from([9]));
+    annotations
+}
+
+#[test]
+fn projects_round_trip_every_field() {
+    let json = Project::new(&markers(), &lines(), &annotations()).to_json();
+    let ((markers_back, lines_back), annotations_back) = Project::from_json(&json).unwrap().into_parts();
+
+    // Debug output covers the fields `PartialEq` skips, such as ids.
+    assert_eq!(format!("{:?}", markers_back), format!("{:?}", markers()));
+    assert_eq!(format!("{:?}", lines_back), format!("{:?}", lines()));
+    assert_eq!(annotations_back, annotations());
+    assert_eq!(
+        annotations_back.marker(1263, 3),
+        Some(&Metadata { name: "Left portal".into(), notes: "Stack here\nafter the first portal".into(), group: "Portal group 1".into(), tag: None })
+    );
+    assert_eq!(annotations_back.groups(), ["Portal group 1"]);
+}
+
+#[test]
+fn game_strings_leave_metadata_out() {
+    let project = Project::new(&markers(), &lines(), &annotations());
+    let ((markers_back, lines_back), _) = project.into_parts();
+    let exported = build_combined_string(&markers_back, &lines_back, 1700000000);
+
+    assert_eq!(exported, build_combined_string(&markers(), &lines(), 1700000000));
+    assert!(!exported.contains("portal"), "{exported}");
+}
+
+#[test]
+fn clearing_every_field_drops_the_entry() {
+    let mut annotations = annotations();
+    annotations.apply(1263, &MetadataEdit::Group(" ".to_string()), &HashSet::new(), &HashSet::from([9]));
+    assert!(annotations.line(1263, 9).is_some(), "still tagged");
+    annotations.apply(1263, &MetadataEdit::Tag(None), &HashSet::new(), &HashSet::from([9]));
+    assert!(annotations.lines.is_empty());
+}
+
+#[test]
+fn new_items_do_not_inherit_a_deleted_items_metadata() {
+    let mut annotations = annotations();
+    let before = markers();
+    let mut deleted = before.clone();
+    deleted.get_mut(&1263).unwrap().retain(|m| matches!(m, Marker::Elms(_)));
+    annotations.forget_new((&before, &lines()), (&deleted, &lines()));
+    assert!(annotations.marker(1263, 3).is_some(), "kept for undo");
+
+    let mut reused = deleted.clone();
+    reused.get_mut(&1263).unwrap().push(before[&1263][1].clone());
+    annotations.forget_new((&deleted, &lines()), (&reused, &lines()));
+    assert!(annotations.marker(1263, 3).is_none());
+    assert!(annotations.marker(1263, 7).is_some());
+}
+
+#[test]
+fn notes_follow_their_markers_when_the_string_is_parsed_again() {
+    let zones = populate_zone_data();
+    let parse = |s: &str| parse_combined_string(s, zones.clone()).data;
+    let (a, b, c) = ("/1263//92500,35000,95000,1/", "/1263//93000,35000,95000,2/", "/1263//93500,35000,95000,3/");
+    let before = parse(&format!("{a}{b}{c}"));
+    let mut annotations = Annotations::default();
+    annotations.apply(1263, &MetadataEdit::Name("B".to_string()), &HashSet::from([1]), &HashSet::new());
+    annotations.apply(1263, &MetadataEdit::Name("C".to_string()), &HashSet::from([2]), &HashSet::new());
+    annotations.edit_layer("", &LayerEdit::Locked(true));
+
+    // Deleting the first marker shifts the others' ids down.
+    let shifted = parse(&format!("{b}{c}"));
+    let remapped = annotations.remap((&before.0, &before.1), (&shifted.0, &shifted.1));
+    assert_eq!(remapped.marker(1263, 0).unwrap().name, "B");
+    assert_eq!(remapped.marker(1263, 1).unwrap().name, "C");
+    assert_eq!(remapped.layers, annotations.layers);
+
+    // A marker edited in place keeps its id, and its notes.
+    let moved = parse(&format!("{a}{b}/1263//93600,35000,95000,3/"));
+    let remapped = annotations.remap((&before.0, &before.1), (&moved.0, &moved.1));
+    assert_eq!(remapped.marker(1263, 2).unwrap().name, "C");
+
+    let cleared = annotations.remap((&before.0, &before.1), (&HashMap::new(), &HashMap::new()));
+    assert!(cleared.markers.is_empty());
+}
+
+#[test]
+fn newer_or_broken_projects_are_refused() {
+    let newer = format!(r#"{{"version": {}, "zones": []}}"#, PROJECT_VERSION + 1);
+    assert_eq!(Project::from_json(&newer), Err(ProjectError::UnsupportedVersion(PROJECT_VERSION + 1)));
+    assert!(matches!(Project::from_json("{\"zones\": 3}"), Err(ProjectError::Json(_))));
+}
+
+fn names(layers: &[Layer]) -> Vec<&str> {
+    layers.iter().map(|l| l.name.as_str()).collect()
+}
+
+#[test]
+fn every_group_is_a_layer_above_the_ungrouped_one() {
+    let mut annotations = annotations();
+    annotations.apply(1263, &MetadataEdit::Group("Right portal".to_string()), &HashSet::new(), &HashSet::from([9]));
+    assert_eq!(names(&annotations.layers()), ["Portal group 1", "Right portal", ""]);
+
+    annotations.edit_layer("Right portal", &LayerEdit::Raise);
+    annotations.edit_layer("", &LayerEdit::Raise);
+    assert_eq!(names(&annotations.layers()), ["Right portal", "", "Portal group 1"]);
+    annotations.edit_layer("Right portal", &LayerEdit::Raise);
+    assert_eq!(names(&annotations.layers()), ["Right portal", "", "Portal group 1"], "already on top");
+
+    // A group that empties out keeps its place for when it comes back.
+    annotations.apply(1263, &MetadataEdit::Group(String::new()), &HashSet::new(), &HashSet::from([9]));
+    assert_eq!(names(&annotations.layers()), ["", "Portal group 1"]);
+    annotations.apply(1263, &MetadataEdit::Group("Right portal".to_string()), &HashSet::new(), &HashSet::from([9]));
+    assert_eq!(names(&annotations.layers()), ["Right portal", "", "Portal group 1"]);
+}
+
+#[test]
+fn hidden_layers_are_left_out_and_the_rest_drawn_bottom_first() {
+    let mut annotations = annotations();
+    let extra = Marker::Elms(ElmMarker { position: Position3D { x: 0, y: 0, z: 0 }, icon: ElmsIcon::Num(1), size: 1, active: true, id: 8, map_id: 2004 });
+    let (mut markers, lines) = (markers()[&1263].clone(), lines()[&1263].clone());
+    markers.insert(0, extra);
+    annotations.edit_layer("", &LayerEdit::Raise);
+
+    let (mut shown, mut shown_lines) = (markers.clone(), lines.clone());
+    annotations.arrange(1263, &mut shown, &mut shown_lines);
+    assert_eq!(shown.iter().map(get_marker_id).collect::<Vec<_>>(), [7, 3, 8], "ungrouped marker now on top");
+    assert_eq!(shown_lines.len(), 1);
+
+    annotations.edit_layer("Portal group 1", &LayerEdit::Visible(false));
+    let (mut shown, mut shown_lines) = (markers.clone(), lines.clone());
+    annotations.arrange(1263, &mut shown, &mut shown_lines);
+    assert_eq!(shown.iter().map(get_marker_id).collect::<Vec<_>>(), [8]);
+    assert!(shown_lines.is_empty());
+}
+
+#[test]
+fn locked_layers_show_but_cannot_be_picked() {
+    let mut annotations = annotations();
+    annotations.edit_layer("Portal group 1", &LayerEdit::Locked(true));
+    annotations.edit_layer("Portal group 1", &LayerEdit::Opacity(1.5));
+    let layer = annotations.marker_layer(1263, 3);
+    assert_eq!(layer, Layer { name: "Portal group 1".to_string(), visible: true, locked: true, opacity: 1.0 });
+    assert!(!layer.editable());
+
+    let (mut markers, mut lines) = (markers()[&1263].clone(), lines()[&1263].clone());
+    annotations.arrange(1263, &mut markers, &mut lines);
+    assert_eq!(markers.len(), 2);
+    annotations.retain_editable(1263, &mut markers, &mut lines);
+    assert!(markers.is_empty() && lines.is_empty());
+}
+
+#[test]
+fn projects_keep_layers_only_once_changed() {
+    let json = Project::new(&markers(), &lines(), &annotations()).to_json();
+    assert!(!json.contains("\"layers\""), "{json}");
+
+    let mut annotations = annotations();
+    annotations.edit_layer("Portal group 1", &LayerEdit::Opacity(0.4));
+    annotations.edit_layer("", &LayerEdit::Visible(false));
+    let json = Project::new(&markers(), &lines(), &annotations).to_json();
+    let (_, annotations_back) = Project::from_json(&json).unwrap().into_parts();
+    assert_eq!(annotations_back, annotations);
+    assert_eq!(annotations_back.layer("Portal group 1").opacity, 0.4);
+    assert!(!annotations_back.layer("").visible);
+}
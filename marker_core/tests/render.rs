@@ -0,0 +1,73 @@
+use marker_core::marker::{BreadcrumbLine, M0rIcon, M0rMarker, M0rTexture, Marker};
+use marker_core::render::{m0r_bitmap_path, render, Image};
+use marker_core::selection::WorldRect;
+
+mod common;
+use common::{elms, line, m0r_marker, square_map};
+
+/// Tiles are blue, icons white and textures a white square, all solid.
+fn load(path: &str) -> Option<Image> {
+    match path {
+        p if p.starts_with("maps/") => Some(Image::new(4, 4, (0, 0, 255, 255))),
+        "icons/1.png" | "icons/m0r/square.png" => Some(Image::new(16, 16, (255, 255, 255, 255))),
+        _ => None,
+    }
+}
+
+#[test]
+fn tiles_lines_and_markers_are_drawn_in_place() {
+    let line = BreadcrumbLine { colour: (0, 255, 0, 255), ..line(0, (0, 9050), (10000, 9050), 0, 10) };
+    let m0r = Marker::M0r(M0rMarker { background_texture: M0rTexture::Known(M0rIcon::Square), colour: (255, 0, 0, 255), ..m0r_marker(1, (7500, 0, 2500), 10) });
+    let image = render(&square_map(), &[elms(0, (2500, 0, 2500), 10), m0r], &[line], 300, None, &load);
+
+    assert_eq!((image.width, image.height), (300, 300));
+    assert_eq!(image.pixel(150, 150), (0, 0, 255, 255), "tile");
+    assert_eq!(image.pixel(75, 75), (255, 255, 255, 255), "elms icon");
+    assert_eq!(image.pixel(225, 76), (255, 0, 0, 255), "tinted m0r texture");
+    let (r, g, b, _) = image.pixel(150, 271);
+    assert!(g > 200 && r < 50 && b < 50, "line: {:?}", (r, g, b));
+}
+
+#[test]
+fn cropping_shows_only_that_area() {
+    let crop = WorldRect { min_x: 2000.0, max_x: 3000.0, min_z: 2000.0, max_z: 2500.0 };
+    let image = render(&square_map(), &[elms(0, (2500, 0, 2500), 10)], &[], 200, Some(crop), &load);
+
+    assert_eq!((image.width, image.height), (200, 100));
+    assert_eq!(image.pixel(100, 99), (255, 255, 255, 255));
+    assert_eq!(image.pixel(2, 2), (0, 0, 255, 255));
+}
+
+#[test]
+fn shapes_are_drawn_from_their_bitmaps() {
+    assert_eq!(m0r_bitmap_path(&M0rTexture::Known(M0rIcon::Circle)), "icons/m0r/circle.png");
+    assert_eq!(m0r_bitmap_path(&M0rTexture::Known(M0rIcon::SharkPog)), "icons/m0r/sharkpog.png");
+}
+
+#[test]
+fn images_round_trip_through_png() {
+    let mut image = Image::new(3, 2, (10, 20, 30, 255));
+    image.pixels[4..8].copy_from_slice(&[200, 100, 0, 128]);
+    assert_eq!(Image::from_png(&image.to_png()).unwrap(), image);
+    assert!(Image::from_png(b"not a png").is_err());
+}
+
+/// The icons come in most colour types and bit depths; the map tiles are all RGB.
+#[test]
+fn every_icon_is_read() {
+    let mut dirs = vec![std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../static/icons")];
+    let mut read = 0;
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path.extension().is_some_and(|e| e == "png") {
+                let image = Image::from_png(&std::fs::read(&path).unwrap()).unwrap_or_else(|e| panic!("{}: {e}", path.display()));
+                assert_eq!(image.pixels.len(), image.width as usize * image.height as usize * 4, "{}", path.display());
+                read += 1;
+            }
+        }
+    }
+    assert!(read > 0);
+}
@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+
+use marker_core::marker::{
+    build_elms_string, build_m0r_string, find_best_map, lines_to_string, parse_elms_string, parse_lines_string,
+    parse_m0r_string, BreadcrumbLine, ElmMarker, ElmsIcon, M0rMarker, M0rTexture, Marker, Position3D, ALL_ELMS_ICONS,
+    ALL_M0R_ICONS,
+};
+use marker_core::zone::{bundled_zones, Zone};
+use proptest::prelude::*;
+
+fn zones() -> &'static [Zone] {
+    bundled_zones()
+}
+
+fn expected_map_id(p: Position3D, zone: &Zone) -> u16 {
+    find_best_map(p.x, p.y, p.z, zone).map_or(0, |m| m.map_id)
+}
+
+/// A position inside one of the zone's maps. Y is allowed to go negative so
+/// that the minimum offsets written by the builders do too.
+fn position_in(zone: &'static Zone) -> impl Strategy<Value = Position3D> {
+    (0..zone.maps.len()).prop_flat_map(move |i| {
+        let s = &zone.maps[i].scale_data;
+        (s.min_x as i32..=s.max_x as i32, -5000..60000i32, s.min_z as i32..=s.max_z as i32)
+            .prop_map(|(x, y, z)| Position3D { x, y, z })
+    })
+}
+
+fn zone_strategy() -> impl Strategy<Value = &'static Zone> {
+    (0..zones().len()).prop_map(|i| &zones()[i])
+}
+
+fn elms_marker(zone: &'static Zone) -> impl Strategy<Value = Marker> {
+    // Unknown is written as 14 and comes back as MarkerLightBlue.
+    let icons: Vec<ElmsIcon> = ALL_ELMS_ICONS.iter().copied().filter(|i| *i != ElmsIcon::Unknown).collect();
+    (position_in(zone), prop::sample::select(icons)).prop_map(move |(position, icon)| {
+        Marker::Elms(ElmMarker { position, icon, size: 1, active: true, id: 0, map_id: expected_map_id(position, zone) })
+    })
+}
+
+fn m0r_texture() -> impl Strategy<Value = M0rTexture> {
+    prop_oneof![
+        prop::sample::select(ALL_M0R_ICONS.to_vec()),
+        "esoui/art/[a-z_/]{1,20}\\.dds".prop_map(M0rTexture::Unknown),
+    ]
+}
+
+fn m0r_marker(zone: &'static Zone) -> impl Strategy<Value = Marker> {
+    (
+        position_in(zone),
+        m0r_texture(),
+        // Every character the m0r format has to escape, plus some non-ASCII.
+        prop::option::of("[a-zA-Z0-9 :,\\];>\n!?éü]{1,12}"),
+        (0..=100u8).prop_map(|n| n as f32 / 10.0),
+        any::<(u8, u8, u8, u8)>(),
+        prop::option::of(any::<(i8, i16)>()),
+    )
+        .prop_map(move |(position, background_texture, text, size, colour, orientation)| {
+            Marker::M0r(M0rMarker {
+                position,
+                background_texture,
+                text,
+                size,
+                colour,
+                orientation,
+                active: true,
+                id: 0,
+                map_id: expected_map_id(position, zone),
+            })
+        })
+}
+
+fn breadcrumb_line(zone: &'static Zone) -> impl Strategy<Value = BreadcrumbLine> {
+    (position_in(zone), position_in(zone), any::<(u8, u8, u8)>()).prop_map(move |(position1, position2, (r, g, b))| {
+        let mid = Position3D {
+            x: (position1.x + position2.x) / 2,
+            y: (position1.y + position2.y) / 2,
+            z: (position1.z + position2.z) / 2,
+        };
+        BreadcrumbLine { position1, position2, active: true, colour: (r, g, b, 255), id: 0, map_id: expected_map_id(mid, zone) }
+    })
+}
+
+fn by_zone<T: std::fmt::Debug, S: Strategy<Value = T>>(
+    item: impl Fn(&'static Zone) -> S + Clone + 'static,
+) -> impl Strategy<Value = HashMap<u16, Vec<T>>> {
+    prop::collection::vec(
+        zone_strategy().prop_flat_map(move |zone| (Just(zone.id), prop::collection::vec(item(zone), 1..16))),
+        1..4,
+    )
+    .prop_map(|entries| {
+        let mut map: HashMap<u16, Vec<T>> = HashMap::new();
+        for (zone_id, items) in entries {
+            map.entry(zone_id).or_default().extend(items);
+        }
+        map
+    })
+}
+
+/// The Elms parser drops repeated markers, so compare against the first of each.
+fn dedupe(markers: &HashMap<u16, Vec<Marker>>) -> HashMap<u16, Vec<Marker>> {
+    markers
+        .iter()
+        .map(|(zone, ms)| {
+            let mut unique: Vec<Marker> = Vec::new();
+            for m in ms {
+                if !unique.contains(m) {
+                    unique.push(m.clone());
+                }
+            }
+            (*zone, unique)
+        })
+        .collect()
+}
+
+proptest! {
+    #[test]
+    fn elms_markers_round_trip(markers in by_zone(elms_marker)) {
+        let built = build_elms_string(&markers);
+        let parsed = parse_elms_string(&built, zones().to_vec());
+
+        prop_assert!(parsed.diagnostics.is_empty(), "{:?}", parsed.diagnostics);
+        prop_assert_eq!(&parsed.data, &dedupe(&markers));
+        prop_assert_eq!(build_elms_string(&parsed.data), built);
+    }
+
+    #[test]
+    fn m0r_markers_round_trip(markers in by_zone(m0r_marker)) {
+        let built = build_m0r_string(&markers, 1_700_000_000);
+        let parsed = parse_m0r_string(&built, zones().to_vec());
+
+        prop_assert!(parsed.diagnostics.is_empty(), "{:?}", parsed.diagnostics);
+        prop_assert_eq!(&parsed.data, &markers);
+        prop_assert_eq!(build_m0r_string(&parsed.data, 1_700_000_000), built);
+    }
+
+    #[test]
+    fn breadcrumb_lines_round_trip(lines in by_zone(breadcrumb_line)) {
+        let built = lines_to_string(&lines);
+        let parsed = parse_lines_string(&built, zones().to_vec());
+
+        prop_assert!(parsed.diagnostics.is_empty(), "{:?}", parsed.diagnostics);
+        prop_assert_eq!(&parsed.data, &lines);
+        prop_assert_eq!(lines_to_string(&parsed.data), built);
+    }
+
+    #[test]
+    fn combined_string_round_trips(
+        m0r in by_zone(m0r_marker),
+        elms in by_zone(elms_marker),
+        lines in by_zone(breadcrumb_line),
+    ) {
+        let combined = format!(
+            "{}\n{}\n{}",
+            lines_to_string(&lines),
+            build_m0r_string(&m0r, 1_700_000_000),
+            build_elms_string(&elms),
+        );
+
+        let parsed_m0r = parse_m0r_string(&combined, zones().to_vec());
+        let parsed_elms = parse_elms_string(&combined, zones().to_vec());
+        let parsed_lines = parse_lines_string(&combined, zones().to_vec());
+
+        prop_assert!(parsed_m0r.diagnostics.is_empty(), "{:?}", parsed_m0r.diagnostics);
+        prop_assert!(parsed_elms.diagnostics.is_empty(), "{:?}", parsed_elms.diagnostics);
+        prop_assert!(parsed_lines.diagnostics.is_empty(), "{:?}", parsed_lines.diagnostics);
+        prop_assert_eq!(parsed_m0r.data, m0r);
+        prop_assert_eq!(parsed_elms.data, dedupe(&elms));
+        prop_assert_eq!(parsed_lines.data, lines);
+    }
+}
+
+#[test]
+fn negative_m0r_minimums_are_not_clamped() {
+    let zone = zones().iter().find(|z| z.id == 975).unwrap();
+    let position = Position3D { x: -3000, y: -250, z: -13000 };
+    let marker = M0rMarker {
+        position,
+        background_texture: M0rTexture::Unknown("esoui/art/icons/x.dds".into()),
+        text: None,
+        size: 1.0,
+        colour: (255, 255, 255, 255),
+        orientation: None,
+        active: true,
+        id: 0,
+        map_id: expected_map_id(position, zone),
+    };
+    let markers = HashMap::from([(975, vec![Marker::M0r(marker)])]);
+
+    let built = build_m0r_string(&markers, 0);
+    assert!(built.contains("]-bb8:-fa:-32c8]"), "{built}");
+    assert_eq!(parse_m0r_string(&built, zones().to_vec()).data, markers);
+}
+
+#[test]
+fn breadcrumb_colours_are_written_without_alpha() {
+    let zone = zones().iter().find(|z| z.id == 1263).unwrap();
+    let (position1, position2) = (Position3D { x: 92500, y: 35000, z: 95000 }, Position3D { x: 93000, y: 35000, z: 95500 });
+    let line = BreadcrumbLine {
+        position1,
+        position2,
+        active: true,
+        colour: (0, 128, 255, 96),
+        id: 0,
+        map_id: expected_map_id(Position3D { x: 92750, y: 35000, z: 95250 }, zone),
+    };
+    let lines = HashMap::from([(1263, vec![line])]);
+
+    let built = lines_to_string(&lines);
+    assert!(built.contains(";1;80FF;"), "{built}");
+    let parsed = parse_lines_string(&built, zones().to_vec());
+    assert!(parsed.diagnostics.is_empty(), "{:?}", parsed.diagnostics);
+    assert_eq!(parsed.data[&1263][0].colour, (0, 128, 255, 255));
+}
+
+#[test]
+fn inactive_breadcrumb_lines_are_left_out() {
+    let line = |active, x| BreadcrumbLine {
+        position1: Position3D { x, y: 35000, z: 95000 },
+        position2: Position3D { x: x + 500, y: 35000, z: 95500 },
+        active,
+        colour: (255, 0, 0, 255),
+        id: 0,
+        map_id: 0,
+    };
+    let lines = HashMap::from([(1263, vec![line(true, 92500), line(false, 93500)])]);
+
+    let parsed = parse_lines_string(&lines_to_string(&lines), zones().to_vec());
+    assert_eq!(parsed.data[&1263].len(), 1);
+    assert_eq!(parsed.data[&1263][0].position1.x, 92500);
+    assert_eq!(lines_to_string(&HashMap::from([(1263, vec![line(false, 92500)])])), "");
+}
@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+
+use marker_core::diagnostic::Severity;
+use marker_core::marker::{get_marker_id, get_marker_position, parse_combined_string, ElmsIcon, Marker};
+use marker_core::savedvars::{addons_in, build_saved_variables, parse_saved_variables, patch_saved_variables, Addon, SavedVariablesError};
+use marker_core::zone::populate_zone_data;
+
+const M0R: &str = "<1263]1700000000]1694c:88b8:17318]]]]ff0000:1]^1:1]0:0:0:Tank>";
+
+fn elms_file() -> String {
+    r#"ElmsMarkers_SavedVariables =
+{
+    ["Default"] = 
+    {
+        ["@Someone"] = 
+        {
+            ["$AccountWide"] = 
+            {
+                ["selectedIcon"] = 3,
+                ["positions"] = 
+                {
+                    [1263] = 
+                    {
+                        [1] = 
+                        {
+                            ["x"] = 92500,
+                            ["y"] = 35000,
+                            ["z"] = 95000,
+                            ["iconKey"] = 2,
+                        },
+                        [2] = { 92600, 35000, 95100, 12 },
+                        [3] = "not a position",
+                    },
+                },
+                ["version"] = 1,
+            },
+        },
+    },
+}
+"#.to_string()
+}
+
+fn m0r_file() -> String {
+    format!("M0RMarkers_SavedVariables =\n{{\n    [\"Default\"] = \n    {{\n        [\"@Someone\"] = \n        {{\n            [\"$AccountWide\"] = \n            {{\n                [\"zones\"] = \n                {{\n                    [1263] = \"{}\",\n                }},\n            }},\n        }},\n    }},\n}}\n", M0R)
+}
+
+#[test]
+fn both_addons_are_read_into_zones() {
+    let text = format!("{}{}", elms_file(), m0r_file());
+    assert_eq!(addons_in(&text), [Addon::Elms, Addon::M0r]);
+    let parsed = parse_saved_variables(&text, populate_zone_data());
+
+    let markers = &parsed.data[&1263];
+    assert_eq!(markers.iter().map(get_marker_id).collect::<Vec<_>>(), [0, 1, 2], "numbered per zone, M0R first");
+    assert!(matches!(&markers[0], Marker::M0r(m) if m.text.as_deref() == Some("Tank")));
+    assert!(matches!(&markers[1], Marker::Elms(m) if m.icon == ElmsIcon::from(2)));
+    assert!(matches!(&markers[2], Marker::Elms(m) if m.icon == ElmsIcon::from(12) && m.position.x == 92600));
+
+    let errors: Vec<_> = parsed.diagnostics.iter().filter(|d| d.severity == Severity::Error).collect();
+    assert_eq!(errors.len(), 1, "{:?}", parsed.diagnostics);
+    assert_eq!(&text[errors[0].span.clone()], "\"not a position\"");
+}
+
+#[test]
+fn files_read_like_the_same_markers_pasted() {
+    let parsed = parse_saved_variables(&elms_file(), populate_zone_data());
+    let pasted = parse_combined_string("/1263//92500,35000,95000,2//1263//92600,35000,95100,12/", populate_zone_data());
+    assert_eq!(format!("{:?}", parsed.data), format!("{:?}", pasted.data.0));
+}
+
+#[test]
+fn patching_rewrites_only_the_zone_tables() {
+    let text = elms_file();
+    let mut markers = parse_saved_variables(&text, populate_zone_data()).data;
+    markers.get_mut(&1263).unwrap().remove(0);
+    markers.insert(636, parse_combined_string("/636//82625,15000,69525,1/", populate_zone_data()).data.0[&636].clone());
+
+    let patched = patch_saved_variables(&text, &markers, 1700000000).unwrap();
+    assert!(patched.contains("                [\"selectedIcon\"] = 3,\n                [\"positions\"] = \n                {\n                    [636] = \n"), "{patched}");
+    assert!(patched.ends_with("                },\n                [\"version\"] = 1,\n            },\n        },\n    },\n}\n"), "{patched}");
+
+    let back = parse_saved_variables(&patched, populate_zone_data());
+    assert!(back.diagnostics.is_empty(), "{:?}", back.diagnostics);
+    assert_eq!(back.data[&1263].iter().map(get_marker_position).map(|p| p.x).collect::<Vec<_>>(), [92600]);
+    assert_eq!(back.data[&636].len(), 1);
+}
+
+#[test]
+fn each_addon_is_written_its_own_markers() {
+    let text = format!("{}{}", elms_file(), m0r_file());
+    let markers = parse_saved_variables(&text, populate_zone_data()).data;
+
+    let patched = patch_saved_variables(&m0r_file(), &markers, 1700000000).unwrap();
+    assert!(patched.contains(&format!("[1263] = \"{}\"", M0R)), "{patched}");
+    assert!(!patched.contains("positions"));
+
+    let built = build_saved_variables(Addon::Elms, "@Someone", &markers, 1700000000);
+    assert!(built.starts_with("ElmsMarkers_SavedVariables =\n{\n    [\"Default\"] = \n    {\n        [\"@Someone\"] = "), "{built}");
+    let back = parse_saved_variables(&built, populate_zone_data()).data;
+    let elms_only: HashMap<u16, Vec<Marker>> = HashMap::from([(1263, markers[&1263].iter().filter(|m| Addon::Elms.holds(m)).cloned().collect())]);
+    assert_eq!(back, elms_only);
+}
+
+#[test]
+fn files_without_marker_tables_are_refused() {
+    let markers = HashMap::new();
+    assert_eq!(patch_saved_variables("Other_SavedVariables = {}", &markers, 0), Err(SavedVariablesError::NoAddon));
+    assert_eq!(patch_saved_variables("M0RMarkers_SavedVariables = { version = 1 }", &markers, 0), Err(SavedVariablesError::NoZones(Addon::M0r)));
+    assert!(matches!(patch_saved_variables("ElmsMarkers_SavedVariables = {", &markers, 0), Err(SavedVariablesError::Lua(_))));
+
+    let parsed = parse_saved_variables("ElmsMarkers_SavedVariables = {", populate_zone_data());
+    assert!(parsed.has_errors() && parsed.data.is_empty());
+}
+
+#[test]
+fn only_account_wide_markers_are_read_and_written() {
+    let character = |id: &str, x: i64| format!("            [\"{}\"] = \n            {{\n                [\"positions\"] = \n                {{\n                    [1263] = \n                    {{\n                        [1] = {{ {}, 35000, 95000, 1 }},\n                    }},\n                }},\n            }},\n", id, x);
+    let first = character("8796093022215001", 92700);
+    let second = character("8796093022215002", 92800);
+    let text = elms_file().replacen("            [\"$AccountWide\"] = ", &format!("{}{}            [\"$AccountWide\"] = ", first, second), 1);
+
+    let parsed = parse_saved_variables(&text, populate_zone_data());
+    assert_eq!(parsed.data[&1263].iter().map(get_marker_position).map(|p| p.x).collect::<Vec<_>>(), [92500, 92600]);
+    let warnings: Vec<_> = parsed.diagnostics.iter().filter(|d| d.severity == Severity::Warning).collect();
+    assert_eq!(warnings.len(), 2, "{:?}", parsed.diagnostics);
+    assert!(first.contains(&text[warnings[0].span.clone()]) && second.contains(&text[warnings[1].span.clone()]));
+
+    // Writing the markers back neither copies them into each character nor
+    // theirs into the account, so reading again finds the same two.
+    let patched = patch_saved_variables(&text, &parsed.data, 1700000000).unwrap();
+    assert!(patched.starts_with(&text[..text.find("            [\"$AccountWide\"]").unwrap()]), "{patched}");
+    let back = parse_saved_variables(&patched, populate_zone_data());
+    assert_eq!(back.data, parsed.data);
+}
@@ -0,0 +1,104 @@
+use marker_core::marker::{get_marker_id, parse_combined_string};
+use marker_core::project::{Annotations, Metadata, Project};
+use marker_core::saves::{Autosave, SaveBundle, SaveError, BUNDLE_VERSION};
+use marker_core::zone::bundled_zones;
+
+fn bundle() -> SaveBundle {
+    let mut bundle = SaveBundle::default();
+    bundle.save("Rockgrove HM prog", "/1263//92500,35000,95000,1/", None, 100).unwrap();
+    bundle.save("Cloudrest +3", "/1051//0,0,0,1/", None, 200).unwrap();
+    bundle
+}
+
+#[test]
+fn saving_an_existing_name_replaces_it() {
+    let mut bundle = bundle();
+    bundle.save("Cloudrest +3", "new", None, 300).unwrap();
+    assert_eq!(bundle.saves.len(), 2);
+    assert_eq!(bundle.get("Cloudrest +3").unwrap().data, "new");
+    assert_eq!(bundle.save("   ", "x", None, 0), Err(SaveError::EmptyName));
+}
+
+#[test]
+fn rename_refuses_names_in_use() {
+    let mut bundle = bundle();
+    assert_eq!(bundle.rename("Cloudrest +3", "Rockgrove HM prog"), Err(SaveError::NameTaken("Rockgrove HM prog".into())));
+    assert_eq!(bundle.rename("Missing", "Other"), Err(SaveError::NotFound("Missing".into())));
+
+    bundle.rename("Cloudrest +3", "Cloudrest +3 (old)").unwrap();
+    assert!(bundle.get("Cloudrest +3").is_none());
+    assert_eq!(bundle.get("Cloudrest +3 (old)").unwrap().data, "/1051//0,0,0,1/");
+}
+
+#[test]
+fn duplicates_get_numbered_names() {
+    let mut bundle = bundle();
+    assert_eq!(bundle.duplicate("Cloudrest +3", 300).unwrap(), "Cloudrest +3 (copy)");
+    assert_eq!(bundle.duplicate("Cloudrest +3", 400).unwrap(), "Cloudrest +3 (copy 2)");
+    assert_eq!(bundle.get("Cloudrest +3 (copy 2)").unwrap().data, "/1051//0,0,0,1/");
+}
+
+#[test]
+fn delete_removes_only_that_layout() {
+    let mut bundle = bundle();
+    bundle.delete("Rockgrove HM prog").unwrap();
+    assert_eq!(bundle.saves.len(), 1);
+    assert!(bundle.delete("Rockgrove HM prog").is_err());
+}
+
+#[test]
+fn bundles_round_trip_through_json() {
+    let bundle = bundle();
+    let json = bundle.to_json();
+    assert!(json.contains(&format!("\"version\": {}", BUNDLE_VERSION)));
+    assert_eq!(SaveBundle::from_json(&json).unwrap(), bundle);
+}
+
+#[test]
+fn newer_bundles_are_refused() {
+    let json = r#"{"version": 99, "saves": []}"#;
+    assert_eq!(SaveBundle::from_json(json), Err(SaveError::UnsupportedVersion(99)));
+    assert!(matches!(SaveBundle::from_json("not json"), Err(SaveError::Json(_))));
+}
+
+fn project(data: &str) -> Project {
+    let (markers, lines) = parse_combined_string(data, bundled_zones().to_vec()).data;
+    let mut annotations = Annotations::default();
+    let (&zone_id, zone_markers) = markers.iter().next().unwrap();
+    annotations.markers.entry(zone_id).or_default().insert(get_marker_id(&zone_markers[0]), Metadata { name: "Tank".into(), ..Metadata::default() });
+    Project::new(&markers, &lines, &annotations)
+}
+
+#[test]
+fn layouts_keep_their_project() {
+    let data = "/1263//92500,35000,95000,1/";
+    let mut bundle = bundle();
+    bundle.save("Named", data, Some(&project(data)), 300).unwrap();
+    bundle.duplicate("Named", 400).unwrap();
+
+    let read = SaveBundle::from_json(&bundle.to_json()).unwrap();
+    assert_eq!(read.get("Named").unwrap().project, Some(project(data)));
+    assert_eq!(read.get("Named (copy)").unwrap().project, Some(project(data)));
+
+    let autosave = Autosave { data: data.into(), project: Some(project(data)), saved_at: 500 };
+    assert_eq!(Autosave::from_json(&autosave.to_json()), Some(autosave));
+}
+
+#[test]
+fn string_only_entries_still_load() {
+    let json = r#"{"version": 1, "saves": [{"name": "Old", "data": "/1051//0,0,0,1/", "saved_at": 100}]}"#;
+    let bundle = SaveBundle::from_json(json).unwrap();
+    assert_eq!(bundle.get("Old").unwrap().data, "/1051//0,0,0,1/");
+    assert_eq!(bundle.get("Old").unwrap().project, None);
+
+    let autosave = Autosave::from_json(r#"{"data": "/1051//0,0,0,1/", "saved_at": 100}"#).unwrap();
+    assert_eq!(autosave.project, None);
+}
+
+#[test]
+fn newer_projects_fall_back_to_the_string() {
+    let json = r#"{"data": "/1051//0,0,0,1/", "project": {"version": 99, "zones": "changed"}, "saved_at": 100}"#;
+    let autosave = Autosave::from_json(json).unwrap();
+    assert_eq!(autosave.data, "/1051//0,0,0,1/");
+    assert_eq!(autosave.project, None);
+}
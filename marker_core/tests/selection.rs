@@ -0,0 +1,63 @@
+use std::collections::HashSet;
+
+use marker_core::marker::{get_marker_active, set_marker_active, BreadcrumbLine, ElmsIcon, M0rMarker, Marker};
+use marker_core::selection::{lines_in, markers_in, BulkEdit, WorldRect};
+
+mod common;
+use common::{elms, line, m0r, m0r_marker};
+
+fn ids(ids: &[u16]) -> HashSet<u16> {
+    ids.iter().copied().collect()
+}
+
+#[test]
+fn boxes_pick_up_what_is_inside_on_the_map() {
+    let rect = WorldRect::from_corners((2000.0, 2000.0), (0.0, 0.0));
+    let mut hidden = elms(3, (500, 35000, 500), 1354);
+    set_marker_active(&mut hidden, false);
+    let mut elsewhere = m0r(4, (500, 35000, 500), 1354);
+    if let Marker::M0r(m) = &mut elsewhere {
+        m.map_id = 1;
+    }
+    let markers = [elms(0, (0, 35000, 0), 1354), m0r(1, (2000, 35000, 1500), 1354), elms(2, (2001, 35000, 500), 1354), hidden, elsewhere];
+    assert_eq!(markers_in(&markers, 1354, &rect), ids(&[0, 1]));
+
+    let lines = [line(0, (100, 100), (1900, 1900), 35000, 1354), line(1, (100, 100), (2500, 100), 35000, 1354)];
+    assert_eq!(lines_in(&lines, 1354, &rect), ids(&[0]));
+}
+
+#[test]
+fn bulk_edits_only_touch_what_they_apply_to() {
+    let translucent = |id| Marker::M0r(M0rMarker { colour: (255, 255, 255, 128), ..m0r_marker(id, (0, 35000, 0), 1354) });
+    let red = |id| BreadcrumbLine { colour: (255, 0, 0, 200), ..line(id, (0, 0), (1, 1), 35000, 1354) };
+    let mut markers = vec![elms(0, (0, 35000, 0), 1354), translucent(1), translucent(2)];
+    let mut lines = vec![red(0), red(1)];
+    let selected = ids(&[0, 1]);
+
+    let applied = BulkEdit::Colour((0, 255, 0)).apply(&mut markers, &mut lines, &selected, &ids(&[1]));
+    assert_eq!(applied, 2);
+    let Marker::M0r(changed) = &markers[1] else { panic!() };
+    assert_eq!(changed.colour, (0, 255, 0, 128));
+    let Marker::M0r(untouched) = &markers[2] else { panic!() };
+    assert_eq!(untouched.colour, (255, 255, 255, 128));
+    assert_eq!(lines[0].colour, (255, 0, 0, 200));
+    assert_eq!(lines[1].colour, (0, 255, 0, 200));
+
+    assert_eq!(BulkEdit::Size(3.5).apply(&mut markers, &mut lines, &selected, &HashSet::new()), 1);
+    assert_eq!(BulkEdit::ElmsIcon(ElmsIcon::Num(7)).apply(&mut markers, &mut lines, &selected, &HashSet::new()), 1);
+    let Marker::Elms(icon) = &markers[0] else { panic!() };
+    assert_eq!(icon.icon, ElmsIcon::Num(7));
+
+    assert_eq!(BulkEdit::SetActive(false).apply(&mut markers, &mut lines, &selected, &ids(&[0])), 3);
+    assert_eq!(markers.iter().map(get_marker_active).collect::<Vec<_>>(), [false, false, true]);
+    assert!(!lines[0].active && lines[1].active);
+}
+
+#[test]
+fn bulk_delete_removes_the_selection() {
+    let mut markers = vec![elms(0, (0, 35000, 0), 1354), m0r(1, (0, 35000, 0), 1354), m0r(2, (0, 35000, 0), 1354)];
+    let mut lines = vec![line(0, (0, 0), (1, 1), 35000, 1354), line(1, (0, 0), (1, 1), 35000, 1354)];
+    assert_eq!(BulkEdit::Delete.apply(&mut markers, &mut lines, &ids(&[0, 2, 9]), &ids(&[1])), 3);
+    assert_eq!(markers.len(), 1);
+    assert_eq!(lines.iter().map(|l| l.id).collect::<Vec<_>>(), [0]);
+}
@@ -0,0 +1,34 @@
+use marker_core::share::{decode_share, encode_share, ShareError, ShareState};
+
+fn state() -> ShareState {
+    ShareState {
+        data: include_str!("fixtures/edge/elms.txt").lines().filter(|l| !l.starts_with('#')).collect::<Vec<_>>().join("\n"),
+        zone_id: 1263,
+        map_id: 1354,
+        zoom: 2.5,
+        pan: (-0.25, -0.75),
+    }
+}
+
+#[test]
+fn share_links_round_trip() {
+    let state = state();
+    let encoded = encode_share(&state);
+    assert!(encoded.starts_with("v1."));
+    assert!(encoded[3..].chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'), "{encoded}");
+    assert_eq!(decode_share(&encoded).unwrap(), state);
+    assert_eq!(decode_share(&format!("#{encoded}")).unwrap(), state);
+}
+
+#[test]
+fn share_links_are_smaller_than_the_string() {
+    let state = state();
+    assert!(encode_share(&state).len() < state.data.len() / 2);
+}
+
+#[test]
+fn bad_share_links_are_rejected() {
+    assert_eq!(decode_share("v2.abc"), Err(ShareError::UnknownVersion));
+    assert_eq!(decode_share("v1.!!!"), Err(ShareError::Base64));
+    assert_eq!(decode_share("v1.AAAA"), Err(ShareError::Compression));
+}
@@ -0,0 +1,30 @@
+use marker_core::breadcrumbs::polyline;
+use marker_core::marker::{ElmMarker, ElmsIcon, Marker, Position3D};
+use marker_core::snap::{grid_lines, Snap, Snapped};
+
+#[test]
+fn points_snap_to_the_world_grid() {
+    let snap = Snap { grid: Some(500.0), magnet: false };
+    assert_eq!(snap.point(123457.0, 98740.4, &[], &[], 1, 50.0), Snapped { x: 123500, z: 98500, y: None });
+    assert_eq!(snap.point(-260.0, 249.0, &[], &[], 1, 50.0), Snapped { x: -500, z: 0, y: None });
+    assert_eq!(Snap::default().point(123457.4, 98740.6, &[], &[], 1, 50.0), Snapped { x: 123457, z: 98741, y: None });
+}
+
+#[test]
+fn the_magnet_wins_over_the_grid() {
+    let markers = [Marker::Elms(ElmMarker { position: Position3D { x: 1030, y: 40, z: 990 }, icon: ElmsIcon::Num(1), size: 1, active: true, id: 0, map_id: 1 })];
+    let lines = polyline(&[Position3D { x: 0, y: 7, z: 0 }, Position3D { x: 300, y: 7, z: 0 }], (255, 255, 255, 255), 1, 0);
+    let snap = Snap { grid: Some(100.0), magnet: true };
+
+    assert_eq!(snap.point(1000.0, 1000.0, &markers, &lines, 1, 50.0), Snapped { x: 1030, z: 990, y: Some(40) });
+    assert_eq!(snap.point(290.0, 20.0, &markers, &lines, 1, 50.0), Snapped { x: 300, z: 0, y: Some(7) });
+    // Nothing in reach, so the grid applies.
+    assert_eq!(snap.point(640.0, 640.0, &markers, &lines, 1, 50.0), Snapped { x: 600, z: 600, y: None });
+}
+
+#[test]
+fn grid_lines_cover_the_range_unless_too_dense() {
+    assert_eq!(grid_lines(-120.0, 260.0, 100.0), [-100.0, 0.0, 100.0, 200.0]);
+    assert!(grid_lines(0.0, 100000.0, 100.0).is_empty());
+    assert!(grid_lines(0.0, 1000.0, 0.0).is_empty());
+}
@@ -0,0 +1,83 @@
+use std::collections::HashSet;
+
+use marker_core::marker::{get_marker_position, BreadcrumbLine, M0rMarker, Marker, Position3D};
+use marker_core::transform::{centroid, Transform};
+
+mod common;
+use common::{elms, m0r_marker};
+
+fn position(marker: &Marker) -> (i32, i32) {
+    let p = get_marker_position(marker);
+    (p.x, p.z)
+}
+
+fn orientation(marker: &Marker) -> Option<(i8, i16)> {
+    match marker {
+        Marker::M0r(m) => m.orientation,
+        Marker::Elms(_) => None,
+    }
+}
+
+fn all() -> HashSet<u16> {
+    (0..10).collect()
+}
+
+#[test]
+fn rotation_is_clockwise_on_the_map_and_turns_yaw() {
+    let rotate = Transform::Rotate { pivot: (1000.0, 1000.0), degrees: 90.0 };
+    // North of the pivot ends up east of it.
+    let (x, z) = rotate.point(1000.0, 0.0);
+    assert_eq!((x.round(), z.round()), (2000.0, 1000.0));
+    // Facing north (0) turns to face east (270).
+    assert_eq!(rotate.yaw(0), 270);
+    assert_eq!(rotate.yaw(90), 0);
+
+    let mut markers = vec![Marker::M0r(M0rMarker { orientation: Some((-90, 0)), ..m0r_marker(0, (1000, 35000, 0), 1354) }), elms(1, (1000, 35000, 1000), 1354), elms(2, (0, 35000, 0), 1354)];
+    rotate.apply(&mut markers, &mut [], &[0, 1].into(), &HashSet::new());
+    assert_eq!(position(&markers[0]), (2000, 1000));
+    assert_eq!(orientation(&markers[0]), Some((-90, 270)));
+    assert_eq!(position(&markers[1]), (1000, 1000));
+    assert_eq!(position(&markers[2]), (0, 0));
+}
+
+#[test]
+fn mirroring_reflects_positions_and_yaw() {
+    // A north-south axis swaps east and west.
+    let mirror = Transform::Mirror { point: (500.0, 0.0), degrees: 90.0 };
+    let (x, z) = mirror.point(200.0, 300.0);
+    assert_eq!((x.round(), z.round()), (800.0, 300.0));
+    assert_eq!(mirror.yaw(90), 270);
+    assert_eq!(mirror.yaw(0), 0);
+
+    // An east-west axis swaps north and south.
+    let mirror = Transform::Mirror { point: (0.0, 500.0), degrees: 0.0 };
+    assert_eq!(mirror.point(200.0, 300.0), (200.0, 700.0));
+    assert_eq!(mirror.yaw(0), 180);
+    assert_eq!(mirror.yaw(45), 135);
+}
+
+#[test]
+fn translate_and_scale_move_lines_too() {
+    let mut markers = vec![Marker::M0r(M0rMarker { orientation: Some((-90, 45)), ..m0r_marker(0, (0, 35000, 0), 1354) })];
+    let mut lines = vec![BreadcrumbLine {
+        position1: Position3D { x: 100, y: 5, z: 100 },
+        position2: Position3D { x: 300, y: 6, z: 100 },
+        active: true,
+        colour: (255, 0, 0, 255),
+        id: 0,
+        map_id: 1354,
+    }];
+    assert_eq!(centroid(&markers, &lines, &all(), &all()), Some((400.0 / 3.0, 200.0 / 3.0)));
+
+    Transform::Translate { dx: 50.0, dz: -25.0 }.apply(&mut markers, &mut lines, &all(), &all());
+    assert_eq!(position(&markers[0]), (50, -25));
+    assert_eq!(orientation(&markers[0]), Some((-90, 45)));
+    assert_eq!((lines[0].position1.x, lines[0].position1.z), (150, 75));
+
+    Transform::Scale { centre: (250.0, 75.0), factor: 2.0 }.apply(&mut markers, &mut lines, &HashSet::new(), &all());
+    assert_eq!((lines[0].position1.x, lines[0].position2.x), (50, 450));
+    assert_eq!((lines[0].position1.y, lines[0].position2.y), (5, 6));
+    assert_eq!(position(&markers[0]), (50, -25));
+
+    assert_eq!(centroid(&markers, &lines, &HashSet::new(), &HashSet::new()), None);
+}
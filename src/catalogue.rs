@@ -0,0 +1,108 @@
+//! Fetching the zone catalogue at startup, and the panel for override files.
+
+use marker_core::catalogue::{load_zones, BUNDLED_CATALOGUE};
+use marker_core::zone::{populate_zone_data, Zone};
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+use web_sys::{HtmlInputElement, Response};
+use yew::prelude::*;
+
+use crate::storage;
+
+/// Catalogue files served alongside the editor.
+pub struct Sources {
+    pub base: String,
+    /// `static/zones.local.json`, for anyone hosting their own copy
+    pub local: Option<String>,
+}
+
+async fn fetch_json(url: &str) -> Option<String> {
+    let window = web_sys::window()?;
+    let response: Response = JsFuture::from(window.fetch_with_str(url)).await.ok()?.dyn_into().ok()?;
+    // Some static servers answer a missing file with the index page.
+    let is_json = response.headers().get("content-type").ok().flatten().is_some_and(|t| t.contains("json"));
+    if !response.ok() || !is_json {
+        return None;
+    }
+    JsFuture::from(response.text().ok()?).await.ok()?.as_string()
+}
+
+/// Falls back to the copy built into the editor if `static/zones.json` can't
+/// be fetched.
+pub async fn fetch_sources() -> Sources {
+    Sources {
+        base: fetch_json("static/zones.json").await.unwrap_or_else(|| BUNDLED_CATALOGUE.to_string()),
+        local: fetch_json("static/zones.local.json").await,
+    }
+}
+
+/// The zones from `sources` with the user's override file applied. If that
+/// fails validation, the errors come back with the zones from `static/zones.json`
+/// alone, or the built-in copy if even that is broken.
+pub fn build(sources: &Sources, user: Option<&(String, String)>) -> (Vec<Zone>, Vec<String>) {
+    let mut overrides = Vec::new();
+    if let Some(local) = &sources.local {
+        overrides.push(("zones.local.json", local.as_str()));
+    }
+    if let Some((name, contents)) = user {
+        overrides.push((name.as_str(), contents.as_str()));
+    }
+    match load_zones(&sources.base, &overrides) {
+        Ok(zones) => (zones, Vec::new()),
+        Err(errors) => {
+            let zones = load_zones(&sources.base, &[]).unwrap_or_else(|_| populate_zone_data());
+            (zones, errors)
+        }
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct CataloguePanelProps {
+    pub zone_count: usize,
+    pub errors: Vec<String>,
+    /// File name of the override the user loaded, if any
+    pub override_name: Option<String>,
+    /// Called with (file name, contents) for a new override, or `None` to remove it
+    pub on_override: Callback<Option<(String, String)>>,
+}
+
+#[function_component(CataloguePanel)]
+pub fn catalogue_panel(props: &CataloguePanelProps) -> Html {
+    let on_file = {
+        let on_override = props.on_override.clone();
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            let on_override = on_override.clone();
+            spawn_local(async move {
+                if let Some(file) = storage::read_file(&input).await {
+                    on_override.emit(Some(file));
+                }
+                input.set_value("");
+            });
+        })
+    };
+    let on_remove = props.on_override.reform(|_: MouseEvent| None);
+
+    html! {
+        <>
+            if !props.errors.is_empty() {
+                <ul style="list-style:none;padding:0;margin:0.5em auto;width:80%;text-align:left;font-size:0.9em;color:#ff6b6b;">
+                    { for props.errors.iter().map(|e| html! { <li>{ format!("Zone catalogue: {}", e) }</li> }) }
+                </ul>
+            }
+            <details style="width:80%;margin:0.5em auto;text-align:left;">
+                <summary style="cursor:pointer;">{ format!("Zones ({})", props.zone_count) }</summary>
+                <p style="margin:0.5em 0;font-size:0.9em;color:#ccc;">
+                    {"Add or correct zones with an override file in the same format as static/zones.json. Zones in it replace any with the same id."}
+                </p>
+                <div style="display:flex;gap:0.5em;align-items:center;">
+                    <input type="file" accept=".json,application/json" onchange={on_file} />
+                    if let Some(name) = &props.override_name {
+                        <span style="flex-grow:1;">{ format!("Using {}", name) }</span>
+                        <button onclick={on_remove}>{"Remove override"}</button>
+                    }
+                </div>
+            </details>
+        </>
+    }
+}
@@ -0,0 +1,135 @@
+//! Saving the map being shown as a PNG or SVG image.
+
+use marker_core::export::ExportOptions;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, HtmlImageElement, HtmlInputElement};
+use yew::prelude::*;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ImageFormat {
+    Png,
+    Svg,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct ExportRequest {
+    pub format: ImageFormat,
+    pub options: ExportOptions,
+    /// Whether to show only the area around the selection
+    pub crop_to_selection: bool,
+    /// Whether to leave out markers and lines on hidden layers
+    pub visible_layers_only: bool,
+}
+
+/// Draws `svg` onto a canvas and returns it as a PNG `data:` URL. Images in
+/// the SVG must be embedded, as the browser won't fetch them from here.
+pub async fn svg_to_png(svg: &str, width: u32, height: u32) -> Option<String> {
+    let document = web_sys::window()?.document()?;
+    let parts = js_sys::Array::of1(&svg.into());
+    let options = web_sys::BlobPropertyBag::new();
+    options.set_type("image/svg+xml");
+    let blob = web_sys::Blob::new_with_str_sequence_and_options(&parts, &options).ok()?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob).ok()?;
+
+    let img = HtmlImageElement::new().ok()?;
+    let loaded = js_sys::Promise::new(&mut |resolve, reject| {
+        img.set_onload(Some(&resolve));
+        img.set_onerror(Some(&reject));
+    });
+    img.set_src(&url);
+    let result = JsFuture::from(loaded).await;
+    img.set_onload(None);
+    img.set_onerror(None);
+    let _ = web_sys::Url::revoke_object_url(&url);
+    result.ok()?;
+
+    let canvas = document.create_element("canvas").ok()?.dyn_into::<HtmlCanvasElement>().ok()?;
+    canvas.set_width(width);
+    canvas.set_height(height);
+    let ctx = canvas.get_context("2d").ok()??.dyn_into::<CanvasRenderingContext2d>().ok()?;
+    ctx.draw_image_with_html_image_element_and_dw_and_dh(&img, 0.0, 0.0, width as f64, height as f64).ok()?;
+    canvas.to_data_url_with_type("image/png").ok()
+}
+
+#[derive(Properties, PartialEq)]
+pub struct ExportPanelProps {
+    pub has_selection: bool,
+    pub on_export: Callback<ExportRequest>,
+}
+
+#[function_component(ExportPanel)]
+pub fn export_panel(props: &ExportPanelProps) -> Html {
+    let size = use_state(|| 2048_u32);
+    let title = use_state(String::new);
+    let legend = use_state(|| false);
+    let crop = use_state(|| false);
+    let visible_only = use_state(|| true);
+
+    let export = |format: ImageFormat| {
+        let on_export = props.on_export.clone();
+        let options = ExportOptions {
+            size: *size,
+            title: Some(title.trim().to_string()).filter(|t| !t.is_empty()),
+            legend: *legend,
+            crop: None,
+        };
+        let crop_to_selection = *crop && props.has_selection;
+        let visible_layers_only = *visible_only;
+        Callback::from(move |_: MouseEvent| on_export.emit(ExportRequest { format, options: options.clone(), crop_to_selection, visible_layers_only }))
+    };
+    let on_size = {
+        let size = size.clone();
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            if let Ok(value) = input.value().parse() {
+                size.set(value);
+            }
+        })
+    };
+    let on_title = {
+        let title = title.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            title.set(input.value());
+        })
+    };
+    let toggle = |state: &UseStateHandle<bool>| {
+        let state = state.clone();
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            state.set(input.checked());
+        })
+    };
+
+    html! {
+        <details style="width:80%;margin:0.5em auto;text-align:left;">
+            <summary>{"Export image"}</summary>
+            <div style="display:flex;gap:0.5em;align-items:center;flex-wrap:wrap;margin-top:0.5em;">
+                <input type="text" placeholder="Title" value={(*title).clone()} oninput={on_title} />
+                <label title="Pixels across the map">
+                    {"Size "}
+                    <select onchange={on_size}>
+                        { for [1024_u32, 2048, 4096].into_iter().map(|s| html! {
+                            <option value={s.to_string()} selected={*size == s}>{ s }</option>
+                        }) }
+                    </select>
+                </label>
+                <label>
+                    <input type="checkbox" checked={*legend} onchange={toggle(&legend)} />
+                    {"Legend"}
+                </label>
+                <label title={if props.has_selection { "Only the area around the selected markers and lines" } else { "Select some markers or lines first" }}>
+                    <input type="checkbox" checked={*crop && props.has_selection} disabled={!props.has_selection} onchange={toggle(&crop)} />
+                    {"Crop to selection"}
+                </label>
+                <label title="Leave out markers and lines on hidden layers">
+                    <input type="checkbox" checked={*visible_only} onchange={toggle(&visible_only)} />
+                    {"Visible layers only"}
+                </label>
+                <button onclick={export(ImageFormat::Png)}>{"PNG"}</button>
+                <button onclick={export(ImageFormat::Svg)}>{"SVG"}</button>
+            </div>
+        </details>
+    }
+}
@@ -0,0 +1,119 @@
+//! Map tiles and marker icons, loaded once and kept between redraws, with m0r
+//! textures tinted once per colour.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use marker_core::export::{elms_icon_path, m0r_texture_path};
+use marker_core::marker::{ElmsIcon, M0rTexture};
+use wasm_bindgen::{prelude::Closure, JsCast};
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, HtmlImageElement};
+
+/// A texture and the colour it was tinted.
+type TintKey = (M0rTexture, (u8, u8, u8, u8));
+
+pub struct IconCache {
+    images: RefCell<HashMap<String, HtmlImageElement>>,
+    data_urls: RefCell<HashMap<String, String>>,
+    tinted: RefCell<HashMap<TintKey, HtmlCanvasElement>>,
+    /// Set whenever an image finishes loading, so the map gets drawn again
+    loaded: Rc<Cell<bool>>,
+    // Shared by every image rather than one closure leaked per image.
+    on_load: Closure<dyn Fn()>,
+}
+
+impl IconCache {
+    pub fn new() -> Self {
+        let loaded = Rc::new(Cell::new(false));
+        let on_load = {
+            let loaded = loaded.clone();
+            Closure::wrap(Box::new(move || loaded.set(true)) as Box<dyn Fn()>)
+        };
+        Self { images: RefCell::default(), data_urls: RefCell::default(), tinted: RefCell::default(), loaded, on_load }
+    }
+
+    /// Whether an image has loaded since this was last asked.
+    pub fn take_loaded(&self) -> bool {
+        self.loaded.replace(false)
+    }
+
+    /// The image at `src` if it has loaded. Starts loading it otherwise.
+    pub fn image(&self, src: &str) -> Option<HtmlImageElement> {
+        let mut images = self.images.borrow_mut();
+        let img = images.entry(src.to_string()).or_insert_with(|| {
+            let img = HtmlImageElement::new().unwrap();
+            img.set_onload(Some(self.on_load.as_ref().unchecked_ref()));
+            img.set_src(src);
+            img
+        });
+        // A broken image is complete too, but has nothing to draw.
+        (img.complete() && img.natural_width() > 0).then(|| img.clone())
+    }
+
+    pub fn elms(&self, icon: ElmsIcon) -> Option<HtmlImageElement> {
+        self.image(&format!("static/{}", elms_icon_path(icon)))
+    }
+
+    /// The image at `src` as a `data:` URL, for embedding in exported files,
+    /// once it has loaded.
+    pub fn data_url(&self, src: &str) -> Option<String> {
+        if let Some(url) = self.data_urls.borrow().get(src) {
+            return Some(url.clone());
+        }
+        let img = self.image(src)?;
+        let canvas = web_sys::window()?
+            .document()?
+            .create_element("canvas").ok()?
+            .dyn_into::<HtmlCanvasElement>().ok()?;
+        canvas.set_width(img.natural_width());
+        canvas.set_height(img.natural_height());
+        let ctx = canvas.get_context("2d").ok()??.dyn_into::<CanvasRenderingContext2d>().ok()?;
+        ctx.draw_image_with_html_image_element(&img, 0.0, 0.0).ok()?;
+        let url = canvas.to_data_url().ok()?;
+        self.data_urls.borrow_mut().insert(src.to_string(), url.clone());
+        Some(url)
+    }
+
+    /// `texture` filled with `colour`, at the texture's own resolution.
+    pub fn m0r(&self, texture: &M0rTexture, colour: (u8, u8, u8, u8)) -> Option<HtmlCanvasElement> {
+        let key = (texture.clone(), colour);
+        if let Some(canvas) = self.tinted.borrow().get(&key) {
+            return Some(canvas.clone());
+        }
+        let icon = self.image(&format!("static/{}", m0r_texture_path(texture)))?;
+        let (w, h) = (icon.natural_width(), icon.natural_height());
+
+        let canvas = web_sys::window()?
+            .document()?
+            .create_element("canvas").ok()?
+            .dyn_into::<HtmlCanvasElement>().ok()?;
+        canvas.set_width(w);
+        canvas.set_height(h);
+        let ctx = canvas.get_context("2d").ok()??.dyn_into::<CanvasRenderingContext2d>().ok()?;
+        let (w, h) = (w as f64, h as f64);
+        ctx.set_fill_style_str(&format!("rgba({},{},{},{})", colour.0, colour.1, colour.2, colour.3));
+        ctx.fill_rect(0.0, 0.0, w, h);
+        ctx.set_global_composite_operation("destination-in").ok()?;
+        ctx.draw_image_with_html_image_element_and_dw_and_dh(&icon, 0.0, 0.0, w, h).ok()?;
+
+        self.tinted.borrow_mut().insert(key, canvas.clone());
+        Some(canvas)
+    }
+}
+
+// There is one cache for the app, handed down as a prop.
+impl PartialEq for IconCache {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl Drop for IconCache {
+    fn drop(&mut self) {
+        // Images still loading mustn't call the closure once it's gone.
+        for img in self.images.borrow().values() {
+            img.set_onload(None);
+        }
+    }
+}
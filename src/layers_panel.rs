@@ -0,0 +1,78 @@
+//! Layers: one for each group, to show, hide, lock, fade and reorder, so
+//! alternative plans can live in one file side by side.
+
+use marker_core::project::{Layer, LayerEdit};
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct LayersPanelProps {
+    /// Every layer in use, top first
+    pub layers: Vec<Layer>,
+    /// Called with the name of the layer to change and the change
+    pub on_edit: Callback<(String, LayerEdit)>,
+}
+
+#[function_component(LayersPanel)]
+pub fn layers_panel(props: &LayersPanelProps) -> Html {
+    let count = props.layers.len();
+    let row = |(i, layer): (usize, &Layer)| {
+        let edit = |edit: LayerEdit| {
+            let (on_edit, name) = (props.on_edit.clone(), layer.name.clone());
+            Callback::from(move |_: MouseEvent| on_edit.emit((name.clone(), edit.clone())))
+        };
+        let toggle = |edit: fn(bool) -> LayerEdit| {
+            let (on_edit, name) = (props.on_edit.clone(), layer.name.clone());
+            Callback::from(move |e: Event| {
+                let input: HtmlInputElement = e.target_unchecked_into();
+                on_edit.emit((name.clone(), edit(input.checked())));
+            })
+        };
+        let on_opacity = {
+            let (on_edit, name) = (props.on_edit.clone(), layer.name.clone());
+            Callback::from(move |e: InputEvent| {
+                let input: HtmlInputElement = e.target_unchecked_into();
+                if let Ok(percent) = input.value().parse::<f64>() {
+                    on_edit.emit((name.clone(), LayerEdit::Opacity(percent / 100.0)));
+                }
+            })
+        };
+        let label = if layer.name.is_empty() { "No group".to_string() } else { layer.name.clone() };
+        html! {
+            <li style="display:flex;gap:0.5em;align-items:center;">
+                <label title="Show on the map">
+                    <input type="checkbox" checked={layer.visible} onchange={toggle(LayerEdit::Visible)} />
+                    {"Show"}
+                </label>
+                <label title="Keep it from being picked or dragged on the map">
+                    <input type="checkbox" checked={layer.locked} onchange={toggle(LayerEdit::Locked)} />
+                    {"Lock"}
+                </label>
+                <span style={if layer.name.is_empty() { "flex-grow:1;font-style:italic;" } else { "flex-grow:1;" }}>{ label }</span>
+                <input
+                    type="range"
+                    min="0"
+                    max="100"
+                    title={format!("Opacity {}%", (layer.opacity * 100.0).round())}
+                    value={(layer.opacity * 100.0).round().to_string()}
+                    oninput={on_opacity}
+                    style="width:6em;"
+                />
+                <button onclick={edit(LayerEdit::Raise)} disabled={i == 0} title="Move up">{"▲"}</button>
+                <button onclick={edit(LayerEdit::Lower)} disabled={i + 1 == count} title="Move down">{"▼"}</button>
+            </li>
+        }
+    };
+
+    html! {
+        <details style="width:80%;margin:0.5em auto;text-align:left;">
+            <summary style="cursor:pointer;">{ format!("Layers ({})", count) }</summary>
+            if count <= 1 {
+                <div style="margin-top:0.25em;font-size:0.9em;color:#ccc;">{"Give markers and lines a group under Notes to put them on their own layer."}</div>
+            }
+            <ul style="list-style:none;padding:0;margin:0.25em 0;">
+                { for props.layers.iter().enumerate().map(row) }
+            </ul>
+        </details>
+    }
+}
@@ -0,0 +1,167 @@
+//! The line tool's controls and the list of Breadcrumbs lines on the map.
+
+use std::collections::HashSet;
+
+use marker_core::marker::BreadcrumbLine;
+use stylist::{css, Style};
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+use yew_icons::{Icon, IconId};
+
+fn colour_hex((r, g, b, _): (u8, u8, u8, u8)) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// Red, green and blue from a colour input's `#rrggbb`, with the given alpha.
+fn parse_colour(value: &str, alpha: u8) -> Option<(u8, u8, u8, u8)> {
+    let hex = u32::from_str_radix(value.trim_start_matches('#'), 16).ok()?;
+    Some(((hex >> 16) as u8, (hex >> 8) as u8, hex as u8, alpha))
+}
+
+#[derive(Properties, PartialEq)]
+pub struct LinesPanelProps {
+    /// Every line in the zone
+    pub zone_lines: Vec<BreadcrumbLine>,
+    /// The lines on the map being shown
+    pub current_lines: Vec<BreadcrumbLine>,
+    /// Called with every line in the zone after an edit
+    pub on_update: Callback<Vec<BreadcrumbLine>>,
+    pub selected: HashSet<u16>,
+    pub on_select: Callback<HashSet<u16>>,
+    /// Whether clicks on the map draw lines
+    pub drawing: bool,
+    pub on_drawing: Callback<bool>,
+    /// Colour for new lines
+    pub colour: (u8, u8, u8, u8),
+    pub on_colour: Callback<(u8, u8, u8, u8)>,
+}
+
+#[function_component(LinesPanel)]
+pub fn lines_panel(props: &LinesPanelProps) -> Html {
+    let delete_style = Style::new(css!(r#"
+        color: #fff;
+        transition: color 0.3s, scale 0.3s;
+        cursor: pointer;
+        margin-left: 1em;
+        &:hover {
+            color: #ff0000;
+            transform: scale(1.5);
+        }
+    "#)).expect("Couldn't create delete_style");
+
+    // Applies `edit` to one line and sends the zone's lines back.
+    let edit = {
+        let zone_lines = props.zone_lines.clone();
+        let on_update = props.on_update.clone();
+        move |id: u16, edit: &dyn Fn(&mut BreadcrumbLine)| {
+            let mut lines = zone_lines.clone();
+            if let Some(line) = lines.iter_mut().find(|l| l.id == id) {
+                edit(line);
+            }
+            on_update.emit(lines);
+        }
+    };
+    let delete = {
+        let zone_lines = props.zone_lines.clone();
+        let on_update = props.on_update.clone();
+        Callback::from(move |id: u16| on_update.emit(zone_lines.iter().filter(|l| l.id != id).cloned().collect()))
+    };
+    let toggle_selected = {
+        let selected = props.selected.clone();
+        let on_select = props.on_select.clone();
+        Callback::from(move |(id, is_on): (u16, bool)| {
+            let mut new_selected = selected.clone();
+            if is_on {
+                new_selected.insert(id);
+            } else {
+                new_selected.remove(&id);
+            }
+            on_select.emit(new_selected);
+        })
+    };
+    let on_new_colour = {
+        let on_colour = props.on_colour.clone();
+        let alpha = props.colour.3;
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            if let Some(colour) = parse_colour(&input.value(), alpha) {
+                on_colour.emit(colour);
+            }
+        })
+    };
+    let drawing = props.drawing;
+
+    html! {
+        <div style="display:flex;flex-direction:column;max-height:40vh;margin-bottom:1em;text-shadow: 2px 1.5px black;">
+            <h1 style="text-align:center;">{"Lines"}</h1>
+            <div style="display:flex;gap:0.5em;justify-content:center;align-items:center;margin-bottom:0.5em;">
+                <button onclick={props.on_drawing.reform(move |_| !drawing)} title="Click the map to add points. Right click or Escape ends a line">
+                    { if drawing { "Stop drawing" } else { "Draw lines" } }
+                </button>
+                <label>
+                    {"Colour "}
+                    <input type="color" value={colour_hex(props.colour)} onchange={on_new_colour} />
+                </label>
+            </div>
+            <div style="overflow-y:auto;">
+                <ul style="padding:0;margin:0;list-style:none;display:flex;flex-direction:column;">
+                { for props.current_lines.iter().map(|line| {
+                    let id = line.id;
+                    let alpha = line.colour.3;
+                    let sel = toggle_selected.clone();
+                    let del = delete.clone();
+                    let on_colour = {
+                        let edit = edit.clone();
+                        Callback::from(move |e: Event| {
+                            let input: HtmlInputElement = e.target_unchecked_into();
+                            if let Some(colour) = parse_colour(&input.value(), alpha) {
+                                edit(id, &|l| l.colour = colour);
+                            }
+                        })
+                    };
+                    let on_active = {
+                        let edit = edit.clone();
+                        Callback::from(move |e: Event| {
+                            let input: HtmlInputElement = e.target_unchecked_into();
+                            let active = input.checked();
+                            edit(id, &|l| l.active = active);
+                        })
+                    };
+                    let (a, b) = (line.position1, line.position2);
+                    let metres = ((b.x - a.x) as f64).hypot((b.z - a.z) as f64) / 100.0;
+                    html! {
+                        <li key={id} style={format!(
+                            "display:flex;align-items:center;gap:1em;justify-content:center;padding:4px;{}",
+                            if props.selected.contains(&id) { "background:rgba(255,255,255,0.12);" } else { "" }
+                        )}>
+                            <input
+                                type="checkbox"
+                                checked={props.selected.contains(&id)}
+                                style="cursor:pointer;"
+                                title="Select"
+                                onchange={Callback::from(move |e: Event| {
+                                    let inp: HtmlInputElement = e.target_unchecked_into();
+                                    sel.emit((id, inp.checked()));
+                                })}
+                            />
+                            <input type="color" value={colour_hex(line.colour)} onchange={on_colour} title="Colour" />
+                            <span title="X, Y, Z of each end">{ format!("{}, {}, {} → {}, {}, {}", a.x, a.y, a.z, b.x, b.y, b.z) }</span>
+                            <span>{ format!("{:.1} m", metres) }</span>
+                            <input type="checkbox" checked={line.active} style="cursor:pointer;" title="Enabled" onchange={on_active} />
+                            <Icon
+                                class={delete_style.clone()}
+                                style="cursor:pointer;"
+                                title={"Delete"}
+                                width={"1em"}
+                                height={"1em"}
+                                icon_id={IconId::BootstrapXLg}
+                                onclick={Callback::from(move |_| del.emit(id))}
+                            />
+                        </li>
+                    }
+                }) }
+                </ul>
+            </div>
+        </div>
+    }
+}
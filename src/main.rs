@@ -1,17 +1,249 @@
-use std::collections::HashMap;
+mod catalogue;
+mod export_panel;
+mod icon_cache;
+mod layers_panel;
+mod lines_panel;
+mod metadata_panel;
+mod pattern_panel;
+mod project_panel;
+mod ruler_panel;
+mod savedvars_panel;
+mod saves_panel;
+mod selection_toolbar;
+mod status_bar;
+mod storage;
+mod transform_panel;
+
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
 use stylist::{css, Style};
 use wasm_bindgen::{prelude::Closure, JsCast};
-use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, HtmlImageElement, HtmlInputElement, MouseEvent, WheelEvent};
+use wasm_bindgen_futures::spawn_local;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, HtmlInputElement, KeyboardEvent, MouseEvent, WheelEvent};
 use yew::prelude::*;
 use yew_icons::{Icon, IconId};
 
-mod marker;
-mod zone;
+use marker_core::breadcrumbs::{endpoint_at, move_endpoint, polyline};
+use marker_core::catalogue::ZoneCategory;
+use marker_core::convert::convert_marker;
+use marker_core::diagnostic::{Diagnostic, Severity};
+use marker_core::export::{ICON_FRACTION, bounds, icon_rect, svg, tile_path};
+use marker_core::history::{Command, History, Snapshot};
+use marker_core::measure::{Measurement, Point, pairwise};
+use marker_core::pattern::Generator;
+use marker_core::project::{Annotations, LayerEdit, Metadata, MetadataEdit, Project};
+use marker_core::saves::SavedLayout;
+use marker_core::savedvars::{Addon, SavedVariablesError, addons_in, build_saved_variables, parse_saved_variables, patch_saved_variables};
+use marker_core::selection::{BulkEdit, WorldRect, lines_in, markers_in};
+use marker_core::share::{ShareError, ShareState, decode_share, encode_share};
+use marker_core::snap::{Snap, grid_lines};
+use marker_core::marker::{ALL_ELMS_ICONS, ALL_M0R_ICONS, BreadcrumbLine, ElmMarker, Marker::{self, Elms, M0r}, MarkerTypes, Position3D, build_combined_string, find_best_map, get_marker_active, locate, placement_y, get_marker_id, get_marker_map_id, get_marker_position, get_svg, hex_to_rgba, m0r_texture_to_og, parse_combined_string, rgba_to_hex_string, set_marker_active, set_marker_id, set_marker_map_id, set_marker_position};
+use marker_core::transform::{Transform, centroid};
+use marker_core::zone::{Map, MapScaleData, populate_zone_data};
+
+use crate::catalogue::CataloguePanel;
+use crate::export_panel::{ExportPanel, ExportRequest, ImageFormat, svg_to_png};
+use crate::icon_cache::IconCache;
+use crate::layers_panel::LayersPanel;
+use crate::lines_panel::LinesPanel;
+use crate::metadata_panel::MetadataPanel;
+use crate::pattern_panel::{Pick, PatternPanel};
+use crate::project_panel::ProjectPanel;
+use crate::ruler_panel::RulerPanel;
+use crate::savedvars_panel::SavedVariablesPanel;
+use crate::saves_panel::SavesPanel;
+use crate::selection_toolbar::SelectionToolbar;
+use crate::status_bar::StatusBar;
+use crate::transform_panel::TransformPanel;
+
+fn get_timestamp() -> u64 {
+    (js_sys::Date::now() / 1000.0).floor() as u64
+}
 
-use crate::marker::{ALL_ELMS_ICONS, ALL_M0R_ICONS, BreadcrumbLine, ElmMarker, Marker::{self, Elms, M0r}, MarkerTypes, Position3D, build_elms_string, build_m0r_string, get_marker_id, get_svg, hex_to_rgba, lines_to_string, m0r_texture_to_og, parse_elms_string, parse_lines_string, parse_m0r_string, rgba_to_hex_string, set_marker_active};
-use crate::zone::{Map, populate_zone_data};
+/// The layout in the page's URL fragment, if it has one.
+fn read_share_fragment() -> Option<Result<ShareState, ShareError>> {
+    let hash = web_sys::window()?.location().hash().ok()?;
+    (hash.len() > 1).then(|| decode_share(&hash))
+}
 
-#[derive(Properties, PartialEq)]
+/// Drops the fragment once it has been read, so later edits aren't confused with the link.
+fn clear_share_fragment() {
+    if let Some(window) = web_sys::window()
+        && let Ok(history) = window.history()
+    {
+        let location = window.location();
+        let url = format!("{}{}", location.pathname().unwrap_or_default(), location.search().unwrap_or_default());
+        let _ = history.replace_state_with_url(&wasm_bindgen::JsValue::NULL, "", Some(&url));
+    }
+}
+
+/// A Unix time in the user's locale.
+fn format_time(secs: u64) -> String {
+    let date = js_sys::Date::new(&wasm_bindgen::JsValue::from_f64(secs as f64 * 1000.0));
+    String::from(date.to_locale_string("default", &wasm_bindgen::JsValue::UNDEFINED))
+}
+
+/// Top-left corner and side length of a marker's icon in unzoomed canvas
+/// pixels, as `CanvasMap` draws it.
+fn marker_rect(marker: &Marker, scale: &MapScaleData, canvas_px: f64, zoom: f64) -> (f64, f64, f64) {
+    let p = get_marker_position(marker);
+    let (nx, nz) = scale.normalise(p.x as f64, p.z as f64);
+    icon_rect(marker, (nx * canvas_px, nz * canvas_px), canvas_px * ICON_FRACTION / zoom)
+}
+
+/// Id of the topmost visible marker on `map` under a point in unzoomed canvas pixels.
+fn marker_at(markers: &[Marker], map: &Map, canvas_px: f64, zoom: f64, point: (f64, f64)) -> Option<u16> {
+    markers.iter().rev()
+        .filter(|m| get_marker_active(m) && get_marker_map_id(m) == map.map_id)
+        .find(|m| {
+            let (x, y, size) = marker_rect(m, &map.scale_data, canvas_px, zoom);
+            point.0 >= x && point.0 <= x + size && point.1 >= y && point.1 <= y + size
+        })
+        .map(get_marker_id)
+}
+
+/// Id of the topmost visible line on `map` within a few screen pixels of a
+/// point in unzoomed canvas pixels.
+fn line_at(lines: &[BreadcrumbLine], map: &Map, canvas_px: f64, zoom: f64, point: (f64, f64)) -> Option<u16> {
+    let project = |p: &Position3D| {
+        let (nx, nz) = map.scale_data.normalise(p.x as f64, p.z as f64);
+        (nx * canvas_px, nz * canvas_px)
+    };
+    let tolerance = 5.0 / zoom;
+    lines.iter().rev()
+        .filter(|l| l.active && l.map_id == map.map_id)
+        .find(|l| {
+            let (a, b) = (project(&l.position1), project(&l.position2));
+            let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+            let length_sq = dx * dx + dy * dy;
+            let t = if length_sq == 0.0 { 0.0 } else { (((point.0 - a.0) * dx + (point.1 - a.1) * dy) / length_sq).clamp(0.0, 1.0) };
+            let (cx, cy) = (a.0 + t * dx, a.1 + t * dy);
+            (point.0 - cx).hypot(point.1 - cy) <= tolerance
+        })
+        .map(|l| l.id)
+}
+
+/// World units around the selection when an export is cropped to it.
+const EXPORT_MARGIN: f64 = 1000.0;
+
+/// The least zoom "go to" leaves the map at, so the position is easy to find.
+const GO_TO_ZOOM: f64 = 4.0;
+
+/// How close, in screen pixels, a click has to be to a line end or marker to
+/// grab or snap to it.
+const SNAP_PIXELS: f64 = 8.0;
+
+/// The snapping for a mouse event: none while Alt is held.
+fn snap_for(e: &MouseEvent, snap: Snap) -> Snap {
+    if e.alt_key() { Snap::default() } else { snap }
+}
+
+/// World units covered by `pixels` screen pixels on `map` at this zoom.
+fn world_distance(map: &Map, canvas_px: f64, zoom: f64, pixels: f64) -> f64 {
+    pixels / zoom / canvas_px * (map.scale_data.max_x - map.scale_data.min_x) as f64
+}
+
+/// Mouse position in unzoomed canvas pixels, if the event landed on a canvas.
+fn canvas_point(e: &MouseEvent, pan: (f64, f64), zoom: f64) -> Option<(f64, f64)> {
+    let canvas = e.target_dyn_into::<HtmlCanvasElement>()?;
+    let rect = canvas.get_bounding_client_rect();
+    let mx = e.client_x() as f64 - rect.left();
+    let my = e.client_y() as f64 - rect.top();
+    Some(((mx - pan.0) / zoom, (my - pan.1) / zoom))
+}
+
+/// Callbacks for the window's keyboard shortcuts.
+#[derive(Clone, Default)]
+struct Shortcuts {
+    undo: Callback<()>,
+    redo: Callback<()>,
+    /// Deletes the selection, if there is one
+    delete: Callback<()>,
+    /// Ends the line being drawn, drops the measurement being made, or clears
+    /// the selection
+    escape: Callback<()>,
+}
+
+/// Pushes the change from `before` to `after` onto the undo stack, if there
+/// is one, and clears any notes left on ids it has just reused.
+fn record_edit(history: &RefCell<History>, annotations: &RefCell<Annotations>, before: Snapshot, after: Snapshot, label: Option<&str>, continuous: bool) {
+    let old = annotations.borrow().clone();
+    annotations.borrow_mut().forget_new(before, after);
+    push_edit(history, &old, &annotations.borrow(), before, after, label, continuous);
+}
+
+/// Pushes the change from `before` to `after`, and from `old` annotations to
+/// `new`, onto the undo stack, if anything changed. `continuous` edits, such
+/// as keystrokes in a field, merge with the one before if it came just now.
+fn push_edit(history: &RefCell<History>, old: &Annotations, new: &Annotations, before: Snapshot, after: Snapshot, label: Option<&str>, continuous: bool) {
+    if let Some(command) = Command::annotated(before, after, old, new) {
+        let command = match label {
+            Some(label) => command.with_label(label),
+            None => command,
+        };
+        let command = if continuous { command.merging() } else { command };
+        history.borrow_mut().push(command, js_sys::Date::now());
+    }
+}
+
+/// Outline for selected markers and the selection box.
+const HIGHLIGHT: &str = "#4fc3f7";
+/// Wide stroke behind selected lines, and the selection box's fill.
+const HIGHLIGHT_HALO: &str = "rgba(79,195,247,0.45)";
+/// The snapping grid.
+const GRID: &str = "rgba(255,255,255,0.2)";
+/// Measurements made with the ruler.
+const RULER: &str = "#ffd166";
+/// Selections with more markers than this don't show the distances between them.
+const MAX_MEASURED_SELECTION: usize = 6;
+
+/// Draws a measurement from `a` to `b`, in unzoomed canvas pixels, with its
+/// label halfway along.
+fn draw_measurement(ctx: &CanvasRenderingContext2d, a: (f64, f64), b: (f64, f64), label: &str, colour: &str, zoom: f64) {
+    ctx.save();
+    ctx.set_stroke_style_str(colour);
+    ctx.set_fill_style_str(colour);
+    ctx.set_line_width(2.0 / zoom);
+    let dash = js_sys::Array::of2(&(8.0 / zoom).into(), &(4.0 / zoom).into());
+    let _ = ctx.set_line_dash(&dash);
+    ctx.begin_path();
+    ctx.move_to(a.0, a.1);
+    ctx.line_to(b.0, b.1);
+    ctx.stroke();
+    for (x, y) in [a, b] {
+        ctx.begin_path();
+        let _ = ctx.arc(x, y, 3.0 / zoom, 0.0, std::f64::consts::TAU);
+        ctx.fill();
+    }
+    ctx.set_font(&format!("{}px Univers", 12.0 / zoom));
+    ctx.set_text_align("center");
+    ctx.set_text_baseline("bottom");
+    ctx.set_stroke_style_str("black");
+    ctx.set_line_width(3.0 / zoom);
+    let _ = ctx.set_line_dash(&js_sys::Array::new());
+    let (x, y) = ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0 - 4.0 / zoom);
+    let _ = ctx.stroke_text(label, x, y);
+    ctx.set_fill_style_str("white");
+    let _ = ctx.fill_text(label, x, y);
+    ctx.restore();
+}
+
+/// Draws a marker or line's name from its notes, centred above `x, y`.
+fn draw_name(ctx: &CanvasRenderingContext2d, name: &str, x: f64, y: f64, zoom: f64) {
+    ctx.save();
+    ctx.set_font(&format!("italic {}px Univers", 11.0 / zoom));
+    ctx.set_text_align("center");
+    ctx.set_text_baseline("bottom");
+    ctx.set_stroke_style_str("black");
+    ctx.set_line_width(3.0 / zoom);
+    let _ = ctx.stroke_text(name, x, y);
+    ctx.set_fill_style_str("#ddd");
+    let _ = ctx.fill_text(name, x, y);
+    ctx.restore();
+}
+
+#[derive(Properties, PartialEq, Clone)]
 pub struct CanvasMapProps {
     pub map: Map,
     pub markers: Vec<Marker>,
@@ -21,307 +253,412 @@ pub struct CanvasMapProps {
     pub width: u32,
     pub height: u32,
     pub force_redraw: bool,
+    pub selected: HashSet<u16>,
+    pub selected_lines: HashSet<u16>,
+    /// Opposite corners of the box being dragged out, in unzoomed canvas pixels
+    pub selection_box: Option<((f64, f64), (f64, f64))>,
+    /// Shows a crosshair rather than a hand, for picking points
+    pub crosshair: bool,
+    /// Marks line ends, which new lines snap to
+    pub show_endpoints: bool,
+    /// The line being drawn, from its last point to the mouse, in unzoomed canvas pixels
+    pub draft_line: Option<((f64, f64), (f64, f64))>,
+    pub draft_colour: (u8, u8, u8, u8),
+    /// Grid spacing in world units, if the grid is shown
+    pub grid: Option<f64>,
+    /// Ruler measurements, pinned or being made
+    pub measurements: Vec<Measurement>,
+    /// Between each pair of selected markers
+    pub selection_measurements: Vec<Measurement>,
+    /// Called with a position typed into the status bar
+    pub on_go_to: Callback<(i32, Option<i32>, i32)>,
+    pub cache: Rc<IconCache>,
+    /// Names and tags of this zone's markers, by id
+    pub marker_meta: HashMap<u16, Metadata>,
+    /// Names and tags of this zone's lines, by id
+    pub line_meta: HashMap<u16, Metadata>,
+    /// Opacity of markers on faded layers, by id
+    pub marker_opacity: HashMap<u16, f64>,
+    /// Opacity of lines on faded layers, by id
+    pub line_opacity: HashMap<u16, f64>,
 }
-#[function_component(CanvasMap)]
-fn canvas_map(props: &CanvasMapProps) -> Html {
-    let map_canvas_ref = use_node_ref();
-    let marker_canvas_ref = use_node_ref();
 
-    let map = props.map.clone();
-    let markers = props.markers.clone();
-    let zoom = props.zoom;
-    let pan = props.pan;
-    let canvas_width = props.width;
-    let canvas_height = props.height;
-    let force_redraw = props.force_redraw;
-    let lines = props.lines.clone();
-
-    let tile_images = {
-        let tiles = map.tiles.clone();
-        use_memo(tiles.clone(), move |tiles: &Vec<_>| {
-            tiles.iter().map(|tile| {
-                let img = web_sys::HtmlImageElement::new().unwrap();
-                img.set_src(&format!("static/maps/{}", tile.path));
-                img
-            }).collect::<Vec<_>>()
-        })
+/// Sets a canvas's size only when it has changed, as that reallocates it.
+fn resize(canvas: &HtmlCanvasElement, width: u32, height: u32) {
+    if canvas.width() != width || canvas.height() != height {
+        canvas.set_width(width);
+        canvas.set_height(height);
+    }
+}
+
+/// Draws the map's tiles that have loaded.
+fn draw_tiles(canvas: &HtmlCanvasElement, props: &CanvasMapProps, cache: &IconCache) {
+    let ctx = canvas
+        .get_context("2d").unwrap().unwrap()
+        .dyn_into::<CanvasRenderingContext2d>().unwrap();
+    let (map, zoom, pan) = (&props.map, props.zoom, props.pan);
+
+    resize(canvas, props.width, props.height);
+
+    let w = canvas.width() as f64;
+    let h = canvas.height() as f64;
+
+    ctx.set_transform(1.0, 0.0, 0.0, 1.0, 0.0, 0.0).unwrap();
+    ctx.clear_rect(0.0, 0.0, w, h);
+    ctx.set_transform(zoom, 0.0, 0.0, zoom, pan.0, pan.1).unwrap();
+    ctx.set_image_smoothing_enabled(false);
+
+    let tile_size = w / (map.count as f64);
+
+    for (i, tile) in map.tiles.iter().enumerate() {
+        let Some(img) = cache.image(&format!("static/{}", tile_path(tile))) else { continue };
+        let row = (i as u8) / map.count;
+        let col = (i as u8) % map.count;
+
+        let raw_x1 = col as f64 * tile_size;
+        let raw_y1 = row as f64 * tile_size;
+        let raw_x2 = (col as f64 + 1.0) * tile_size;
+        let raw_y2 = (row as f64 + 1.0) * tile_size;
+        let x = raw_x1.floor();
+        let y = raw_y1.floor();
+        let dw = raw_x2.ceil() - x;
+        let dh = raw_y2.ceil() - y;
+
+        ctx.draw_image_with_html_image_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
+            &img,
+            0.0, 0.0,
+            img.width() as f64,
+            img.height() as f64,
+            x, y,
+            dw, dh,
+        ).unwrap();
+    }
+}
+
+/// Draws the lines, markers and everything over them. Icons that haven't
+/// loaded yet are left out until the next frame after they do.
+fn draw_markers(canvas: &HtmlCanvasElement, props: &CanvasMapProps, cache: &IconCache) {
+    let map = &props.map;
+    let (markers, lines, zoom, pan) = (&props.markers, &props.lines, &props.zoom, &props.pan);
+    let (selected, selected_lines, selection_box) = (&props.selected, &props.selected_lines, &props.selection_box);
+    let (show_endpoints, grid) = (&props.show_endpoints, &props.grid);
+    let draft = props.draft_line.map(|line| (line, props.draft_colour));
+    let (measurements, selection_measurements) = (&props.measurements, &props.selection_measurements);
+    let (marker_meta, line_meta) = (&props.marker_meta, &props.line_meta);
+    let (marker_opacity, line_opacity) = (&props.marker_opacity, &props.line_opacity);
+
+    let ctx = canvas
+        .get_context("2d").unwrap().unwrap()
+        .dyn_into::<CanvasRenderingContext2d>().unwrap();
+
+    resize(canvas, props.width, props.height);
+
+    let w = canvas.width() as f64;
+    let h = canvas.height() as f64;
+
+    ctx.set_transform(1.0, 0.0, 0.0, 1.0, 0.0, 0.0).unwrap();
+    ctx.clear_rect(0.0, 0.0, w, h);
+    ctx.set_transform(*zoom, 0.0, 0.0, *zoom, pan.0, pan.1).unwrap();
+    ctx.set_image_smoothing_enabled(true);
+
+    let project = |p: &Position3D| -> (f64, f64) {
+        let (nx, nz) = map.scale_data.normalise(p.x as f64, p.z as f64);
+        (nx * w, nz * h)
     };
 
-    {
-        let map_canvas_ref = map_canvas_ref.clone();
-        let tile_images = tile_images.clone();
-        use_effect_with((tile_images.clone(), zoom, pan, canvas_width, canvas_height),
-            move |(tile_images, zoom, pan, canvas_width, canvas_height)| {
-                let canvas = map_canvas_ref.cast::<HtmlCanvasElement>().unwrap();
-                let ctx = canvas
-                    .get_context("2d").unwrap().unwrap()
-                    .dyn_into::<CanvasRenderingContext2d>().unwrap();
+    if let Some(step) = *grid {
+        // Only the part of the map on screen, and only if the lines
+        // aren't so close together they'd cover it.
+        let scale = &map.scale_data;
+        let spacing = step / (scale.max_x - scale.min_x) as f64 * w * zoom;
+        if spacing >= 4.0 {
+            let (x0, z0) = scale.denormalise(-pan.0 / zoom / w, -pan.1 / zoom / h);
+            let (x1, z1) = scale.denormalise((w - pan.0) / zoom / w, (h - pan.1) / zoom / h);
+            ctx.save();
+            ctx.begin_path();
+            ctx.set_stroke_style_str(GRID);
+            ctx.set_line_width(1.0 / zoom);
+            for x in grid_lines(x0.min(x1), x0.max(x1), step) {
+                let (nx, _) = scale.normalise(x, z0);
+                ctx.move_to(nx * w, 0.0);
+                ctx.line_to(nx * w, h);
+            }
+            for z in grid_lines(z0.min(z1), z0.max(z1), step) {
+                let (_, nz) = scale.normalise(x0, z);
+                ctx.move_to(0.0, nz * h);
+                ctx.line_to(w, nz * h);
+            }
+            ctx.stroke();
+            ctx.restore();
+        }
+    }
 
-                canvas.set_width(*canvas_width);
-                canvas.set_height(*canvas_height);
+    for line in lines.iter() {
+        if !line.active || line.map_id != map.map_id { continue; }
 
-                let w = canvas.width() as f64;
-                let h = canvas.height() as f64;
-
-                ctx.set_transform(*zoom, 0.0, 0.0, *zoom, pan.0, pan.1).unwrap();
-                ctx.set_image_smoothing_enabled(false);
-                ctx.clear_rect(0.0, 0.0, w / zoom, h / zoom);
-
-                let tile_size = w / (map.count as f64);
-
-                for (i, img) in tile_images.iter().enumerate() {
-                    let row = (i as u8) / map.count;
-                    let col = (i as u8) % map.count;
-
-                    let raw_x1 = col as f64 * tile_size;
-                    let raw_y1 = row as f64 * tile_size;
-                    let raw_x2 = (col as f64 + 1.0) * tile_size;
-                    let raw_y2 = (row as f64 + 1.0) * tile_size;
-                    let x = raw_x1.floor();
-                    let y = raw_y1.floor();
-                    let dw = raw_x2.ceil() - x;
-                    let dh = raw_y2.ceil() - y;
-
-                    if img.complete() {
-                        ctx.draw_image_with_html_image_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
-                            img,
-                            0.0, 0.0,
-                            img.width() as f64,
-                            img.height() as f64,
-                            x, y,
-                            dw, dh,
-                        ).unwrap();
-                    } else {
-                        let ctx_clone = ctx.clone();
-                        let img_clone = img.clone();
-                        let draw = Closure::wrap(Box::new(move || {
-                            ctx_clone.draw_image_with_html_image_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
-                                &img_clone,
-                                0.0, 0.0,
-                                img_clone.width() as f64,
-                                img_clone.height() as f64,
-                                x, y,
-                                dw, dh,
-                            ).unwrap();
-                        }) as Box<dyn Fn()>);
-                        img.set_onload(Some(draw.as_ref().unchecked_ref()));
-                        draw.forget();
-                    }
-                }
+        let (x1, y1) = project(&line.position1);
+        let (x2, y2) = project(&line.position2);
+        ctx.set_global_alpha(line_opacity.get(&line.id).copied().unwrap_or(1.0));
 
-                || ()
-        });
+        if selected_lines.contains(&line.id) {
+            ctx.begin_path();
+            ctx.set_stroke_style_str(HIGHLIGHT_HALO);
+            ctx.set_line_width(8.0 / zoom.max(0.0001));
+            ctx.move_to(x1, y1);
+            ctx.line_to(x2, y2);
+            ctx.stroke();
+        }
+
+        let meta = line_meta.get(&line.id);
+        if let Some((r, g, b)) = meta.and_then(|m| m.tag) {
+            ctx.begin_path();
+            ctx.set_stroke_style_str(&format!("rgba({},{},{},0.6)", r, g, b));
+            ctx.set_line_width(6.0 / zoom.max(0.0001));
+            ctx.move_to(x1, y1);
+            ctx.line_to(x2, y2);
+            ctx.stroke();
+        }
+
+        let (r, g, b, _a) = line.colour;
+        let rgba = format!("rgba({},{},{},{})", r, g, b, 0.9);
+
+        ctx.begin_path();
+        ctx.set_stroke_style_str(&rgba);
+        ctx.set_line_width(2.0 / zoom.max(0.0001));
+        ctx.move_to(x1, y1);
+        ctx.line_to(x2, y2);
+        ctx.stroke();
+        ctx.close_path();
+
+        if let Some(meta) = meta.filter(|m| !m.name.is_empty()) {
+            draw_name(&ctx, &meta.name, (x1 + x2) / 2.0, (y1 + y2) / 2.0 - 4.0 / zoom, *zoom);
+        }
+
+        if *show_endpoints {
+            ctx.set_fill_style_str(&rgba);
+            for (x, y) in [(x1, y1), (x2, y2)] {
+                ctx.begin_path();
+                let _ = ctx.arc(x, y, 3.0 / zoom, 0.0, std::f64::consts::TAU);
+                ctx.fill();
+            }
+        }
+    }
+    ctx.set_global_alpha(1.0);
+
+    if let Some(((from, to), (r, g, b, _))) = draft {
+        ctx.save();
+        ctx.begin_path();
+        ctx.set_stroke_style_str(&format!("rgba({},{},{},0.6)", r, g, b));
+        ctx.set_line_width(2.0 / zoom);
+        let dash = js_sys::Array::of2(&(6.0 / zoom).into(), &(4.0 / zoom).into());
+        let _ = ctx.set_line_dash(&dash);
+        ctx.move_to(from.0, from.1);
+        ctx.line_to(to.0, to.1);
+        ctx.stroke();
+        ctx.restore();
     }
 
-    {
-        let marker_canvas_ref = marker_canvas_ref.clone();
-        let markers = markers.clone();
-        let lines = lines.clone();
+    for marker in markers.iter().filter(|m| get_marker_active(m)) {
+        let (dx, dy, display_size) = marker_rect(marker, &map.scale_data, w, *zoom);
+        ctx.set_global_alpha(marker_opacity.get(&get_marker_id(marker)).copied().unwrap_or(1.0));
+        match marker {
+            Marker::Elms(elms_marker) => {
+                if let Some(icon) = cache.elms(elms_marker.icon) {
+                    ctx.draw_image_with_html_image_element_and_dw_and_dh(&icon, dx, dy, display_size, display_size).unwrap();
+                }
+            },
+            Marker::M0r(m0r_marker) => {
+                if let Some(tinted) = cache.m0r(&m0r_marker.background_texture, m0r_marker.colour) {
+                    ctx.draw_image_with_html_canvas_element_and_dw_and_dh(&tinted, dx, dy, display_size, display_size).unwrap();
+                }
+
+                if let Some(text) = &m0r_marker.text {
+                    ctx.save();
+                    ctx.set_font(format!("{}px Univers", 12.0/zoom).as_str());
+                    ctx.set_fill_style_str("white");
+                    ctx.set_stroke_style_str("black");
+                    ctx.set_line_width(3.0/zoom);
+                    ctx.set_text_align("center");
+                    ctx.set_text_baseline("top");
+
+                    let text_x = dx + display_size / 2.0;
+                    let text_y = dy + display_size / 2.0;
+
+                    ctx.stroke_text(text, text_x, text_y).unwrap();
+                    ctx.fill_text(text, text_x, text_y).unwrap();
+                    ctx.restore();
+                }
+            },
+        }
+    }
+    ctx.set_global_alpha(1.0);
+
+    for marker in markers.iter().filter(|m| get_marker_active(m)) {
+        let Some(meta) = marker_meta.get(&get_marker_id(marker)) else { continue };
+        let (dx, dy, display_size) = marker_rect(marker, &map.scale_data, w, *zoom);
+        if let Some((r, g, b)) = meta.tag {
+            ctx.begin_path();
+            ctx.set_stroke_style_str(&format!("rgb({},{},{})", r, g, b));
+            ctx.set_line_width(2.0 / zoom);
+            let _ = ctx.arc(dx + display_size / 2.0, dy + display_size / 2.0, display_size * 0.6, 0.0, std::f64::consts::TAU);
+            ctx.stroke();
+        }
+        if !meta.name.is_empty() {
+            draw_name(&ctx, &meta.name, dx + display_size / 2.0, dy - 2.0 / zoom, *zoom);
+        }
+    }
 
-        use_effect_with((markers.clone(), lines.clone(), zoom, pan, canvas_width, canvas_height, force_redraw),
-            move |(markers, lines, zoom, pan, canvas_width, canvas_height, _force_redraw)| {
-                let canvas = marker_canvas_ref.cast::<HtmlCanvasElement>().unwrap();
-                let ctx = canvas
-                    .get_context("2d").unwrap().unwrap()
-                    .dyn_into::<CanvasRenderingContext2d>().unwrap();
+    ctx.set_stroke_style_str(HIGHLIGHT);
+    ctx.set_line_width(2.0 / zoom);
+    for marker in markers.iter().filter(|m| get_marker_active(m) && selected.contains(&get_marker_id(m))) {
+        let (dx, dy, display_size) = marker_rect(marker, &map.scale_data, w, *zoom);
+        let pad = 3.0 / zoom;
+        ctx.stroke_rect(dx - pad, dy - pad, display_size + pad * 2.0, display_size + pad * 2.0);
+    }
 
-                canvas.set_width(*canvas_width);
-                canvas.set_height(*canvas_height);
+    let project_point = |p: &Point| -> (f64, f64) {
+        let (nx, nz) = map.scale_data.normalise(p.x, p.z);
+        (nx * w, nz * h)
+    };
+    for (list, colour) in [(selection_measurements, HIGHLIGHT), (measurements, RULER)] {
+        for m in list {
+            draw_measurement(&ctx, project_point(&m.from), project_point(&m.to), &m.label(), colour, *zoom);
+        }
+    }
 
-                let w = canvas.width() as f64;
-                let h = canvas.height() as f64;
-
-                ctx.set_transform(*zoom, 0.0, 0.0, *zoom, pan.0, pan.1).unwrap();
-                ctx.set_image_smoothing_enabled(true);
-                ctx.clear_rect(0.0, 0.0, w / zoom, h / zoom);
-
-                let project = |p: &Position3D| -> (f64, f64) {
-                    let nx = (p.x as f64 - map.scale_data.min_x as f64)
-                        / (map.scale_data.max_x as f64 - map.scale_data.min_x as f64);
-                    let nz = (p.z as f64 - map.scale_data.min_z as f64)
-                        / (map.scale_data.max_z as f64 - map.scale_data.min_z as f64);
-                    (nx * w, nz * h)
-                };
+    if let Some((a, b)) = selection_box {
+        ctx.save();
+        ctx.set_fill_style_str(HIGHLIGHT_HALO);
+        ctx.set_line_width(1.0 / zoom);
+        let dash = js_sys::Array::of2(&(4.0 / zoom).into(), &(3.0 / zoom).into());
+        let _ = ctx.set_line_dash(&dash);
+        let (x, y) = (a.0.min(b.0), a.1.min(b.1));
+        let (bw, bh) = ((a.0 - b.0).abs(), (a.1 - b.1).abs());
+        ctx.fill_rect(x, y, bw, bh);
+        ctx.stroke_rect(x, y, bw, bh);
+        ctx.restore();
+    }
+}
 
-                for line in lines.iter() {
-                    if !line.active || line.map_id != map.map_id { continue; }
+/// Which of the map's two canvases need drawing on the next frame.
+#[derive(Default)]
+struct Dirty {
+    tiles: Cell<bool>,
+    markers: Cell<bool>,
+}
 
-                    let (x1, y1) = project(&line.position1);
-                    let (x2, y2) = project(&line.position2);
+#[function_component(CanvasMap)]
+fn canvas_map(props: &CanvasMapProps) -> Html {
+    let map_canvas_ref = use_node_ref();
+    let marker_canvas_ref = use_node_ref();
 
-                    let (r, g, b, _a) = line.colour;
-                    let rgba = format!("rgba({},{},{},{})", r, g, b, 0.9);
+    let zoom = props.zoom;
+    let pan = props.pan;
+    let canvas_width = props.width;
+    let canvas_height = props.height;
+    // World X/Z under the mouse, for the status bar.
+    let cursor = use_state(|| None::<(f64, f64)>);
 
-                    ctx.begin_path();
-                    ctx.set_stroke_style_str(&rgba);
-                    ctx.set_line_width(2.0 / zoom.max(0.0001));
-                    ctx.move_to(x1, y1);
-                    ctx.line_to(x2, y2);
-                    ctx.stroke();
-                    ctx.close_path();
-                }
+    let cache = props.cache.clone();
+    let dirty = use_memo((), |_| Dirty::default());
+    // What the next frame draws: the props from the latest render.
+    let scene = use_mut_ref(|| None::<CanvasMapProps>);
 
-                let base = *canvas_width as f64 / 30.0;
-                for marker in markers.iter() {
-                    match marker {
-                        Marker::Elms(elms_marker) => {
-                            if !elms_marker.active { continue; }
-
-                            let (mx, mz) = {
-                                let nx = (elms_marker.position.x as f64 - map.scale_data.min_x as f64)
-                                    / (map.scale_data.max_x as f64 - map.scale_data.min_x as f64);
-                                let nz = (elms_marker.position.z as f64 - map.scale_data.min_z as f64)
-                                    / (map.scale_data.max_z as f64 - map.scale_data.min_z as f64);
-                                (nx * w, nz * h)
-                            };
-
-                            let display_size = base * (1.0 / zoom) * (elms_marker.size as f64);
-                            let dx = mx - display_size / 2.0;
-                            let dy = mz - display_size / 2.0;
-                            let icon_img = HtmlImageElement::new().unwrap();
-                            icon_img.set_src(&format!("static/icons/{}", String::from(elms_marker.icon)));
-
-                            if icon_img.complete() {
-                                ctx.draw_image_with_html_image_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
-                                    &icon_img,
-                                    0.0, 0.0,
-                                    icon_img.width() as f64,
-                                    icon_img.height() as f64,
-                                    dx, dy,
-                                    display_size,
-                                    display_size,
-                                ).unwrap();
-                            } else {
-                                let ctx_clone = ctx.clone();
-                                let icon_clone = icon_img.clone();
-                                let draw = Closure::wrap(Box::new(move || {
-                                    ctx_clone.draw_image_with_html_image_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
-                                        &icon_clone,
-                                        0.0, 0.0,
-                                        icon_clone.width() as f64,
-                                        icon_clone.height() as f64,
-                                        dx, dy,
-                                        display_size,
-                                        display_size,
-                                    ).unwrap();
-                                }) as Box<dyn Fn()>);
-                                icon_img.set_onload(Some(draw.as_ref().unchecked_ref()));
-                                draw.forget();
-                            }
-                        },
-                        Marker::M0r(m0r_marker) => {
-                            if !m0r_marker.active { continue; }
-
-                            let nx = (m0r_marker.position.x as f64 - map.scale_data.min_x as f64)
-                                / (map.scale_data.max_x as f64 - map.scale_data.min_x as f64);
-                            let nz = (m0r_marker.position.z as f64 - map.scale_data.min_z as f64)
-                                / (map.scale_data.max_z as f64 - map.scale_data.min_z as f64);
-                            let mx = nx * w;
-                            let mz = nz * h;
-
-                            let display_size = base * (1.0 / zoom) * (1.0 + (m0r_marker.size as f64 / 8.0));
-                            let high_res_size = display_size * zoom;
-                            let dx = mx - display_size / 2.0;
-                            let dy = mz - display_size * 0.25;
-
-                            let offscreen: HtmlCanvasElement = web_sys::window()
-                                .unwrap()
-                                .document()
-                                .unwrap()
-                                .create_element("canvas")
-                                .unwrap()
-                                .dyn_into::<HtmlCanvasElement>()
-                                .unwrap();
-                            offscreen.set_width(high_res_size as u32);
-                            offscreen.set_height(high_res_size as u32);
-
-                            let off_ctx: CanvasRenderingContext2d = offscreen
-                                .get_context("2d")
-                                .unwrap()
-                                .unwrap()
-                                .dyn_into::<CanvasRenderingContext2d>()
-                                .unwrap();
-
-                            let icon_img = HtmlImageElement::new().unwrap();
-                            icon_img.set_src(&format!("static/icons/m0r/{}", String::from(m0r_marker.background_texture.clone())));
-
-                            let rgba = format!(
-                                "rgba({},{},{},{})",
-                                m0r_marker.colour.0,
-                                m0r_marker.colour.1,
-                                m0r_marker.colour.2,
-                                m0r_marker.colour.3
-                            );
-
-                            let draw_marker = move |ctx: &CanvasRenderingContext2d, icon: &HtmlImageElement| {
-                                off_ctx.set_global_composite_operation("multiply").unwrap();
-                                off_ctx.clear_rect(0.0, 0.0, high_res_size, high_res_size);
-                                off_ctx.set_fill_style_str(&rgba);
-                                off_ctx.fill_rect(0.0, 0.0, high_res_size, high_res_size);
-
-                                off_ctx.set_global_composite_operation("destination-in").unwrap();
-                                off_ctx
-                                    .draw_image_with_html_image_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
-                                        icon,
-                                        0.0,
-                                        0.0,
-                                        icon.width() as f64,
-                                        icon.height() as f64,
-                                        0.0,
-                                        0.0,
-                                        high_res_size,
-                                        high_res_size,
-                                    )
-                                    .unwrap();
-
-                                ctx.draw_image_with_html_canvas_element_and_dw_and_dh(&offscreen, dx, dy, display_size, display_size).unwrap();
-                            };
-
-                            if icon_img.complete() {
-                                draw_marker(&ctx, &icon_img);
-                            } else {
-                                let ctx_clone = ctx.clone();
-                                let icon_clone = icon_img.clone();
-                                let draw_closure = Closure::wrap(Box::new(move || {
-                                    draw_marker(&ctx_clone, &icon_clone);
-                                }) as Box<dyn Fn()>);
-                                icon_img.set_onload(Some(draw_closure.as_ref().unchecked_ref()));
-                                draw_closure.forget();
-                            }
+    {
+        let scene = scene.clone();
+        let dirty = dirty.clone();
+        let props = props.clone();
+        use_effect(move || {
+            let mut scene = scene.borrow_mut();
+            if scene.as_ref() != Some(&props) {
+                let moved = scene.as_ref().is_none_or(|last| {
+                    (&last.map, last.zoom, last.pan, last.width, last.height) != (&props.map, props.zoom, props.pan, props.width, props.height)
+                });
+                dirty.tiles.set(dirty.tiles.get() || moved);
+                dirty.markers.set(true);
+                *scene = Some(props);
+            }
+        });
+    }
 
-                            if let Some(text) = &m0r_marker.text {
-                                ctx.save();
-                                ctx.set_font(format!("{}px Univers", 12.0/zoom).as_str());
-                                ctx.set_fill_style_str("white");
-                                ctx.set_stroke_style_str("black");
-                                ctx.set_line_width(3.0/zoom);
-                                ctx.set_text_align("center");
-                                ctx.set_text_baseline("top");
-
-                                let text_x = dx + display_size / 2.0;
-                                let text_y = dy + display_size / 2.0;
-
-                                ctx.stroke_text(&text, text_x, text_y).unwrap();
-                                ctx.fill_text(&text, text_x, text_y).unwrap();
-                                ctx.restore();
-                            }
-                        },
+    {
+        // One animation frame loop for the component's life, drawing only
+        // the canvases something has changed on.
+        let map_canvas_ref = map_canvas_ref.clone();
+        let marker_canvas_ref = marker_canvas_ref.clone();
+        let cache = cache.clone();
+        let dirty = dirty.clone();
+        let scene = scene.clone();
+        use_effect_with((), move |_| {
+            let frame = Rc::new(RefCell::new(None::<Closure<dyn FnMut()>>));
+            let handle = Rc::new(Cell::new(0));
+            let tick = {
+                let frame = frame.clone();
+                let handle = handle.clone();
+                move || {
+                    if cache.take_loaded() {
+                        dirty.tiles.set(true);
+                        dirty.markers.set(true);
+                    }
+                    if let Some(props) = &*scene.borrow() {
+                        if dirty.tiles.get()
+                            && let Some(canvas) = map_canvas_ref.cast::<HtmlCanvasElement>()
+                        {
+                            draw_tiles(&canvas, props, &cache);
+                            dirty.tiles.set(false);
+                        }
+                        if dirty.markers.get()
+                            && let Some(canvas) = marker_canvas_ref.cast::<HtmlCanvasElement>()
+                        {
+                            draw_markers(&canvas, props, &cache);
+                            dirty.markers.set(false);
+                        }
+                    }
+                    if let Some(next) = &*frame.borrow() {
+                        handle.set(web_sys::window().unwrap().request_animation_frame(next.as_ref().unchecked_ref()).unwrap_or(0));
                     }
                 }
-
-                || ()
+            };
+            let first = Closure::wrap(Box::new(tick) as Box<dyn FnMut()>);
+            handle.set(web_sys::window().unwrap().request_animation_frame(first.as_ref().unchecked_ref()).unwrap_or(0));
+            *frame.borrow_mut() = Some(first);
+
+            move || {
+                let _ = web_sys::window().unwrap().cancel_animation_frame(handle.get());
+                // Drops the closure, and with it the loop's hold on itself.
+                frame.borrow_mut().take();
+            }
         });
     }
 
+    let onmousemove = {
+        let cursor = cursor.clone();
+        let scale = props.map.scale_data.clone();
+        Callback::from(move |e: MouseEvent| {
+            cursor.set(canvas_point(&e, pan, zoom).map(|(px, pz)| scale.denormalise(px / canvas_width as f64, pz / canvas_width as f64)));
+        })
+    };
+    let onmouseleave = {
+        let cursor = cursor.clone();
+        Callback::from(move |_: MouseEvent| cursor.set(None))
+    };
+
     html! {
-        <div style={format!(
-            "position: relative; min-width: 475px; min-height: 475px; cursor: grab; width: {}px; height: {}px;",
-            canvas_width, canvas_height
+        <div {onmousemove} {onmouseleave} style={format!(
+            "position: relative; min-width: 475px; min-height: 475px; cursor: {}; width: {}px; height: {}px;",
+            if props.crosshair { "crosshair" } else { "grab" }, canvas_width, canvas_height
         )}>
             <canvas ref={map_canvas_ref} 
                 style="position: absolute; top: 0; left: 0; width: 100%; height: 100%;" />
             <canvas ref={marker_canvas_ref} 
                 style="position: absolute; top: 0; left: 0; width: 100%; height: 100%;" />
+            <StatusBar
+                cursor={*cursor}
+                map_id={props.map.map_id}
+                map_name={props.map.name.clone()}
+                zoom={zoom}
+                on_go_to={props.on_go_to.clone()}
+            />
         </div>
     }
 }
@@ -330,8 +667,12 @@ fn canvas_map(props: &CanvasMapProps) -> Html {
 pub struct MarkerListPanelProps {
     pub zone_markers: Vec<Marker>,
     pub current_markers: Vec<Marker>,
-    pub on_update: Callback<Vec<Marker>>,
+    /// Called with the zone's markers after an edit, and whether it was typed
+    /// into a field, so a run of keystrokes can be undone as one step.
+    pub on_update: Callback<(Vec<Marker>, bool)>,
     pub world_bounds: (f32, f32, f32, f32),
+    pub selected: HashSet<u16>,
+    pub on_select: Callback<HashSet<u16>>,
 }
 
 #[function_component(MarkerListPanel)]
@@ -345,7 +686,6 @@ fn marker_list_panel(props: &MarkerListPanelProps) -> Html {
             || ()
         });
     }
-    // web_sys::console::log_1(&format!("current: {current:?}").into());
 
     let delete_style = Style::new(css!(r#"
         color: #fff;
@@ -362,11 +702,11 @@ fn marker_list_panel(props: &MarkerListPanelProps) -> Html {
     let on_update_cb  = props.on_update.clone();
     let icon_picker_for = use_state(|| None::<usize>);
 
-    let update_marker = {
+    let set_field = {
         let current = current.clone();
         let zone_for_upd  = zone_template.clone();
         let emit_for_upd  = on_update_cb.clone();
-        Callback::from(move |(pos, field, val): (usize, String, String)| {
+        Callback::from(move |(pos, field, val, typed): (usize, String, String, bool)| {
             let mut new_current = (*current).clone();
             if let Some(m) = new_current.get_mut(pos) {
                 match field.as_str() {
@@ -395,17 +735,16 @@ fn marker_list_panel(props: &MarkerListPanelProps) -> Html {
                         }
                     },
                     "size" => {
-                        match m {
-                            Marker::M0r(marker) => {marker.size = val.parse::<f32>().unwrap_or(1.0)}
-                            _ => {},
+                        if let Marker::M0r(marker) = m {
+                            marker.size = val.parse::<f32>().unwrap_or(1.0)
                         }
                     },
                     "pitch" => {
-                        match m {
-                            Marker::M0r(marker) => {
-                                let new_pitch = val.parse::<i8>();
-                                if new_pitch.is_err() {marker.orientation = None} else {
-                                    let pitch = new_pitch.unwrap().clamp(-90, 90);
+                        if let Marker::M0r(marker) = m {
+                            match val.parse::<i8>() {
+                                Err(_) => marker.orientation = None,
+                                Ok(new_pitch) => {
+                                    let pitch = new_pitch.clamp(-90, 90);
                                     if let Some(existing) = marker.orientation {
                                         marker.orientation = Some((pitch, existing.1));
                                     } else {
@@ -413,15 +752,14 @@ fn marker_list_panel(props: &MarkerListPanelProps) -> Html {
                                     }
                                 }
                             }
-                            _ => {},
                         }
                     },
                     "yaw" => {
-                        match m {
-                            Marker::M0r(marker) => {
-                                let new_yaw = val.parse::<i16>();
-                                if new_yaw.is_err() {marker.orientation = None} else {
-                                    let yaw = new_yaw.unwrap().clamp(0, 360);
+                        if let Marker::M0r(marker) = m {
+                            match val.parse::<i16>() {
+                                Err(_) => marker.orientation = None,
+                                Ok(new_yaw) => {
+                                    let yaw = new_yaw.clamp(0, 360);
                                     if let Some(existing) = marker.orientation {
                                         marker.orientation = Some((existing.0, yaw));
                                     } else {
@@ -429,49 +767,39 @@ fn marker_list_panel(props: &MarkerListPanelProps) -> Html {
                                     }
                                 }
                             }
-                            _ => {},
                         }
                     },
                     "text" => {
-                        match m {
-                            Marker::M0r(marker) => {
-                                if val.is_empty() {
-                                    marker.text = None;
-                                } else {
-                                    marker.text = Some(val);
-                                }
+                        if let Marker::M0r(marker) = m {
+                            if val.is_empty() {
+                                marker.text = None;
+                            } else {
+                                marker.text = Some(val);
                             }
-                            _ => {},
                         }
                     },
                     "colour" => {
-                        match m {
-                            Marker::M0r(marker) => {
-                                let x = val.trim_start_matches('#').parse::<u32>().unwrap_or(65535);
-                                marker.colour = hex_to_rgba(x);
-                            }
-                            _ => {},
+                        if let Marker::M0r(marker) = m {
+                            let x = val.trim_start_matches('#').parse::<u32>().unwrap_or(65535);
+                            marker.colour = hex_to_rgba(x);
                         }
                     },
                     "colour_tuple" => {
-                        match m {
-                            Marker::M0r(marker) => {
-                                let nums: Vec<u8> = val
-                                    .trim()
-                                    .trim_start_matches('(')
-                                    .trim_end_matches(')')
-                                    .split(',')
-                                    .filter_map(|p| p.trim().parse::<u8>().ok())
-                                    .collect();
-                                if nums.len() >= 4 {
-                                    let r = nums[0];
-                                    let g = nums[1];
-                                    let b = nums[2];
-                                    let a = nums[3];
-                                    marker.colour = (r, g, b, a);
-                                }
+                        if let Marker::M0r(marker) = m {
+                            let nums: Vec<u8> = val
+                                .trim()
+                                .trim_start_matches('(')
+                                .trim_end_matches(')')
+                                .split(',')
+                                .filter_map(|p| p.trim().parse::<u8>().ok())
+                                .collect();
+                            if nums.len() >= 4 {
+                                let r = nums[0];
+                                let g = nums[1];
+                                let b = nums[2];
+                                let a = nums[3];
+                                marker.colour = (r, g, b, a);
                             }
-                            _ => {},
                         }
                     },
                     _ => {}
@@ -483,14 +811,18 @@ fn marker_list_panel(props: &MarkerListPanelProps) -> Html {
                 .map(|zm| {
                     new_current
                         .iter()
-                        .find(|cm| get_marker_id(*cm) == get_marker_id(zm))
+                        .find(|cm| get_marker_id(cm) == get_marker_id(zm))
                         .cloned()
                         .unwrap_or_else(|| zm.clone())
                 })
                 .collect();
-            emit_for_upd.emit(rebuilt);
+            emit_for_upd.emit((rebuilt, typed));
         })
     };
+    let update_marker = {
+        let set_field = set_field.clone();
+        Callback::from(move |(pos, field, val): (usize, String, String)| set_field.emit((pos, field, val, true)))
+    };
 
     let toggle_active = {
         let current = current.clone();
@@ -507,12 +839,12 @@ fn marker_list_panel(props: &MarkerListPanelProps) -> Html {
                 .map(|zm| {
                     new_current
                         .iter()
-                        .find(|cm| get_marker_id(*cm) == get_marker_id(zm))
+                        .find(|cm| get_marker_id(cm) == get_marker_id(zm))
                         .cloned()
                         .unwrap_or_else(|| zm.clone())
                 })
                 .collect();
-            emit_for_toggle.emit(rebuilt);
+            emit_for_toggle.emit((rebuilt, false));
         })
     };
 
@@ -521,26 +853,59 @@ fn marker_list_panel(props: &MarkerListPanelProps) -> Html {
         let zone_for_delete = zone_template.clone();
         let emit_for_delete = on_update_cb.clone();
         Callback::from(move |pos: usize| {
-            if let Some(to_delete) = (*current).get(pos).map(|m| get_marker_id(m)) {
+            if let Some(to_delete) = (*current).get(pos).map(get_marker_id) {
                 let filtered_full: Vec<Marker> = zone_for_delete
                     .iter()
-                    .cloned()
                     .filter(|zm| get_marker_id(zm) != to_delete)
+                    .cloned()
                     .collect();
 
                 let new_current: Vec<Marker> = (*current)
                     .iter()
-                    .filter(|cm| get_marker_id(&**cm) != to_delete)
+                    .filter(|cm| get_marker_id(cm) != to_delete)
                     .cloned()
                     .collect();
                 current.set(new_current);
 
-                emit_for_delete.emit(filtered_full);
+                emit_for_delete.emit((filtered_full, false));
+            }
+        })
+    };
+
+    let toggle_selected = {
+        let selected = props.selected.clone();
+        let on_select = props.on_select.clone();
+        Callback::from(move |(id, is_on): (u16, bool)| {
+            let mut new_selected = selected.clone();
+            if is_on {
+                new_selected.insert(id);
+            } else {
+                new_selected.remove(&id);
             }
+            on_select.emit(new_selected);
         })
     };
 
     let input_text_style = css!("width: 5em; height: 1em; flex-basis: 26%;");
+    let row_style = |id: u16| format!(
+        "display:flex;align-items:center;gap:1em;justify-content:center;padding:4px;{}",
+        if props.selected.contains(&id) { "background:rgba(255,255,255,0.12);" } else { "" }
+    );
+    let select_box = |id: u16| {
+        let sel = toggle_selected.clone();
+        html! {
+            <input
+                type="checkbox"
+                checked={props.selected.contains(&id)}
+                style="cursor:pointer;"
+                title="Select"
+                onchange={Callback::from(move |e: Event| {
+                    let inp: HtmlInputElement = e.target_unchecked_into();
+                    sel.emit((id, inp.checked()));
+                })}
+            />
+        }
+    };
 
     html! {
         <div style="display:flex;flex-direction:column;max-height:85vh;margin-bottom:5vh;text-shadow: 2px 1.5px black;">
@@ -561,7 +926,8 @@ fn marker_list_panel(props: &MarkerListPanelProps) -> Html {
                     match marker {
                         Marker::Elms(marker) => {
                             html! {
-                            <li key={marker.id.clone()} style="display:flex;align-items:center;gap:1em;justify-content:center;padding:4px;">
+                            <li key={marker.id} style={row_style(marker.id)}>
+                                { select_box(marker.id) }
                                 <img
                                     src={format!("static/icons/{}", String::from(marker.icon))}
                                     style="height:2em;cursor:pointer;"
@@ -633,7 +999,8 @@ fn marker_list_panel(props: &MarkerListPanelProps) -> Html {
                         }
                         Marker::M0r(marker) => {
                             html!{
-                                <li key={marker.id.clone()} style="display:flex;align-items:center;gap:1em;justify-content:center;padding:4px;">
+                                <li key={marker.id} style={row_style(marker.id)}>
+                                { select_box(marker.id) }
                                     if let Some(svg_data) = get_svg(&marker.background_texture) {
                                         <svg
                                             height="2em"
@@ -678,7 +1045,7 @@ fn marker_list_panel(props: &MarkerListPanelProps) -> Html {
                                                         min={min.to_string()}
                                                         max={max.to_string()}
                                                         step="25"
-                                                        title={format!("{}", axis.to_uppercase())}
+                                                        title={axis.to_uppercase()}
                                                         value={val_str.clone()}
                                                         oninput={Callback::from(move |e: InputEvent| {
                                                             let inp: HtmlInputElement = e.target_unchecked_into();
@@ -814,10 +1181,10 @@ fn marker_list_panel(props: &MarkerListPanelProps) -> Html {
                         Callback::from(move |_| picker.set(None))
                     };
                     let choose = {
-                        let upd = update_marker.clone();
+                        let upd = set_field.clone();
                         let picker = icon_picker_for.clone();
                         Callback::from(move |icon_name: String| {
-                            upd.emit((idx, "icon".into(), icon_name));
+                            upd.emit((idx, "icon".into(), icon_name, false));
                             picker.set(None);
                         })
                     };
@@ -833,7 +1200,7 @@ fn marker_list_panel(props: &MarkerListPanelProps) -> Html {
                                         MarkerTypes::Elms => html! {
                                             for ALL_ELMS_ICONS.iter().map(|icon| {
                                                 let choose = choose.clone();
-                                                let icon_name = String::from(icon.clone());
+                                                let icon_name = String::from(*icon);
                                                 let src = format!("static/icons/{}", icon_name.clone());
                                                 html! {
                                                     <div style="text-align:center;">
@@ -952,19 +1319,85 @@ fn marker_list_panel(props: &MarkerListPanelProps) -> Html {
 
 #[function_component(App)]
 fn app() -> Html {
-    let zones = populate_zone_data();
+    // The built-in zones until `static/zones.json` has been fetched.
+    let zone_list = use_state(populate_zone_data);
+    let zones = (*zone_list).clone();
     let zone_ids: Vec<u16> = zones.iter().map(|z| z.id).collect();
     let selected_zone_index = use_state(|| 0_usize);
     let selected_map_index = use_state(|| 0_usize);
-    let marker_input = use_state(|| String::new());
-    let parsed_markers = use_state(|| HashMap::<u16, Vec<Marker>>::new());
-    let parsed_lines = use_state(|| HashMap::<u16, Vec<BreadcrumbLine>>::new());
+    let marker_input = use_state(String::new);
+    let parsed_markers = use_state(HashMap::<u16, Vec<Marker>>::new);
+    let parsed_lines = use_state(HashMap::<u16, Vec<BreadcrumbLine>>::new);
+    let parse_diagnostics = use_state(Vec::<(Diagnostic, String)>::new);
+    let selected_markers = use_state(HashSet::<u16>::new);
+    let selected_lines = use_state(HashSet::<u16>::new);
+    let conversion_notes = use_state(Vec::<String>::new);
+    let history = use_mut_ref(History::default);
+    // Names, notes and groups, kept beside the markers as the undo history is.
+    let annotations = use_mut_ref(Annotations::default);
     let zoom = use_state(|| 1.0);
     let pan = use_state(|| (0.0, 0.0));
     let canvas_size = use_state(|| 0);
     let window_width = use_state(|| 0.0);
     let window_height = use_state(|| 0.0);
 
+    let catalogue_sources = use_mut_ref(|| None::<catalogue::Sources>);
+    let catalogue_errors = use_state(Vec::<String>::new);
+    let zone_override = use_state(storage::load_zone_override);
+    // Bumped whenever the zones are rebuilt. 0 until the first fetch finishes.
+    let catalogue_generation = use_state(|| 0_u32);
+    let catalogue_builds = use_mut_ref(|| 0_u32);
+    // The zone and map on screen, so a rebuilt catalogue can keep showing them.
+    let shown_view = use_mut_ref(|| (0_u16, 0_u16));
+
+    let rebuild_zones = {
+        let catalogue_sources = catalogue_sources.clone();
+        let catalogue_errors = catalogue_errors.clone();
+        let zone_override = zone_override.clone();
+        let catalogue_generation = catalogue_generation.clone();
+        let catalogue_builds = catalogue_builds.clone();
+        let shown_view = shown_view.clone();
+        let zone_list = zone_list.clone();
+        let selected_zone_index = selected_zone_index.clone();
+        let selected_map_index = selected_map_index.clone();
+        Rc::new(move |user: Option<(String, String)>| {
+            let sources = catalogue_sources.borrow();
+            let Some(sources) = sources.as_ref() else { return };
+            let (new_zones, errors) = catalogue::build(sources, user.as_ref());
+
+            let (zone_id, map_id) = *shown_view.borrow();
+            let zone_idx = new_zones.iter().position(|z| z.id == zone_id).unwrap_or(0);
+            selected_map_index.set(new_zones[zone_idx].maps.iter().position(|m| m.map_id == map_id).unwrap_or(0));
+            selected_zone_index.set(zone_idx);
+            zone_list.set(new_zones);
+            catalogue_errors.set(errors);
+            zone_override.set(user);
+
+            let mut builds = catalogue_builds.borrow_mut();
+            *builds += 1;
+            catalogue_generation.set(*builds);
+        })
+    };
+    {
+        let catalogue_sources = catalogue_sources.clone();
+        let rebuild_zones = rebuild_zones.clone();
+        use_effect_with((), move |_| {
+            spawn_local(async move {
+                *catalogue_sources.borrow_mut() = Some(catalogue::fetch_sources().await);
+                rebuild_zones(storage::load_zone_override());
+            });
+            || ()
+        });
+    }
+
+    let on_zone_override = {
+        let rebuild_zones = rebuild_zones.clone();
+        Callback::from(move |file: Option<(String, String)>| {
+            storage::write_zone_override(file.as_ref().map(|(name, contents)| (name.as_str(), contents.as_str())));
+            rebuild_zones(file);
+        })
+    };
+
     let on_map_change = {
         let selected_map_index = selected_map_index.clone();
         let zoom = zoom.clone();
@@ -973,16 +1406,52 @@ fn app() -> Html {
             let sel: HtmlInputElement = e.target_unchecked_into();
             if let Ok(idx) = sel.value().parse::<usize>() {
                 selected_map_index.set(idx);
-                // web_sys::console::log_1(&format!("2Setting map index to '{}'", idx).into());
                 zoom.set(1.0);
                 pan.set( (0.0, 0.0) );
             }
         })
     };
 
+    // Centres the map on a typed position, changing zone and map if the
+    // current one doesn't hold it.
+    let go_to = {
+        let zones = zones.clone();
+        let selected_zone_index = selected_zone_index.clone();
+        let selected_map_index = selected_map_index.clone();
+        let selected_markers = selected_markers.clone();
+        let selected_lines = selected_lines.clone();
+        let conversion_notes = conversion_notes.clone();
+        let canvas_size = canvas_size.clone();
+        let zoom = zoom.clone();
+        let pan = pan.clone();
+        Callback::from(move |(x, y, z): (i32, Option<i32>, i32)| {
+            let Some((zone_idx, map_idx)) = locate(x, y, z, &zones, (*selected_zone_index, *selected_map_index)) else {
+                conversion_notes.set(vec![format!("{}, {} isn't on any map.", x, z)]);
+                return;
+            };
+            if zone_idx != *selected_zone_index {
+                selected_zone_index.set(zone_idx);
+                selected_markers.set(HashSet::new());
+                selected_lines.set(HashSet::new());
+            }
+            selected_map_index.set(map_idx);
+            let size = *canvas_size as f64;
+            let (nx, nz) = zones[zone_idx].maps[map_idx].scale_data.normalise(x as f64, z as f64);
+            let new_zoom = f64::max(*zoom, GO_TO_ZOOM);
+            let scaled = size * new_zoom;
+            zoom.set(new_zoom);
+            pan.set((
+                (size / 2.0 - nx * scaled).clamp(size - scaled, 0.0),
+                (size / 2.0 - nz * scaled).clamp(size - scaled, 0.0),
+            ));
+        })
+    };
+
     let on_zone_change = {
         let selected_zone_index = selected_zone_index.clone();
         let selected_map_index = selected_map_index.clone();
+        let selected_markers = selected_markers.clone();
+        let selected_lines = selected_lines.clone();
         let zoom = zoom.clone();
         let pan = pan.clone();
         Callback::from(move |e: Event| {
@@ -990,7 +1459,8 @@ fn app() -> Html {
             if let Ok(idx) = sel.value().parse::<usize>() {
                 selected_zone_index.set(idx);
                 selected_map_index.set(0);
-                // web_sys::console::log_1(&format!("2Setting zone index to '{}'", idx).into());
+                selected_markers.set(HashSet::new());
+                selected_lines.set(HashSet::new());
                 zoom.set(1.0);
                 pan.set( (0.0, 0.0) );
             }
@@ -1043,10 +1513,194 @@ fn app() -> Html {
     let dragging = use_state(|| false);
     let last = use_state(|| (0.0, 0.0));
 
+    // The marker being dragged and every marker as it was when the drag began,
+    // so the whole drag becomes one undo entry.
+    let dragged_marker = use_state(|| None::<(u16, HashMap<u16, Vec<Marker>>)>);
+
+    let snap = use_state(Snap::default);
+
+    // Loaded and tinted images, shared by the map and image exports.
+    let icon_cache = use_memo((), |_| IconCache::new());
+
+    // The mouse position in unzoomed canvas pixels, while drawing or measuring.
+    let hover = use_state(|| None::<(f64, f64)>);
+
+    // The line tool: whether clicks draw, the colour and the end of the line
+    // being drawn.
+    let drawing_lines = use_state(|| false);
+    let line_colour = use_state(|| (255_u8, 255_u8, 255_u8, 255_u8));
+    let line_anchor = use_state(|| None::<Position3D>);
+    // Where the line end being dragged started, and every line as it was.
+    let dragged_endpoint = use_state(|| None::<(Position3D, HashMap<u16, Vec<BreadcrumbLine>>)>);
+
+    // The ruler: whether clicks measure, where the measurement being made
+    // starts, and the ones pinned on each map.
+    let ruler = use_state(|| false);
+    let ruler_start = use_state(|| None::<Point>);
+    let measurements = use_state(Vec::<(u16, Measurement)>::new);
+
+    // Points clicked on the map for the pattern generator, in world X/Z.
+    let picks = use_state(Vec::<(f64, f64)>::new);
+    let picking = use_state(|| None::<Pick>);
+
+    // Shift+drag draws a box: its corners in unzoomed canvas pixels, and
+    // whether it adds to the selection (Ctrl held too) rather than replacing it.
+    let selection_box = use_state(|| None::<((f64, f64), (f64, f64), bool)>);
+
     let onmousedown = {
         let dragging = dragging.clone();
+        let dragged_marker = dragged_marker.clone();
+        let selection_box = selection_box.clone();
+        let selected_markers = selected_markers.clone();
+        let selected_lines = selected_lines.clone();
         let last = last.clone();
+        let zones = zones.clone();
+        let selected_zone_index = selected_zone_index.clone();
+        let selected_map_index = selected_map_index.clone();
+        let parsed_markers = parsed_markers.clone();
+        let parsed_lines = parsed_lines.clone();
+        let canvas_size = canvas_size.clone();
+        let zoom = zoom.clone();
+        let pan = pan.clone();
+        let picks = picks.clone();
+        let picking = picking.clone();
+        let drawing_lines = drawing_lines.clone();
+        let line_anchor = line_anchor.clone();
+        let line_colour = line_colour.clone();
+        let dragged_endpoint = dragged_endpoint.clone();
+        let history = history.clone();
+        let annotations = annotations.clone();
+        let snap = snap.clone();
+        let ruler = ruler.clone();
+        let ruler_start = ruler_start.clone();
+        let measurements = measurements.clone();
         Callback::from(move |e: MouseEvent| {
+            let zone = &zones[*selected_zone_index];
+            let toggle = e.ctrl_key() || e.meta_key();
+            // What's on hidden or locked layers can't be picked or dragged.
+            let mut editable_markers = parsed_markers.get(&zone.id).cloned().unwrap_or_default();
+            let mut editable_lines = parsed_lines.get(&zone.id).cloned().unwrap_or_default();
+            annotations.borrow().retain_editable(zone.id, &mut editable_markers, &mut editable_lines);
+            if e.button() == 0
+                && let Some(pick) = *picking
+                && let Some(map) = zone.maps.get(*selected_map_index)
+                && let Some((px, pz)) = canvas_point(&e, *pan, *zoom)
+            {
+                let size = (*canvas_size).max(1) as f64;
+                let (x, z) = map.scale_data.denormalise(px / size, pz / size);
+                let markers = parsed_markers.get(&zone.id).map(Vec::as_slice).unwrap_or_default();
+                let lines = parsed_lines.get(&zone.id).map(Vec::as_slice).unwrap_or_default();
+                let snapped = snap_for(&e, *snap).point(x, z, markers, lines, map.map_id, world_distance(map, size, *zoom, SNAP_PIXELS));
+                let point = (snapped.x as f64, snapped.z as f64);
+                match pick {
+                    Pick::One => {
+                        picks.set(vec![point]);
+                        picking.set(None);
+                    }
+                    Pick::Many => picks.set(picks.iter().copied().chain([point]).collect()),
+                }
+                return;
+            }
+            if e.button() == 0
+                && *ruler
+                && let Some(map) = zone.maps.get(*selected_map_index)
+                && let Some((px, pz)) = canvas_point(&e, *pan, *zoom)
+            {
+                let size = (*canvas_size).max(1) as f64;
+                let (x, z) = map.scale_data.denormalise(px / size, pz / size);
+                let markers = parsed_markers.get(&zone.id).map(Vec::as_slice).unwrap_or_default();
+                let lines = parsed_lines.get(&zone.id).map(Vec::as_slice).unwrap_or_default();
+                // Ends snap to markers and line ends, so the height difference is known.
+                let snapped = snap_for(&e, Snap { magnet: true, ..*snap }).point(x, z, markers, lines, map.map_id, world_distance(map, size, *zoom, SNAP_PIXELS));
+                let point = Point { x: snapped.x as f64, z: snapped.z as f64, y: snapped.y };
+                match *ruler_start {
+                    Some(from) => {
+                        measurements.set(measurements.iter().copied().chain([(map.map_id, Measurement { from, to: point })]).collect());
+                        ruler_start.set(None);
+                    }
+                    None => ruler_start.set(Some(point)),
+                }
+                return;
+            }
+            if e.button() == 0
+                && e.shift_key()
+                && let Some(point) = canvas_point(&e, *pan, *zoom)
+            {
+                selection_box.set(Some((point, point, toggle)));
+                return;
+            }
+            if e.button() == 0
+                && toggle
+                && let Some(map) = zone.maps.get(*selected_map_index)
+                && let Some(point) = canvas_point(&e, *pan, *zoom)
+            {
+                let size = *canvas_size as f64;
+                if let Some(id) = marker_at(&editable_markers, map, size, *zoom, point) {
+                    let mut new_selected = (*selected_markers).clone();
+                    if !new_selected.remove(&id) {
+                        new_selected.insert(id);
+                    }
+                    selected_markers.set(new_selected);
+                } else if let Some(id) = line_at(&editable_lines, map, size, *zoom, point) {
+                    let mut new_selected = (*selected_lines).clone();
+                    if !new_selected.remove(&id) {
+                        new_selected.insert(id);
+                    }
+                    selected_lines.set(new_selected);
+                }
+                return;
+            }
+            if e.button() == 0
+                && *drawing_lines
+                && let Some(map) = zone.maps.get(*selected_map_index)
+                && let Some((px, pz)) = canvas_point(&e, *pan, *zoom)
+            {
+                let size = (*canvas_size).max(1) as f64;
+                let (x, z) = map.scale_data.denormalise(px / size, pz / size);
+                let markers = parsed_markers.get(&zone.id).map(Vec::as_slice).unwrap_or_default();
+                let lines = parsed_lines.get(&zone.id).cloned().unwrap_or_default();
+                let radius = world_distance(map, size, *zoom, SNAP_PIXELS);
+                // Line ends always join up with what's there, unless Alt is held.
+                let snapped = snap_for(&e, Snap { magnet: true, ..*snap }).point(x, z, markers, &lines, map.map_id, radius);
+                let point = Position3D {
+                    x: snapped.x,
+                    y: snapped.y.unwrap_or_else(|| line_anchor.map_or_else(|| placement_y(map), |a| a.y)),
+                    z: snapped.z,
+                };
+                if let Some(anchor) = *line_anchor
+                    && anchor != point
+                {
+                    let first_id = lines.iter().map(|l| l.id).max().map_or(0, |id| id.saturating_add(1));
+                    let mut new_map = (*parsed_lines).clone();
+                    new_map.entry(zone.id).or_default().extend(polyline(&[anchor, point], *line_colour, map.map_id, first_id));
+                    record_edit(&history, &annotations, (&parsed_markers, &parsed_lines), (&parsed_markers, &new_map), Some("Draw line"), false);
+                    parsed_lines.set(new_map);
+                }
+                line_anchor.set(Some(point));
+                return;
+            }
+            if e.button() == 0
+                && let Some(map) = zone.maps.get(*selected_map_index)
+                && let Some(point) = canvas_point(&e, *pan, *zoom)
+                && let Some(id) = marker_at(&editable_markers, map, *canvas_size as f64, *zoom, point)
+            {
+                dragged_marker.set(Some((id, (*parsed_markers).clone())));
+                return;
+            }
+            if e.button() == 0
+                && let Some(map) = zone.maps.get(*selected_map_index)
+                && let Some((px, pz)) = canvas_point(&e, *pan, *zoom)
+            {
+                let size = (*canvas_size).max(1) as f64;
+                let (x, z) = map.scale_data.denormalise(px / size, pz / size);
+                let radius = world_distance(map, size, *zoom, SNAP_PIXELS);
+                if let Some((id, end)) = endpoint_at(&editable_lines, map.map_id, x, z, radius)
+                    && let Some(line) = editable_lines.iter().find(|l| l.id == id)
+                {
+                    dragged_endpoint.set(Some((line.endpoint(end), (*parsed_lines).clone())));
+                    return;
+                }
+            }
             dragging.set(true);
             last.set((e.client_x() as f64, e.client_y() as f64));
         })
@@ -1054,18 +1708,126 @@ fn app() -> Html {
 
     let onmouseup = {
         let dragging = dragging.clone();
-        Callback::from(move |_| dragging.set(false))
+        let dragged_marker = dragged_marker.clone();
+        let parsed_markers = parsed_markers.clone();
+        let parsed_lines = parsed_lines.clone();
+        let history = history.clone();
+        let annotations = annotations.clone();
+        let selection_box = selection_box.clone();
+        let selected_markers = selected_markers.clone();
+        let selected_lines = selected_lines.clone();
+        let zones = zones.clone();
+        let selected_zone_index = selected_zone_index.clone();
+        let selected_map_index = selected_map_index.clone();
+        let canvas_size = canvas_size.clone();
+        let dragged_endpoint = dragged_endpoint.clone();
+        Callback::from(move |_: MouseEvent| {
+            if let Some((_, before)) = &*dragged_marker {
+                record_edit(&history, &annotations, (before, &parsed_lines), (&parsed_markers, &parsed_lines), None, false);
+                dragged_marker.set(None);
+            }
+            if let Some((_, before)) = &*dragged_endpoint {
+                record_edit(&history, &annotations, (&parsed_markers, before), (&parsed_markers, &parsed_lines), None, false);
+                dragged_endpoint.set(None);
+            }
+            if let Some((a, b, add)) = *selection_box {
+                let zone = &zones[*selected_zone_index];
+                if let Some(map) = zone.maps.get(*selected_map_index) {
+                    let size = (*canvas_size).max(1) as f64;
+                    let corner = |p: (f64, f64)| map.scale_data.denormalise(p.0 / size, p.1 / size);
+                    let rect = WorldRect::from_corners(corner(a), corner(b));
+                    let mut markers = parsed_markers.get(&zone.id).cloned().unwrap_or_default();
+                    let mut lines = parsed_lines.get(&zone.id).cloned().unwrap_or_default();
+                    annotations.borrow().retain_editable(zone.id, &mut markers, &mut lines);
+                    let (mut new_markers, mut new_lines) = (markers_in(&markers, map.map_id, &rect), lines_in(&lines, map.map_id, &rect));
+                    if add {
+                        new_markers.extend(selected_markers.iter());
+                        new_lines.extend(selected_lines.iter());
+                    }
+                    selected_markers.set(new_markers);
+                    selected_lines.set(new_lines);
+                }
+                selection_box.set(None);
+            }
+            dragging.set(false);
+        })
     };
 
     let onmousemove = {
         let dragging = dragging.clone();
+        let dragged_marker = dragged_marker.clone();
         let last = last.clone();
         let pan = pan.clone();
         let zoom = zoom.clone();
+        let zones = zones.clone();
+        let selected_zone_index = selected_zone_index.clone();
+        let selected_map_index = selected_map_index.clone();
+        let parsed_markers = parsed_markers.clone();
+        let canvas_size = canvas_size.clone();
+        let selection_box = selection_box.clone();
+        let drawing_lines = drawing_lines.clone();
+        let ruler = ruler.clone();
+        let hover = hover.clone();
+        let dragged_endpoint = dragged_endpoint.clone();
+        let parsed_lines = parsed_lines.clone();
+        let snap = snap.clone();
         Callback::from(move |e: MouseEvent| {
-            if *dragging {
-                let (lx, ly) = *last;
-                let nx = e.client_x() as f64;
+            if *drawing_lines || *ruler {
+                hover.set(canvas_point(&e, *pan, *zoom));
+            }
+            if let Some((start, _, add)) = *selection_box {
+                if let Some(point) = canvas_point(&e, *pan, *zoom) {
+                    selection_box.set(Some((start, point, add)));
+                }
+            } else if let Some((from, before)) = &*dragged_endpoint {
+                let zone = &zones[*selected_zone_index];
+                let Some(map) = zone.maps.get(*selected_map_index) else { return };
+                let Some((px, pz)) = canvas_point(&e, *pan, *zoom) else { return };
+                let size = (*canvas_size).max(1) as f64;
+                let (x, z) = map.scale_data.denormalise(px / size, pz / size);
+
+                // Snaps to anything but the lines meeting at the end being dragged.
+                let mut lines = before.get(&zone.id).cloned().unwrap_or_default();
+                let others: Vec<BreadcrumbLine> = lines.iter().filter(|l| l.position1 != *from && l.position2 != *from).cloned().collect();
+                let markers = parsed_markers.get(&zone.id).map(Vec::as_slice).unwrap_or_default();
+                let radius = world_distance(map, size, *zoom, SNAP_PIXELS);
+                let snapped = snap_for(&e, Snap { magnet: true, ..*snap }).point(x, z, markers, &others, map.map_id, radius);
+                let to = Position3D { x: snapped.x, y: snapped.y.unwrap_or(from.y), z: snapped.z };
+                move_endpoint(&mut lines, map.map_id, *from, to);
+
+                let mut new_map = (*parsed_lines).clone();
+                new_map.insert(zone.id, lines);
+                parsed_lines.set(new_map);
+            } else if let Some((id, _)) = &*dragged_marker {
+                let zone = &zones[*selected_zone_index];
+                let Some(map) = zone.maps.get(*selected_map_index) else { return };
+                let Some((px, pz)) = canvas_point(&e, *pan, *zoom) else { return };
+                let size = *canvas_size as f64;
+                let (x, z) = map.scale_data.denormalise(px / size, pz / size);
+                let others: Vec<Marker> = parsed_markers.get(&zone.id).into_iter().flatten().filter(|m| get_marker_id(m) != *id).cloned().collect();
+                let lines = parsed_lines.get(&zone.id).map(Vec::as_slice).unwrap_or_default();
+                let snapped = snap_for(&e, *snap).point(x, z, &others, lines, map.map_id, world_distance(map, size, *zoom, SNAP_PIXELS));
+
+                let mut new_map = (*parsed_markers).clone();
+                let dragged = new_map.get_mut(&zone.id)
+                    .and_then(|markers| markers.iter_mut().find(|m| get_marker_id(m) == *id));
+                if let Some(marker) = dragged {
+                    let mut position = get_marker_position(marker);
+                    position.x = snapped.x;
+                    position.z = snapped.z;
+                    set_marker_position(marker, position);
+
+                    let map_id = if map.scale_data.contains(snapped.x as f64, snapped.z as f64) {
+                        map.map_id
+                    } else {
+                        find_best_map(position.x, position.y, position.z, zone).map_or(0, |m| m.map_id)
+                    };
+                    set_marker_map_id(marker, map_id);
+                    parsed_markers.set(new_map);
+                }
+            } else if *dragging {
+                let (lx, ly) = *last;
+                let nx = e.client_x() as f64;
                 let ny = e.client_y() as f64;
                 let dx = nx - lx;
                 let dy = ny - ly;
@@ -1089,104 +1851,242 @@ fn app() -> Html {
         let selected_zone_index = selected_zone_index.clone();
         let selected_map_index = selected_map_index.clone();
         let parsed_markers = parsed_markers.clone();
+        let parsed_lines = parsed_lines.clone();
+        let history = history.clone();
+        let annotations = annotations.clone();
         let canvas_size = canvas_size.clone();
         let zoom = zoom.clone();
         let pan = pan.clone();
+        let drawing_lines = drawing_lines.clone();
+        let line_anchor = line_anchor.clone();
+        let ruler = ruler.clone();
+        let ruler_start = ruler_start.clone();
+        let snap = snap.clone();
         Callback::from(move |e: MouseEvent| {
             e.prevent_default();
 
-            if let Some(canvas) = e.target_dyn_into::<HtmlCanvasElement>() {
-                let canvas_rect = canvas.get_bounding_client_rect();
-                let mx = e.client_x() as f64 - canvas_rect.left();
-                let my = e.client_y() as f64 - canvas_rect.top();
-
-                let zoom = *zoom;
-                let pan = *pan;
-                let world_x = (mx - pan.0) / zoom;
-                let world_z = (my - pan.1) / zoom;
+            // Right click ends the line being drawn, or drops the measurement
+            // being made, rather than placing a marker.
+            if *drawing_lines {
+                line_anchor.set(None);
+                return;
+            }
+            if *ruler {
+                ruler_start.set(None);
+                return;
+            }
 
+            if let Some((world_x, world_z)) = canvas_point(&e, *pan, *zoom) {
                 let size = *canvas_size as f64;
 
                 let zone = &zones[*selected_zone_index];
                 let map = zone.maps.get(*selected_map_index).cloned();
                 if let Some(map) = map {
                     let scale = &map.scale_data;
-                    let nx = world_x / size;
-                    let nz = world_z / size;
-                    let pos_x = scale.min_x as f64 + nx * (scale.max_x - scale.min_x) as f64;
-                    let pos_y = scale.y.unwrap_or(0.0);
-                    let pos_z = scale.min_z as f64 + nz * (scale.max_z - scale.min_z) as f64;
+                    let (pos_x, pos_z) = scale.denormalise(world_x / size, world_z / size);
+                    let zone_markers = parsed_markers.get(&zone.id).map(Vec::as_slice).unwrap_or_default();
+                    let zone_lines = parsed_lines.get(&zone.id).map(Vec::as_slice).unwrap_or_default();
+                    let snapped = snap_for(&e, *snap).point(pos_x, pos_z, zone_markers, zone_lines, map.map_id, world_distance(&map, size, *zoom, SNAP_PIXELS));
 
                     let marker = ElmMarker {
                         active: true,
                         icon: "squares/marker_lightblue.png".into(),
                         position: Position3D {
-                            x: pos_x.round() as i32,
-                            y: pos_y.round() as i32,
-                            z: pos_z.round() as i32,
+                            x: snapped.x,
+                            y: snapped.y.unwrap_or_else(|| placement_y(&map)),
+                            z: snapped.z,
                         },
                         size: 1,
-                        id: parsed_markers.get(&zone.id).map(|v| v.len() as u16).unwrap_or(0),
+                        id: parsed_markers.get(&zone.id).and_then(|v| v.iter().map(get_marker_id).max()).map_or(0, |id| id + 1),
                         map_id: map.map_id,
                     };
 
                     let mut new_map = (*parsed_markers).clone();
                     let entry = new_map.entry(zone.id).or_default();
                     entry.push(Elms(marker));
+                    record_edit(&history, &annotations, (&parsed_markers, &parsed_lines), (&new_map, &parsed_lines), Some("Place marker"), false);
                     parsed_markers.set(new_map);
                 }
             }
         })
     };
 
-    let oninput = {
+    // Replaces everything with a new combined string: a paste, a loaded layout
+    // or a recovered session.
+    let load_string = {
         let elms_input = marker_input.clone();
         let parsed_markers = parsed_markers.clone();
         let parsed_lines = parsed_lines.clone();
+        let parse_diagnostics = parse_diagnostics.clone();
+        let selected_markers = selected_markers.clone();
+        let selected_lines = selected_lines.clone();
+        let conversion_notes = conversion_notes.clone();
+        let history = history.clone();
+        let annotations = annotations.clone();
         let zones = zones.clone();
+        Callback::from(move |(v, label): (String, String)| {
+            elms_input.set(v.clone());
+            selected_markers.set(HashSet::new());
+            selected_lines.set(HashSet::new());
+            conversion_notes.set(Vec::new());
+
+            if !v.is_empty() {
+                let parsed = parse_combined_string(&v, zones.clone());
+                parse_diagnostics.set(parsed.diagnostics.into_iter().map(|d| {
+                    let snippet: String = v.get(d.span.clone()).unwrap_or("").chars().take(40).collect();
+                    (d, snippet)
+                }).collect());
+
+                let (combined_markers, new_lines) = parsed.data;
+                // Ids are numbered afresh, so notes follow the markers and
+                // lines they belong to instead.
+                let old = annotations.borrow().clone();
+                let remapped = old.remap((&parsed_markers, &parsed_lines), (&combined_markers, &new_lines));
+                push_edit(&history, &old, &remapped, (&parsed_markers, &parsed_lines), (&combined_markers, &new_lines), Some(&label), false);
+                *annotations.borrow_mut() = remapped;
+                parsed_markers.set(combined_markers);
+                parsed_lines.set(new_lines);
+            } else {
+                let old = annotations.borrow().clone();
+                let cleared = old.remap((&parsed_markers, &parsed_lines), (&HashMap::new(), &HashMap::new()));
+                push_edit(&history, &old, &cleared, (&parsed_markers, &parsed_lines), (&HashMap::new(), &HashMap::new()), Some("Clear"), false);
+                *annotations.borrow_mut() = cleared;
+                storage::write_autosave("", None, get_timestamp());
+                parsed_markers.set(HashMap::new());
+                parsed_lines.set(HashMap::new());
+                parse_diagnostics.set(Vec::new());
+            }
+        })
+    };
+
+    let oninput = {
+        let load_string = load_string.clone();
         Callback::from(move |e: InputEvent| {
             let input: HtmlInputElement = e.target_unchecked_into();
             let v = input.value();
-            // web_sys::console::log_1(&format!("Textarea input: '{}'", v).into());
-
-            elms_input.set(v.clone());
+            load_string.emit((v, "Paste".to_string()));
+        })
+    };
 
-            if !v.is_empty() {
-                let new_m0r_markers = parse_m0r_string(&v, zones.clone());
+    // Replaces everything with a project, keeping its names, notes and layers.
+    let load_project = {
+        let parsed_markers = parsed_markers.clone();
+        let parsed_lines = parsed_lines.clone();
+        let elms_input = marker_input.clone();
+        let parse_diagnostics = parse_diagnostics.clone();
+        let selected_markers = selected_markers.clone();
+        let selected_lines = selected_lines.clone();
+        let conversion_notes = conversion_notes.clone();
+        let history = history.clone();
+        let annotations = annotations.clone();
+        Callback::from(move |(project, label): (Project, String)| {
+            let ((markers, lines), opened) = project.into_parts();
+            push_edit(&history, &annotations.borrow(), &opened, (&parsed_markers, &parsed_lines), (&markers, &lines), Some(&label), false);
+            *annotations.borrow_mut() = opened;
+            elms_input.set(build_combined_string(&markers, &lines, get_timestamp()));
+            parse_diagnostics.set(Vec::new());
+            selected_markers.set(HashSet::new());
+            selected_lines.set(HashSet::new());
+            conversion_notes.set(Vec::new());
+            parsed_markers.set(markers);
+            parsed_lines.set(lines);
+        })
+    };
 
-                let new_elms_markers = parse_elms_string(&v, zones.clone());
+    // Saved layouts and autosaves from before projects were kept have only
+    // the combined string.
+    let load_saved = {
+        let load_string = load_string.clone();
+        let load_project = load_project.clone();
+        Callback::from(move |(data, project, label): (String, Option<Project>, String)| match project {
+            Some(project) => load_project.emit((project, label)),
+            None => load_string.emit((data, label)),
+        })
+    };
 
-                let mut combined_markers = new_m0r_markers;
+    let on_load_layout = {
+        let load_saved = load_saved.clone();
+        Callback::from(move |layout: SavedLayout| {
+            load_saved.emit((layout.data, layout.project, format!("Load '{}'", layout.name)));
+        })
+    };
 
-                for (key, mut markers) in new_elms_markers {
-                    combined_markers
-                        .entry(key)
-                        .and_modify(|existing| existing.append(&mut markers))
-                        .or_insert(markers);
+    // Whatever was autosaved when the page was last open, restored on load
+    // unless the page was opened from a share link.
+    let recovered = use_state(storage::load_autosave);
+    let shared_link = use_state(read_share_fragment);
+    // The view from a share link, applied once its markers have been parsed.
+    let pending_view = use_state(|| None::<ShareState>);
+    {
+        let recovered = recovered.clone();
+        let shared_link = shared_link.clone();
+        let pending_view = pending_view.clone();
+        let load_string = load_string.clone();
+        let load_saved = load_saved.clone();
+        let marker_input = marker_input.clone();
+        // Waits for the zone catalogue, so markers in zones it adds aren't
+        // thrown away as unknown.
+        use_effect_with(*catalogue_generation, move |generation| {
+            match generation {
+                0 => {}
+                1 => {
+                    if let Some(Ok(state)) = &*shared_link {
+                        load_string.emit((state.data.clone(), "Open share link".to_string()));
+                        pending_view.set(Some(state.clone()));
+                        clear_share_fragment();
+                    } else if let Some(autosave) = &*recovered {
+                        load_saved.emit((autosave.data.clone(), autosave.project.clone(), "Recover session".to_string()));
+                    }
                 }
-
-                for (_zone_id, markers) in combined_markers.iter_mut() {
-                    let mut next_id: u16 = 0;
-                    for m in markers.iter_mut() {
-                        match m {
-                            Marker::Elms(el) => el.id = next_id,
-                            Marker::M0r(m0r) => m0r.id = next_id,
-                        }
-                        next_id = next_id.saturating_add(1);
+                _ => {
+                    if !marker_input.is_empty() {
+                        load_string.emit(((*marker_input).clone(), "Reload zones".to_string()));
                     }
                 }
-                
-                parsed_markers.set(combined_markers);
+            }
+            || ()
+        });
+    }
+    {
+        let parsed_markers = parsed_markers.clone();
+        let parsed_lines = parsed_lines.clone();
+        // Empty input is only written by `load_string`, so the first render
+        // can't wipe the autosave before it has been recovered. Notes change
+        // without the input, so they're watched too.
+        use_effect_with(((*marker_input).clone(), annotations.borrow().clone()), move |(input, annotations)| {
+            if !input.is_empty() {
+                let project = Project::new(&parsed_markers, &parsed_lines, annotations);
+                storage::write_autosave(input, Some(&project), get_timestamp());
+            }
+            || ()
+        });
+    }
 
-                let new_lines = parse_lines_string(&v, zones.clone());
-                parsed_lines.set(new_lines);
-            } else {
-                parsed_markers.set(HashMap::new());
-                parsed_lines.set(HashMap::new());
+    let on_restore_recovery = {
+        let recovered = recovered.clone();
+        let load_saved = load_saved.clone();
+        Callback::from(move |_: MouseEvent| {
+            if let Some(autosave) = &*recovered {
+                load_saved.emit((autosave.data.clone(), autosave.project.clone(), "Recover session".to_string()));
             }
+            recovered.set(None);
         })
     };
 
+    let on_discard_recovery = {
+        let recovered = recovered.clone();
+        let load_string = load_string.clone();
+        Callback::from(move |_: MouseEvent| {
+            load_string.emit((String::new(), "Clear".to_string()));
+            recovered.set(None);
+        })
+    };
+
+    let on_keep_recovery = {
+        let recovered = recovered.clone();
+        Callback::from(move |_: MouseEvent| recovered.set(None))
+    };
+
     {
         let parsed_markers = parsed_markers.clone();
         let parsed_lines = parsed_lines.clone();
@@ -1201,20 +2101,18 @@ fn app() -> Html {
                 zones.extend(parsed_lines.keys().cloned());
                 zones.sort();
                 zones.dedup();
-                // web_sys::console::log_1(&format!("use_effect parsed_markers zones: {:?}", zones).into());
 
                 if !zones.is_empty() {
                     let current_zone = zone_ids[*selected_zone_index];
                     let first_zone = zones[0];
 
-                    if first_zone != current_zone {
-                        if let Some(idx) = zone_ids.iter().position(|&z| z == first_zone) {
-                            selected_zone_index.set(idx);
-                            selected_map_index.set(0);
-                            zoom.set(1.0);
-                            pan.set((0f64, 0f64));
-                            last.set((0f64, 0f64));
-                        }
+                    if first_zone != current_zone
+                        && let Some(idx) = zone_ids.iter().position(|&z| z == first_zone) {
+                        selected_zone_index.set(idx);
+                        selected_map_index.set(0);
+                        zoom.set(1.0);
+                        pan.set((0f64, 0f64));
+                        last.set((0f64, 0f64));
                     }
                 }
 
@@ -1223,6 +2121,32 @@ fn app() -> Html {
         );
     }
 
+    {
+        // Declared after the effect above so that a shared view wins over its
+        // jump to the first zone.
+        let pending_view = pending_view.clone();
+        let zones = zones.clone();
+        let selected_zone_index = selected_zone_index.clone();
+        let selected_map_index = selected_map_index.clone();
+        let zoom = zoom.clone();
+        let pan = pan.clone();
+        use_effect_with((parsed_markers.clone(), pending_view.clone(), *canvas_size), move |(_, view, size)| {
+            if let Some(view) = &**view
+                && *size > 0
+            {
+                if let Some(zone_idx) = zones.iter().position(|z| z.id == view.zone_id) {
+                    selected_zone_index.set(zone_idx);
+                    selected_map_index.set(zones[zone_idx].maps.iter().position(|m| m.map_id == view.map_id).unwrap_or(0));
+                }
+                let size = *size as f64;
+                zoom.set(view.zoom.clamp(1.0, 20.0));
+                pan.set((view.pan.0 * size, view.pan.1 * size));
+                pending_view.set(None);
+            }
+            || ()
+        });
+    }
+
     let do_redraw = use_state(|| false);
     let update_elms_input = {
         let parsed_markers = parsed_markers.clone();
@@ -1231,30 +2155,31 @@ fn app() -> Html {
         let selected_zone_index = selected_zone_index.clone();
         let redraw = do_redraw.clone();
         let zone_ids = zone_ids.clone();
+        let history = history.clone();
+        let annotations = annotations.clone();
 
-        Callback::from(move |update: (Option<Vec<Marker>>, Option<Vec<BreadcrumbLine>>)| {
-            let (maybe_markers, maybe_lines) = update;
+        Callback::from(move |update: (Option<Vec<Marker>>, Option<Vec<BreadcrumbLine>>, bool)| {
+            let (maybe_markers, maybe_lines, typed) = update;
             let zone_id = zone_ids[*selected_zone_index];
 
-            if let Some(markers) = maybe_markers {
-                let mut map = (*parsed_markers).clone();
-                map.insert(zone_id, markers);
-                parsed_markers.set(map);
+            let mut markers_map = (*parsed_markers).clone();
+            let mut lines_map = (*parsed_lines).clone();
+            if let Some(markers) = maybe_markers.clone() {
+                markers_map.insert(zone_id, markers);
             }
-            if let Some(lines) = maybe_lines {
-                let mut map = (*parsed_lines).clone();
-                map.insert(zone_id, lines);
-                parsed_lines.set(map);
+            if let Some(lines) = maybe_lines.clone() {
+                lines_map.insert(zone_id, lines);
             }
-            let markers_map = (*parsed_markers).clone();
-            let lines_map = (*parsed_lines).clone();
+            record_edit(&history, &annotations, (&parsed_markers, &parsed_lines), (&markers_map, &lines_map), None, typed);
 
-            let m0r_markers_str = build_m0r_string(&markers_map);
-            let elms_markers_str = build_elms_string(&markers_map);
-            let lines_str = lines_to_string(&lines_map);
+            if maybe_markers.is_some() {
+                parsed_markers.set(markers_map.clone());
+            }
+            if maybe_lines.is_some() {
+                parsed_lines.set(lines_map.clone());
+            }
 
-            let combined = format!("{}\n{}\n{}", lines_str, m0r_markers_str, elms_markers_str).trim().to_string();
-            elms_input.set(combined);
+            elms_input.set(build_combined_string(&markers_map, &lines_map, get_timestamp()));
             redraw.set(!*redraw);
         })
     };
@@ -1262,14 +2187,459 @@ fn app() -> Html {
     let update_markers = {
         let cb = update_elms_input.clone();
         Callback::from(move |markers: Vec<Marker>| {
-            cb.emit((Some(markers), None));
+            cb.emit((Some(markers), None, false));
+        })
+    };
+    let edit_marker_list = {
+        let cb = update_elms_input.clone();
+        Callback::from(move |(markers, typed): (Vec<Marker>, bool)| {
+            cb.emit((Some(markers), None, typed));
+        })
+    };
+
+    let convert_selected = {
+        let parsed_markers = parsed_markers.clone();
+        let selected_markers = selected_markers.clone();
+        let conversion_notes = conversion_notes.clone();
+        let selected_zone_index = selected_zone_index.clone();
+        let zone_ids = zone_ids.clone();
+        let update_markers = update_markers.clone();
+        Callback::from(move |to: MarkerTypes| {
+            let zone_id = zone_ids[*selected_zone_index];
+            let mut notes = Vec::new();
+            let markers: Vec<Marker> = parsed_markers.get(&zone_id).cloned().unwrap_or_default()
+                .iter()
+                .map(|m| {
+                    if !selected_markers.contains(&get_marker_id(m)) {
+                        return m.clone();
+                    }
+                    let conversion = convert_marker(m, to);
+                    let p = get_marker_position(m);
+                    for loss in &conversion.losses {
+                        notes.push(format!("Marker at {}, {}: {}", p.x, p.z, loss));
+                    }
+                    conversion.marker
+                })
+                .collect();
+            conversion_notes.set(notes);
+            update_markers.emit(markers);
+        })
+    };
+
+    let apply_bulk_edit = {
+        let parsed_markers = parsed_markers.clone();
+        let parsed_lines = parsed_lines.clone();
+        let selected_markers = selected_markers.clone();
+        let selected_lines = selected_lines.clone();
+        let conversion_notes = conversion_notes.clone();
+        let selected_zone_index = selected_zone_index.clone();
+        let zone_ids = zone_ids.clone();
+        let update_elms_input = update_elms_input.clone();
+        Callback::from(move |edit: BulkEdit| {
+            let zone_id = zone_ids[*selected_zone_index];
+            let mut markers = parsed_markers.get(&zone_id).cloned().unwrap_or_default();
+            let mut lines = parsed_lines.get(&zone_id).cloned().unwrap_or_default();
+            let selected_count = markers.iter().filter(|m| selected_markers.contains(&get_marker_id(m))).count()
+                + lines.iter().filter(|l| selected_lines.contains(&l.id)).count();
+
+            let applied = edit.apply(&mut markers, &mut lines, &selected_markers, &selected_lines);
+            let skipped = selected_count - applied;
+            let only = match edit {
+                BulkEdit::ElmsIcon(_) => Some("Icons only apply to Elms markers"),
+                BulkEdit::M0rTexture(_) => Some("Textures only apply to m0r markers"),
+                BulkEdit::Colour(_) => Some("Colour only applies to m0r markers and lines"),
+                BulkEdit::Size(_) => Some("Size only applies to m0r markers"),
+                BulkEdit::Delete | BulkEdit::SetActive(_) => None,
+            };
+            conversion_notes.set(match only {
+                Some(only) if skipped > 0 => vec![format!("{}, so {} selected {} left unchanged.", only, skipped, if skipped == 1 { "item was" } else { "items were" })],
+                _ => Vec::new(),
+            });
+            if edit == BulkEdit::Delete {
+                selected_markers.set(HashSet::new());
+                selected_lines.set(HashSet::new());
+            }
+            update_elms_input.emit((Some(markers), Some(lines), false));
+        })
+    };
+
+    let refresh = use_force_update();
+    let apply_metadata_edit = {
+        let parsed_markers = parsed_markers.clone();
+        let parsed_lines = parsed_lines.clone();
+        let history = history.clone();
+        let annotations = annotations.clone();
+        let selected_markers = selected_markers.clone();
+        let selected_lines = selected_lines.clone();
+        let selected_zone_index = selected_zone_index.clone();
+        let zone_ids = zone_ids.clone();
+        let refresh = refresh.clone();
+        Callback::from(move |edit: MetadataEdit| {
+            let zone_id = zone_ids[*selected_zone_index];
+            let old = annotations.borrow().clone();
+            annotations.borrow_mut().apply(zone_id, &edit, &selected_markers, &selected_lines);
+            let snapshot = (&*parsed_markers, &*parsed_lines);
+            push_edit(&history, &old, &annotations.borrow(), snapshot, snapshot, Some(edit.label()), false);
+            refresh.force_update();
+        })
+    };
+
+    let on_layer_edit = {
+        let parsed_markers = parsed_markers.clone();
+        let parsed_lines = parsed_lines.clone();
+        let history = history.clone();
+        let annotations = annotations.clone();
+        let selected_markers = selected_markers.clone();
+        let selected_lines = selected_lines.clone();
+        let selected_zone_index = selected_zone_index.clone();
+        let zone_ids = zone_ids.clone();
+        let refresh = refresh.clone();
+        Callback::from(move |(name, edit): (String, LayerEdit)| {
+            let old = annotations.borrow().clone();
+            annotations.borrow_mut().edit_layer(&name, &edit);
+            let snapshot = (&*parsed_markers, &*parsed_lines);
+            // The opacity slider sends a change for every step it's dragged.
+            push_edit(&history, &old, &annotations.borrow(), snapshot, snapshot, Some(edit.label()), matches!(edit, LayerEdit::Opacity(_)));
+            // Nothing hidden or locked stays selected.
+            let zone_id = zone_ids[*selected_zone_index];
+            let annotations = annotations.borrow();
+            selected_markers.set(selected_markers.iter().copied().filter(|id| annotations.marker_layer(zone_id, *id).editable()).collect());
+            selected_lines.set(selected_lines.iter().copied().filter(|id| annotations.line_layer(zone_id, *id).editable()).collect());
+            refresh.force_update();
+        })
+    };
+
+    let save_project = {
+        let parsed_markers = parsed_markers.clone();
+        let parsed_lines = parsed_lines.clone();
+        let annotations = annotations.clone();
+        Callback::from(move |_: ()| {
+            let project = Project::new(&parsed_markers, &parsed_lines, &annotations.borrow());
+            storage::download("elmseditor-project.json", "application/json", &project.to_json());
         })
     };
 
+    let open_project = {
+        let load_project = load_project.clone();
+        let conversion_notes = conversion_notes.clone();
+        Callback::from(move |(name, contents): (String, String)| {
+            match Project::from_json(&contents) {
+                Ok(project) => load_project.emit((project, format!("Open '{}'", name))),
+                Err(e) => conversion_notes.set(vec![format!("Couldn't open {}: {}", name, e)]),
+            }
+        })
+    };
+
+    // The SavedVariables file last opened for each addon, as (name, contents),
+    // so downloads change only its markers.
+    let addon_files = use_state(HashMap::<Addon, (String, String)>::new);
+    let open_saved_variables = {
+        let parsed_markers = parsed_markers.clone();
+        let parsed_lines = parsed_lines.clone();
+        let elms_input = marker_input.clone();
+        let parse_diagnostics = parse_diagnostics.clone();
+        let selected_markers = selected_markers.clone();
+        let selected_lines = selected_lines.clone();
+        let conversion_notes = conversion_notes.clone();
+        let history = history.clone();
+        let annotations = annotations.clone();
+        let addon_files = addon_files.clone();
+        let zones = zones.clone();
+        Callback::from(move |(name, contents): (String, String)| {
+            let addons = addons_in(&contents);
+            if addons.is_empty() {
+                conversion_notes.set(vec![format!("Couldn't open {}: {}", name, SavedVariablesError::NoAddon)]);
+                return;
+            }
+            let parsed = parse_saved_variables(&contents, zones.clone());
+            // The file's markers replace those of the same addons; the rest stay.
+            let mut markers = (*parsed_markers).clone();
+            for zone_markers in markers.values_mut() {
+                zone_markers.retain(|m| !addons.iter().any(|addon| addon.holds(m)));
+            }
+            for (zone_id, mut opened) in parsed.data {
+                let zone_markers = markers.entry(zone_id).or_default();
+                let first_id = zone_markers.iter().map(get_marker_id).max().map_or(0, |id| id.saturating_add(1));
+                for (i, m) in opened.iter_mut().enumerate() {
+                    set_marker_id(m, first_id.saturating_add(i as u16));
+                }
+                zone_markers.append(&mut opened);
+            }
+            markers.retain(|_, zone_markers| !zone_markers.is_empty());
+
+            record_edit(&history, &annotations, (&parsed_markers, &parsed_lines), (&markers, &parsed_lines), Some(&format!("Open '{}'", name)), false);
+            elms_input.set(build_combined_string(&markers, &parsed_lines, get_timestamp()));
+            parse_diagnostics.set(Vec::new());
+            selected_markers.set(HashSet::new());
+            selected_lines.set(HashSet::new());
+            conversion_notes.set(parsed.diagnostics.iter().map(|d| {
+                let line = contents[..d.span.start.min(contents.len())].matches('\n').count() + 1;
+                format!("{} line {}: {}", name, line, d.message)
+            }).collect());
+            let mut files = (*addon_files).clone();
+            for addon in addons {
+                files.insert(addon, (name.clone(), contents.clone()));
+            }
+            addon_files.set(files);
+            parsed_markers.set(markers);
+        })
+    };
+    let download_saved_variables = {
+        let parsed_markers = parsed_markers.clone();
+        let addon_files = addon_files.clone();
+        let conversion_notes = conversion_notes.clone();
+        Callback::from(move |(addon, account): (Addon, String)| {
+            match addon_files.get(&addon) {
+                Some((name, contents)) => match patch_saved_variables(contents, &parsed_markers, get_timestamp()) {
+                    Ok(patched) => storage::download(name, "text/plain", &patched),
+                    Err(e) => conversion_notes.set(vec![format!("Couldn't write {}: {}", name, e)]),
+                },
+                None => storage::download(addon.file_name(), "text/plain", &build_saved_variables(addon, &account, &parsed_markers, get_timestamp())),
+            }
+        })
+    };
+
+    // A transform being tried out on the selection, drawn but not applied.
+    let transform_preview = use_state(|| None::<Transform>);
+    let on_transform_preview = {
+        let transform_preview = transform_preview.clone();
+        Callback::from(move |preview: Option<Transform>| transform_preview.set(preview))
+    };
+
+    let apply_transform = {
+        let parsed_markers = parsed_markers.clone();
+        let parsed_lines = parsed_lines.clone();
+        let selected_markers = selected_markers.clone();
+        let selected_lines = selected_lines.clone();
+        let selected_zone_index = selected_zone_index.clone();
+        let zones = zones.clone();
+        let update_elms_input = update_elms_input.clone();
+        Callback::from(move |transform: Transform| {
+            let zone = &zones[*selected_zone_index];
+            let mut markers = parsed_markers.get(&zone.id).cloned().unwrap_or_default();
+            let mut lines = parsed_lines.get(&zone.id).cloned().unwrap_or_default();
+            transform.apply(&mut markers, &mut lines, &selected_markers, &selected_lines);
+
+            // Markers moved off their map go to whichever map they now fall on.
+            for marker in markers.iter_mut().filter(|m| selected_markers.contains(&get_marker_id(m))) {
+                let p = get_marker_position(marker);
+                let on_map = zone.maps.iter()
+                    .find(|m| m.map_id == get_marker_map_id(marker))
+                    .is_some_and(|m| m.scale_data.contains(p.x as f64, p.z as f64));
+                if !on_map {
+                    set_marker_map_id(marker, find_best_map(p.x, p.y, p.z, zone).map_or(0, |m| m.map_id));
+                }
+            }
+            update_elms_input.emit((Some(markers), Some(lines), false));
+        })
+    };
+
+    let on_grid = {
+        let snap = snap.clone();
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            snap.set(Snap { grid: input.value().parse::<f64>().ok(), ..*snap });
+        })
+    };
+    let on_magnet = {
+        let snap = snap.clone();
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            snap.set(Snap { magnet: input.checked(), ..*snap });
+        })
+    };
+
+    let on_drawing = {
+        let drawing_lines = drawing_lines.clone();
+        let line_anchor = line_anchor.clone();
+        let hover = hover.clone();
+        let ruler = ruler.clone();
+        let ruler_start = ruler_start.clone();
+        Callback::from(move |drawing: bool| {
+            drawing_lines.set(drawing);
+            line_anchor.set(None);
+            hover.set(None);
+            if drawing {
+                ruler.set(false);
+                ruler_start.set(None);
+            }
+        })
+    };
+    let on_ruler = {
+        let ruler = ruler.clone();
+        let ruler_start = ruler_start.clone();
+        let hover = hover.clone();
+        let drawing_lines = drawing_lines.clone();
+        let line_anchor = line_anchor.clone();
+        let picking = picking.clone();
+        Callback::from(move |active: bool| {
+            ruler.set(active);
+            ruler_start.set(None);
+            hover.set(None);
+            if active {
+                drawing_lines.set(false);
+                line_anchor.set(None);
+                picking.set(None);
+            }
+        })
+    };
+    let on_line_colour = {
+        let line_colour = line_colour.clone();
+        Callback::from(move |colour: (u8, u8, u8, u8)| line_colour.set(colour))
+    };
+    let on_select_lines = {
+        let selected_lines = selected_lines.clone();
+        Callback::from(move |selected: HashSet<u16>| selected_lines.set(selected))
+    };
+
+    // A generated set of markers being set up, drawn but not added.
+    let pattern_preview = use_state(|| None::<Generator>);
+    let on_pattern_preview = {
+        let pattern_preview = pattern_preview.clone();
+        Callback::from(move |preview: Option<Generator>| pattern_preview.set(preview))
+    };
+    let on_picking = {
+        let picking = picking.clone();
+        Callback::from(move |pick: Option<Pick>| picking.set(pick))
+    };
+    let on_clear_picks = {
+        let picks = picks.clone();
+        Callback::from(move |_: ()| picks.set(Vec::new()))
+    };
+
+    let generate_pattern = {
+        let parsed_markers = parsed_markers.clone();
+        let selected_markers = selected_markers.clone();
+        let selected_lines = selected_lines.clone();
+        let selected_zone_index = selected_zone_index.clone();
+        let selected_map_index = selected_map_index.clone();
+        let zones = zones.clone();
+        let update_markers = update_markers.clone();
+        Callback::from(move |generator: Generator| {
+            let zone = &zones[*selected_zone_index];
+            let Some(map) = zone.maps.get(*selected_map_index) else { return };
+            let mut markers = parsed_markers.get(&zone.id).cloned().unwrap_or_default();
+            let first_id = markers.iter().map(get_marker_id).max().map_or(0, |id| id.saturating_add(1));
+            let generated = generator.markers(placement_y(map), map.map_id, first_id);
+            // The new markers are left selected, ready to move or rotate.
+            selected_markers.set(generated.iter().map(get_marker_id).collect());
+            selected_lines.set(HashSet::new());
+            markers.extend(generated);
+            update_markers.emit(markers);
+        })
+    };
+
+    let clear_selection = {
+        let selected_markers = selected_markers.clone();
+        let selected_lines = selected_lines.clone();
+        Callback::from(move |_: ()| {
+            selected_markers.set(HashSet::new());
+            selected_lines.set(HashSet::new());
+        })
+    };
+
+    let on_select = {
+        let selected_markers = selected_markers.clone();
+        Callback::from(move |selected: HashSet<u16>| selected_markers.set(selected))
+    };
+
+    // Moves through the undo history to the given number of applied edits.
+    let history_jump = {
+        let parsed_markers = parsed_markers.clone();
+        let parsed_lines = parsed_lines.clone();
+        let elms_input = marker_input.clone();
+        let selected_markers = selected_markers.clone();
+        let selected_lines = selected_lines.clone();
+        let history = history.clone();
+        let annotations = annotations.clone();
+        Callback::from(move |position: usize| {
+            let mut markers_map = (*parsed_markers).clone();
+            let mut lines_map = (*parsed_lines).clone();
+            history.borrow_mut().jump_to(position, &mut markers_map, &mut lines_map, &mut annotations.borrow_mut());
+            elms_input.set(build_combined_string(&markers_map, &lines_map, get_timestamp()));
+            selected_markers.set(HashSet::new());
+            selected_lines.set(HashSet::new());
+            parsed_markers.set(markers_map);
+            parsed_lines.set(lines_map);
+        })
+    };
+
+    let history_position = history.borrow().entries().1;
+    let undo = {
+        let history_jump = history_jump.clone();
+        let can_undo = history.borrow().can_undo();
+        Callback::from(move |_: ()| if can_undo { history_jump.emit(history_position - 1) })
+    };
+    let redo = {
+        let history_jump = history_jump.clone();
+        let can_redo = history.borrow().can_redo();
+        Callback::from(move |_: ()| if can_redo { history_jump.emit(history_position + 1) })
+    };
+
+    let has_selection = !selected_markers.is_empty() || !selected_lines.is_empty();
+    // The keydown listener is registered once, so it calls whichever
+    // callbacks the latest render left here.
+    let shortcuts = use_mut_ref(Shortcuts::default);
+    *shortcuts.borrow_mut() = Shortcuts {
+        undo: undo.clone(),
+        redo: redo.clone(),
+        delete: if has_selection { apply_bulk_edit.reform(|_| BulkEdit::Delete) } else { Callback::noop() },
+        escape: match (*line_anchor, *ruler_start) {
+            (Some(_), _) => {
+                let line_anchor = line_anchor.clone();
+                Callback::from(move |_| line_anchor.set(None))
+            }
+            (None, Some(_)) => {
+                let ruler_start = ruler_start.clone();
+                Callback::from(move |_| ruler_start.set(None))
+            }
+            (None, None) => clear_selection.clone(),
+        },
+    };
+    {
+        let shortcuts = shortcuts.clone();
+        use_effect_with((), move |_| {
+            let win = web_sys::window().expect("no global `window` exists");
+            let on_keydown = Closure::wrap(Box::new(move |e: KeyboardEvent| {
+                // Text boxes keep their own undo and delete.
+                let typing = e.target().and_then(|t| t.dyn_into::<web_sys::Element>().ok()).is_some_and(|el| {
+                    el.tag_name() == "TEXTAREA" || (el.tag_name() == "INPUT" && el.get_attribute("type").as_deref() != Some("checkbox"))
+                });
+                if typing {
+                    return;
+                }
+                let shortcuts = shortcuts.borrow().clone();
+                if !(e.ctrl_key() || e.meta_key()) {
+                    match e.key().as_str() {
+                        "Delete" | "Backspace" => shortcuts.delete.emit(()),
+                        "Escape" => shortcuts.escape.emit(()),
+                        _ => return,
+                    }
+                    e.prevent_default();
+                    return;
+                }
+                match e.key().to_lowercase().as_str() {
+                    "z" if e.shift_key() => shortcuts.redo.emit(()),
+                    "z" => shortcuts.undo.emit(()),
+                    "y" => shortcuts.redo.emit(()),
+                    _ => return,
+                }
+                e.prevent_default();
+            }) as Box<dyn FnMut(_)>);
+
+            win.add_event_listener_with_callback("keydown", on_keydown.as_ref().unchecked_ref())
+                .expect("failed to register keydown listener");
+
+            move || {
+                let _ = win.remove_event_listener_with_callback("keydown", on_keydown.as_ref().unchecked_ref());
+            }
+        });
+    }
+
     let update_lines = {
         let cb = update_elms_input.clone();
         Callback::from(move |lines: Vec<BreadcrumbLine>| {
-            cb.emit((None, Some(lines)));
+            cb.emit((None, Some(lines), false));
         })
     };
 
@@ -1336,8 +2706,9 @@ fn app() -> Html {
                     )
                     .expect("failed to register resize listener");
 
-                resize_closure.forget();
-                || ()
+                move || {
+                    let _ = win.remove_event_listener_with_callback("resize", resize_closure.as_ref().unchecked_ref());
+                }
             },
         );
     }
@@ -1348,9 +2719,9 @@ fn app() -> Html {
         color: #fff;
     "#);
 
-    let zone = zones.get(*selected_zone_index).unwrap_or_else(|| {selected_zone_index.set(0); zones.get(0).unwrap()}).clone();
-    // web_sys::console::log_1(&format!("zone_index: {}, zone: {:?}", *selected_zone_index, zone).into());
-    let map = zone.maps.get(*selected_map_index).unwrap_or_else(|| {selected_map_index.set(0); zone.maps.get(0).unwrap()}).clone();
+    let zone = zones.get(*selected_zone_index).unwrap_or_else(|| {selected_zone_index.set(0); zones.first().unwrap()}).clone();
+    let map = zone.maps.get(*selected_map_index).unwrap_or_else(|| {selected_map_index.set(0); zone.maps.first().unwrap()}).clone();
+    *shown_view.borrow_mut() = (zone.id, map.map_id);
 
     let canvas_width = *canvas_size;
     let canvas_height = *canvas_size;
@@ -1358,8 +2729,29 @@ fn app() -> Html {
     let zoom = *zoom.clone();
     let pan = *pan.clone();
 
+    let share_status = use_state(|| None::<String>);
+    let on_copy_share_link = {
+        let share_status = share_status.clone();
+        let data = (*marker_input).clone();
+        let (zone_id, map_id) = (zone.id, map.map_id);
+        let size = (*canvas_size).max(1) as f64;
+        Callback::from(move |_: MouseEvent| {
+            let state = ShareState { data: data.clone(), zone_id, map_id, zoom, pan: (pan.0 / size, pan.1 / size) };
+            let Some(window) = web_sys::window() else { return };
+            let location = window.location();
+            let base = format!(
+                "{}{}{}",
+                location.origin().unwrap_or_default(),
+                location.pathname().unwrap_or_default(),
+                location.search().unwrap_or_default()
+            );
+            let url = format!("{}#{}", base, encode_share(&state));
+            let _ = window.navigator().clipboard().write_text(&url);
+            share_status.set(Some(format!("Share link copied ({} characters)", url.len())));
+        })
+    };
+
     let zone_markers = parsed_markers.get(&zone.id).cloned().unwrap_or_default();
-    // web_sys::console::log_1(&format!("zone_markers: {:?}", zone_markers).into());
     let zone_marker_clone = zone_markers.clone();
     let other_current_markers: Vec<Marker> = zone_markers.into_iter()
         .filter(|m| {
@@ -1374,17 +2766,127 @@ fn app() -> Html {
         })
         .collect();
 
+    let selected_zone_markers: Vec<&Marker> = zone_marker_clone.iter().filter(|m| selected_markers.contains(&get_marker_id(m))).collect();
+
     let zone_lines = parsed_lines.get(&zone.id).cloned().unwrap_or_default();
     let zone_lines_clone = zone_lines.clone();
     let current_lines: Vec<BreadcrumbLine> = zone_lines.into_iter()
         .filter(|l| {l.map_id == map.map_id})
         .collect();
+    let selection_centroid = centroid(&zone_marker_clone, &zone_lines_clone, &selected_markers, &selected_lines);
+
+    let marker_meta = annotations.borrow().markers.get(&zone.id).cloned().unwrap_or_default();
+    let line_meta = annotations.borrow().lines.get(&zone.id).cloned().unwrap_or_default();
+    let selected_zone_lines: Vec<&BreadcrumbLine> = zone_lines_clone.iter().filter(|l| selected_lines.contains(&l.id)).collect();
+    let single_meta = match (&selected_zone_markers[..], &selected_zone_lines[..]) {
+        ([marker], []) => Some(marker_meta.get(&get_marker_id(marker)).cloned().unwrap_or_default()),
+        ([], [line]) => Some(line_meta.get(&line.id).cloned().unwrap_or_default()),
+        _ => None,
+    };
+
+    // Pinned measurements on this map, then the one being made.
+    let map_measurements: Vec<Measurement> = measurements.iter().filter(|(id, _)| *id == map.map_id).map(|(_, m)| *m).collect();
+    let mut canvas_measurements = map_measurements.clone();
+    if let Some(from) = *ruler_start
+        && let Some((px, pz)) = *hover
+    {
+        let (x, z) = map.scale_data.denormalise(px / canvas_width as f64, pz / canvas_width as f64);
+        canvas_measurements.push(Measurement { from, to: Point { x, z, y: None } });
+    }
+    // Every pair of a small selection; a big one would bury the map in lines.
+    let selected_positions: Vec<Position3D> = selected_zone_markers.iter()
+        .filter(|m| get_marker_active(m) && get_marker_map_id(m) == map.map_id)
+        .map(|m| get_marker_position(m))
+        .collect();
+    let selection_measurements = if selected_positions.len() <= MAX_MEASURED_SELECTION { pairwise(&selected_positions) } else { Vec::new() };
+    let on_remove_measurement = {
+        let measurements = measurements.clone();
+        let map_id = map.map_id;
+        Callback::from(move |index: usize| {
+            let mut on_map = 0;
+            measurements.set(measurements.iter().copied().filter(|(id, _)| {
+                if *id != map_id {
+                    return true;
+                }
+                on_map += 1;
+                on_map - 1 != index
+            }).collect());
+        })
+    };
+    let on_clear_measurements = {
+        let measurements = measurements.clone();
+        let map_id = map.map_id;
+        Callback::from(move |_: ()| measurements.set(measurements.iter().copied().filter(|(id, _)| *id != map_id).collect()))
+    };
+
+    let export_image = {
+        let map = map.clone();
+        let markers = other_current_markers.clone();
+        let lines = current_lines.clone();
+        let selected_markers = selected_markers.clone();
+        let selected_lines = selected_lines.clone();
+        let icon_cache = icon_cache.clone();
+        let conversion_notes = conversion_notes.clone();
+        let annotations = annotations.clone();
+        let zone_id = zone.id;
+        Callback::from(move |request: ExportRequest| {
+            let mut options = request.options;
+            let (mut markers, mut lines) = (markers.clone(), lines.clone());
+            if request.visible_layers_only {
+                annotations.borrow().arrange(zone_id, &mut markers, &mut lines);
+            }
+            if request.crop_to_selection {
+                let chosen_markers: Vec<Marker> = markers.iter().filter(|m| selected_markers.contains(&get_marker_id(m))).cloned().collect();
+                let chosen_lines: Vec<BreadcrumbLine> = lines.iter().filter(|l| selected_lines.contains(&l.id)).cloned().collect();
+                options.crop = bounds(&chosen_markers, &chosen_lines, map.map_id, EXPORT_MARGIN);
+            }
+            // Everything is embedded, so the image stands alone.
+            let cache = icon_cache.clone();
+            let href = move |path: &str| {
+                let src = format!("static/{}", path);
+                cache.data_url(&src).unwrap_or(src)
+            };
+            let image = svg(&map, &markers, &lines, &options, &href);
+            let name = options.title.as_deref().unwrap_or(&map.name).replace(|c: char| !c.is_alphanumeric() && c != ' ' && c != '-', "");
+            match request.format {
+                ImageFormat::Svg => storage::download(&format!("{}.svg", name), "image/svg+xml", &image.svg),
+                ImageFormat::Png => {
+                    let conversion_notes = conversion_notes.clone();
+                    spawn_local(async move {
+                        match svg_to_png(&image.svg, image.width, image.height).await {
+                            Some(url) => storage::download_url(&format!("{}.png", name), &url),
+                            None => conversion_notes.set(vec!["The browser couldn't draw the map as a PNG. Try exporting an SVG instead.".to_string()]),
+                        }
+                    });
+                }
+            }
+        })
+    };
+
+    let (mut canvas_markers, mut canvas_lines) = (other_current_markers.clone(), current_lines.clone());
+    annotations.borrow().arrange(zone.id, &mut canvas_markers, &mut canvas_lines);
+    let marker_opacity: HashMap<u16, f64> = canvas_markers.iter()
+        .map(|m| (get_marker_id(m), annotations.borrow().marker_layer(zone.id, get_marker_id(m)).opacity))
+        .filter(|(_, opacity)| *opacity < 1.0)
+        .collect();
+    let line_opacity: HashMap<u16, f64> = canvas_lines.iter()
+        .map(|l| (l.id, annotations.borrow().line_layer(zone.id, l.id).opacity))
+        .filter(|(_, opacity)| *opacity < 1.0)
+        .collect();
+    if let Some(transform) = *transform_preview {
+        transform.apply(&mut canvas_markers, &mut canvas_lines, &selected_markers, &selected_lines);
+    }
+    if let Some(generator) = &*pattern_preview {
+        let first_id = zone_marker_clone.iter().map(get_marker_id).max().map_or(0, |id| id.saturating_add(1));
+        canvas_markers.extend(generator.markers(placement_y(&map), map.map_id, first_id));
+    }
 
     html! {
         <div style={format!("display: flex; background-color: #333; color: #fff; font-family: 'Univers', sans-serif; max-height: {}px; flex-wrap: wrap;", *canvas_size)}>
             <div
                 {onwheel}
                 {onmousedown}
+                onmouseleave={onmouseup.clone()}
                 {onmouseup}
                 {onmousemove}
                 {oncontextmenu}
@@ -1401,18 +2903,37 @@ fn app() -> Html {
                 ", canvas_width, canvas_height)}>
                 <CanvasMap
                     map={map.clone()}
-                    markers={other_current_markers.clone()}
-                    lines = {current_lines.clone()}
+                    markers={canvas_markers}
+                    lines={canvas_lines}
                     zoom={zoom}
                     pan={pan}
                     width={canvas_width}
                     height={canvas_height}
                     force_redraw={*do_redraw}
+                    selected={(*selected_markers).clone()}
+                    selected_lines={(*selected_lines).clone()}
+                    selection_box={selection_box.map(|(a, b, _)| (a, b))}
+                    crosshair={picking.is_some() || *drawing_lines || *ruler}
+                    show_endpoints={*drawing_lines}
+                    draft_line={line_anchor.zip(*hover).map(|(anchor, hover)| {
+                        let (nx, nz) = map.scale_data.normalise(anchor.x as f64, anchor.z as f64);
+                        ((nx * canvas_width as f64, nz * canvas_width as f64), hover)
+                    })}
+                    draft_colour={*line_colour}
+                    grid={snap.grid}
+                    measurements={canvas_measurements}
+                    selection_measurements={selection_measurements.clone()}
+                    on_go_to={go_to}
+                    cache={icon_cache.clone()}
+                    {marker_meta}
+                    {line_meta}
+                    {marker_opacity}
+                    {line_opacity}
                 />
             </div>
 
             <div style="position: absolute; top: 1em; left: 1em;">
-            if zone_marker_clone.len() > 0 || zone_lines_clone.len() > 0 {
+            if !zone_marker_clone.is_empty() || !zone_lines_clone.is_empty() {
                 if zone.maps.len() > 1 {
                     <select onchange={on_map_change}>
                         {
@@ -1449,11 +2970,19 @@ fn app() -> Html {
             } else {
                 <select onchange={on_zone_change}>
                     {
-                        for zones.iter().enumerate().map(|(i, zone)| html! {
-                            <option value={i.to_string()} selected={i == *selected_zone_index}>
-                                { &zone.name }
-                            </option>
-                        })
+                        for [ZoneCategory::Trial, ZoneCategory::Dungeon, ZoneCategory::Arena, ZoneCategory::Other].into_iter()
+                            .filter(|category| zones.iter().any(|z| z.category == *category))
+                            .map(|category| html! {
+                                <optgroup label={category.to_string()}>
+                                    {
+                                        for zones.iter().enumerate().filter(|(_, z)| z.category == category).map(|(i, zone)| html! {
+                                            <option value={i.to_string()} selected={i == *selected_zone_index}>
+                                                { &zone.name }
+                                            </option>
+                                        })
+                                    }
+                                </optgroup>
+                            })
                     }
                 </select>
             }
@@ -1461,6 +2990,22 @@ fn app() -> Html {
 
             <div style={format!("width: {}px; height: {}px; min-width: 300px; flex-grow: 1; flex-shrink: 1; flex-basis: 300px; text-align: center;", window_height.max(*window_width) - (*canvas_size as f64), *window_height)}>
                 <div> 
+                    if let Some(Err(e)) = &*shared_link {
+                        <div style="width:80%;margin:1em auto 0;color:#ff6b6b;text-align:left;">{ format!("Couldn't open the share link: {}", e) }</div>
+                    }
+                    if let Some(autosave) = &*recovered {
+                        <div style="width:80%;margin:1em auto 0;padding:0.5em;border-radius:0.5em;background:#444;display:flex;gap:0.5em;align-items:center;">
+                            if matches!(&*shared_link, Some(Ok(_))) {
+                                <span style="flex-grow:1;text-align:left;">{ format!("Opened a shared layout. Your session from {} is still available.", format_time(autosave.saved_at)) }</span>
+                                <button onclick={on_restore_recovery}>{"Restore it"}</button>
+                                <button onclick={on_keep_recovery}>{"Dismiss"}</button>
+                            } else {
+                                <span style="flex-grow:1;text-align:left;">{ format!("Recovered your session from {}.", format_time(autosave.saved_at)) }</span>
+                                <button onclick={on_keep_recovery}>{"Keep"}</button>
+                                <button onclick={on_discard_recovery}>{"Discard"}</button>
+                            }
+                        </div>
+                    }
                     <textarea
                         oninput={oninput}
                         value={(*marker_input).clone()}
@@ -1474,7 +3019,153 @@ fn app() -> Html {
                             resize: none;
                             margin-top: 1em;"
                     />
-                    <MarkerListPanel zone_markers={zone_marker_clone} current_markers={other_current_markers} on_update={update_markers} world_bounds={(map.scale_data.min_x, map.scale_data.max_x, map.scale_data.min_z, map.scale_data.max_z)} />
+                    if !parse_diagnostics.is_empty() {
+                        <ul style="list-style:none;padding:0;margin:0.5em auto;width:80%;max-height:8em;overflow-y:auto;text-align:left;font-size:0.9em;">
+                            { for parse_diagnostics.iter().map(|(d, snippet)| {
+                                let colour = match d.severity {
+                                    Severity::Error => "#ff6b6b",
+                                    Severity::Warning => "#ffd166",
+                                };
+                                html! {
+                                    <li style={format!("color:{};", colour)} title={format!("bytes {}..{}", d.span.start, d.span.end)}>
+                                        { format!("{}: {}", d.severity, d.message) }
+                                        if !snippet.is_empty() {
+                                            <code style="margin-left:0.5em;color:#ccc;">{ snippet.clone() }</code>
+                                        }
+                                    </li>
+                                }
+                            }) }
+                        </ul>
+                    }
+                    <SavesPanel
+                        current={(*marker_input).clone()}
+                        project={Project::new(&parsed_markers, &parsed_lines, &annotations.borrow())}
+                        on_load={on_load_layout}
+                    />
+                    <ProjectPanel
+                        can_save={!parsed_markers.is_empty() || !parsed_lines.is_empty()}
+                        on_save={save_project}
+                        on_open={open_project}
+                    />
+                    <SavedVariablesPanel
+                        opened={addon_files.iter().map(|(addon, (name, _))| (*addon, name.clone())).collect::<Vec<_>>()}
+                        on_open={open_saved_variables}
+                        on_download={download_saved_variables}
+                    />
+                    <LayersPanel layers={annotations.borrow().layers()} on_edit={on_layer_edit} />
+                    <CataloguePanel
+                        zone_count={zones.len()}
+                        errors={(*catalogue_errors).clone()}
+                        override_name={zone_override.as_ref().map(|(name, _)| name.clone())}
+                        on_override={on_zone_override}
+                    />
+                    <div style="width:80%;margin:0.5em auto;text-align:left;">
+                        <button onclick={undo.reform(|_| ())} disabled={!history.borrow().can_undo()} title="Undo (Ctrl+Z)">{"Undo"}</button>
+                        <button onclick={redo.reform(|_| ())} disabled={!history.borrow().can_redo()} title="Redo (Ctrl+Shift+Z)" style="margin-left:0.5em;">{"Redo"}</button>
+                        <button onclick={on_copy_share_link} disabled={marker_input.is_empty()} style="margin-left:0.5em;">{"Copy share link"}</button>
+                        if let Some(status) = &*share_status {
+                            <span style="margin-left:0.5em;font-size:0.9em;color:#ccc;">{ status.clone() }</span>
+                        }
+                        <details style="margin-top:0.5em;">
+                            <summary style="cursor:pointer;">{ format!("History ({})", history.borrow().entries().0.len()) }</summary>
+                            <ol start="0" style="max-height:10em;overflow-y:auto;margin:0.25em 0;font-size:0.9em;">
+                                {
+                                    for std::iter::once("Start").chain(history.borrow().entries().0).enumerate().map(|(i, label)| {
+                                        let jump = history_jump.clone();
+                                        let style = if i == history_position {
+                                            "cursor:pointer;font-weight:bold;"
+                                        } else if i > history_position {
+                                            "cursor:pointer;color:#888;"
+                                        } else {
+                                            "cursor:pointer;"
+                                        };
+                                        html! {
+                                            <li {style} onclick={Callback::from(move |_| jump.emit(i))}>{ label }</li>
+                                        }
+                                    })
+                                }
+                            </ol>
+                        </details>
+                    </div>
+                    <div style="width:80%;margin:0.5em auto;text-align:left;display:flex;gap:0.5em;align-items:center;">
+                        <label title="Snap placed and moved markers and line ends to a grid">
+                            {"Grid "}
+                            <select onchange={on_grid}>
+                                { for [None, Some(100.0), Some(200.0), Some(500.0), Some(1000.0), Some(2500.0)].into_iter().map(|step| html! {
+                                    <option value={step.map(|s: f64| s.to_string()).unwrap_or_default()} selected={snap.grid == step}>
+                                        { step.map_or("Off".to_string(), |s| format!("{} m", s / 100.0)) }
+                                    </option>
+                                }) }
+                            </select>
+                        </label>
+                        <label title="Snap to nearby markers and line ends">
+                            <input type="checkbox" checked={snap.magnet} onchange={on_magnet} />
+                            {"Magnet"}
+                        </label>
+                        <span style="font-size:0.9em;color:#ccc;">{"Hold Alt to place freely"}</span>
+                    </div>
+                    <ExportPanel has_selection={has_selection} on_export={export_image} />
+                    if !conversion_notes.is_empty() {
+                        <ul style="list-style:none;padding:0;margin:0.5em auto;width:80%;max-height:8em;overflow-y:auto;text-align:left;font-size:0.9em;color:#ffd166;">
+                            { for conversion_notes.iter().map(|note| html! { <li>{ note.clone() }</li> }) }
+                        </ul>
+                    }
+                    if has_selection {
+                        <SelectionToolbar
+                            marker_count={selected_zone_markers.len()}
+                            line_count={selected_zone_lines.len()}
+                            has_elms={selected_zone_markers.iter().any(|m| matches!(m, Elms(_)))}
+                            has_m0r={selected_zone_markers.iter().any(|m| matches!(m, M0r(_)))}
+                            on_edit={apply_bulk_edit}
+                            on_convert={convert_selected}
+                            on_clear={clear_selection}
+                        />
+                        <MetadataPanel
+                            single={single_meta}
+                            count={selected_zone_markers.len() + selected_zone_lines.len()}
+                            groups={annotations.borrow().groups()}
+                            on_edit={apply_metadata_edit}
+                        />
+                    }
+                    if let Some(centroid) = selection_centroid {
+                        <TransformPanel {centroid} on_preview={on_transform_preview} on_apply={apply_transform} />
+                    }
+                    <RulerPanel
+                        active={*ruler}
+                        on_active={on_ruler}
+                        measurements={map_measurements}
+                        on_remove={on_remove_measurement}
+                        on_clear={on_clear_measurements}
+                        selection={selection_measurements}
+                    />
+                    <PatternPanel
+                        picks={(*picks).clone()}
+                        picking={*picking}
+                        {on_picking}
+                        {on_clear_picks}
+                        default_centre={map.scale_data.denormalise(0.5, 0.5)}
+                        on_preview={on_pattern_preview}
+                        on_generate={generate_pattern}
+                    />
+                    <MarkerListPanel
+                        zone_markers={zone_marker_clone}
+                        current_markers={other_current_markers}
+                        on_update={edit_marker_list}
+                        world_bounds={(map.scale_data.min_x, map.scale_data.max_x, map.scale_data.min_z, map.scale_data.max_z)}
+                        selected={(*selected_markers).clone()}
+                        {on_select}
+                    />
+                    <LinesPanel
+                        zone_lines={zone_lines_clone.clone()}
+                        current_lines={current_lines.clone()}
+                        on_update={update_lines.clone()}
+                        selected={(*selected_lines).clone()}
+                        on_select={on_select_lines}
+                        drawing={*drawing_lines}
+                        {on_drawing}
+                        colour={*line_colour}
+                        on_colour={on_line_colour}
+                    />
                 </div>
                 
                 <div style="position: fixed; bottom: 1em; right: 1em; display: flex; gap: 1em;">
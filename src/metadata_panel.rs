@@ -0,0 +1,73 @@
+//! Names, notes, groups and colour tags for the selection. These only live in
+//! the editor and its project files; the game never sees them.
+
+use marker_core::marker::hex_to_rgba;
+use marker_core::project::{Metadata, MetadataEdit};
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct MetadataPanelProps {
+    /// What is kept about the selected marker or line, if only one is selected
+    pub single: Option<Metadata>,
+    /// How many markers and lines are selected
+    pub count: usize,
+    /// Group names already in use, offered as suggestions
+    pub groups: Vec<String>,
+    pub on_edit: Callback<MetadataEdit>,
+}
+
+#[function_component(MetadataPanel)]
+pub fn metadata_panel(props: &MetadataPanelProps) -> Html {
+    let meta = props.single.clone().unwrap_or_default();
+
+    let on_name = props.on_edit.reform(|e: Event| {
+        let input: HtmlInputElement = e.target_unchecked_into();
+        MetadataEdit::Name(input.value())
+    });
+    let on_notes = props.on_edit.reform(|e: Event| {
+        let input: HtmlInputElement = e.target_unchecked_into();
+        MetadataEdit::Notes(input.value())
+    });
+    let on_group = props.on_edit.reform(|e: Event| {
+        let input: HtmlInputElement = e.target_unchecked_into();
+        MetadataEdit::Group(input.value())
+    });
+    let on_tag = props.on_edit.reform(|e: Event| {
+        let input: HtmlInputElement = e.target_unchecked_into();
+        let hex = u32::from_str_radix(input.value().trim_start_matches('#'), 16).unwrap_or(0xFFFFFF);
+        let (r, g, b, _) = hex_to_rgba(hex);
+        MetadataEdit::Tag(Some((r, g, b)))
+    });
+    let tag_hex = meta.tag.map_or("#ffffff".to_string(), |(r, g, b)| format!("#{:02x}{:02x}{:02x}", r, g, b));
+
+    html! {
+        <details open=true style="width:80%;margin:0 auto 0.5em;text-align:left;">
+            <summary style="cursor:pointer;">{"Notes"}</summary>
+            <div style="display:flex;flex-direction:column;gap:0.25em;margin-top:0.25em;">
+                if props.single.is_some() {
+                    <input type="text" placeholder="Name" value={meta.name.clone()} onchange={on_name} />
+                    <textarea placeholder="Notes" rows="3" style="resize:vertical;" value={meta.notes.clone()} onchange={on_notes} />
+                }
+                <div style="display:flex;gap:0.5em;align-items:center;flex-wrap:wrap;">
+                    <input
+                        type="text"
+                        list="metadata-groups"
+                        placeholder={if props.count > 1 { format!("Group for all {}", props.count) } else { "Group, e.g. Tank stack".to_string() }}
+                        value={meta.group.clone()}
+                        onchange={on_group}
+                        style="flex-grow:1;"
+                    />
+                    <datalist id="metadata-groups">
+                        { for props.groups.iter().map(|g| html! { <option value={g.clone()} /> }) }
+                    </datalist>
+                    <label title="A colour to pick these out on the map. Not shown in game">
+                        {"Tag "}
+                        <input type="color" value={tag_hex} onchange={on_tag} />
+                    </label>
+                    <button onclick={props.on_edit.reform(|_| MetadataEdit::Tag(None))} disabled={props.single.is_some() && meta.tag.is_none()}>{"No tag"}</button>
+                </div>
+            </div>
+        </details>
+    }
+}
@@ -0,0 +1,248 @@
+//! Generating rings, grids, lines and clock faces of markers, previewed on the
+//! map until they are added.
+
+use marker_core::marker::{ElmsIcon, ALL_ELMS_ICONS};
+use marker_core::pattern::{Generator, Labels, Shape};
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+/// How many points a click on the map adds while picking.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Pick {
+    /// Replaces the picked points and stops picking
+    One,
+    /// Adds to them until picking is turned off
+    Many,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    Ring,
+    Grid,
+    Line,
+    Clock,
+}
+
+#[derive(Clone, PartialEq)]
+struct Fields {
+    radius: String,
+    count: String,
+    start: String,
+    rows: String,
+    columns: String,
+    spacing: (String, String),
+    labels: Labels,
+}
+
+impl Default for Fields {
+    fn default() -> Self {
+        Self {
+            radius: "1000".into(),
+            count: "8".into(),
+            start: "0".into(),
+            rows: "3".into(),
+            columns: "3".into(),
+            spacing: ("500".into(), "500".into()),
+            labels: Labels::Numbers,
+        }
+    }
+}
+
+/// Keeps a generated set small enough to place and read.
+const MAX_MARKERS: usize = 200;
+
+fn build(kind: Kind, fields: &Fields, centre: (f64, f64), path: &[(f64, f64)]) -> Option<Generator> {
+    let number = |s: &str| s.trim().parse::<f64>().ok().filter(|v| v.is_finite());
+    let count = |s: &str| s.trim().parse::<usize>().ok().filter(|n| (1..=MAX_MARKERS).contains(n));
+    let shape = match kind {
+        Kind::Ring => Shape::Ring { centre, radius: number(&fields.radius)?, count: count(&fields.count)?, start_degrees: number(&fields.start)? },
+        Kind::Grid => {
+            let (rows, columns) = (count(&fields.rows)?, count(&fields.columns)?);
+            if rows * columns > MAX_MARKERS {
+                return None;
+            }
+            Shape::Grid { centre, rows, columns, spacing: (number(&fields.spacing.0)?, number(&fields.spacing.1)?) }
+        }
+        Kind::Line if path.len() >= 2 => Shape::Polyline { points: path.to_vec(), count: count(&fields.count)? },
+        Kind::Line => return None,
+        Kind::Clock => Shape::Clock { centre, radius: number(&fields.radius)? },
+    };
+    Some(Generator { shape, labels: fields.labels })
+}
+
+#[derive(Properties, PartialEq)]
+pub struct PatternPanelProps {
+    /// Points clicked on the map, in world X/Z
+    pub picks: Vec<(f64, f64)>,
+    pub picking: Option<Pick>,
+    pub on_picking: Callback<Option<Pick>>,
+    pub on_clear_picks: Callback<()>,
+    /// Where shapes are centred until a point is picked
+    pub default_centre: (f64, f64),
+    /// Called with the set to draw on the map, or `None` for no preview
+    pub on_preview: Callback<Option<Generator>>,
+    pub on_generate: Callback<Generator>,
+}
+
+#[function_component(PatternPanel)]
+pub fn pattern_panel(props: &PatternPanelProps) -> Html {
+    let kind = use_state(|| None::<Kind>);
+    let fields = use_state(Fields::default);
+    let centre = props.picks.last().copied().unwrap_or(props.default_centre);
+    let generator = kind.and_then(|k| build(k, &fields, centre, &props.picks));
+
+    {
+        let on_preview = props.on_preview.clone();
+        use_effect_with(generator.clone(), move |generator| {
+            on_preview.emit(generator.clone());
+            let on_preview = on_preview.clone();
+            move || on_preview.emit(None)
+        });
+    }
+
+    let choose = |k: Kind| {
+        let kind = kind.clone();
+        let on_picking = props.on_picking.clone();
+        let on_clear_picks = props.on_clear_picks.clone();
+        Callback::from(move |_: MouseEvent| {
+            // A path is built up from scratch; the other shapes keep their centre.
+            if k == Kind::Line {
+                on_clear_picks.emit(());
+                on_picking.emit(Some(Pick::Many));
+            } else {
+                on_picking.emit(None);
+            }
+            kind.set(Some(k));
+        })
+    };
+    let field = |set: fn(&mut Fields, String)| {
+        let fields = fields.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            let mut new_fields = (*fields).clone();
+            set(&mut new_fields, input.value());
+            fields.set(new_fields);
+        })
+    };
+    let on_labels = {
+        let fields = fields.clone();
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            let labels = match input.value().as_str() {
+                "numbers" => Labels::Numbers,
+                "letters" => Labels::Letters,
+                icon => Labels::Same(ElmsIcon::from(icon)),
+            };
+            fields.set(Fields { labels, ..(*fields).clone() });
+        })
+    };
+    let on_generate = {
+        let on_generate = props.on_generate.clone();
+        let generator = generator.clone();
+        Callback::from(move |_: MouseEvent| {
+            if let Some(generator) = &generator {
+                on_generate.emit(generator.clone());
+            }
+        })
+    };
+    let on_close = {
+        let kind = kind.clone();
+        let on_picking = props.on_picking.clone();
+        Callback::from(move |_: MouseEvent| {
+            on_picking.emit(None);
+            kind.set(None);
+        })
+    };
+
+    let input = |value: &str, step: &str, title: &str, oninput: Callback<InputEvent>| html! {
+        <input type="number" style="width:5em;" step={step.to_string()} title={title.to_string()} value={value.to_string()} {oninput} />
+    };
+    let kind_button = |k: Kind, label: &str| html! {
+        <button onclick={choose(k)} disabled={*kind == Some(k)}>{ label }</button>
+    };
+    let picking_centre = props.picking == Some(Pick::One);
+    let centre_picker = html! {
+        <>
+            <span title="World X, Z">{ format!("Centre {}, {}", centre.0.round() as i32, centre.1.round() as i32) }</span>
+            <button onclick={props.on_picking.reform(move |_| (!picking_centre).then_some(Pick::One))}>
+                { if picking_centre { "Click the map…" } else { "Pick on map" } }
+            </button>
+        </>
+    };
+    let picking_path = props.picking == Some(Pick::Many);
+    let path_picker = html! {
+        <>
+            <span>{ format!("{} points", props.picks.len()) }</span>
+            <button onclick={props.on_picking.reform(move |_| (!picking_path).then_some(Pick::Many))}>
+                { if picking_path { "Done" } else { "Add points on map" } }
+            </button>
+            <button onclick={props.on_clear_picks.reform(|_| ())} disabled={props.picks.is_empty()}>{"Clear points"}</button>
+        </>
+    };
+    let labels_value = match fields.labels {
+        Labels::Numbers => "numbers".to_string(),
+        Labels::Letters => "letters".to_string(),
+        Labels::Same(icon) => String::from(icon),
+    };
+
+    html! {
+        <div style="display:flex;flex-direction:column;gap:0.5em;align-items:center;margin-bottom:0.5em;">
+            <div style="display:flex;gap:0.5em;justify-content:center;align-items:center;">
+                {"Generate: "}
+                { kind_button(Kind::Ring, "Ring") }
+                { kind_button(Kind::Grid, "Grid") }
+                { kind_button(Kind::Line, "Line") }
+                { kind_button(Kind::Clock, "Clock") }
+            </div>
+            if let Some(k) = *kind {
+                <div style="display:flex;gap:0.5em;justify-content:center;align-items:center;flex-wrap:wrap;">
+                    {
+                        match k {
+                            Kind::Ring => html! {
+                                <>
+                                    { centre_picker }
+                                    <label>{"Markers "}{ input(&fields.count, "1", "How many", field(|f, v| f.count = v)) }</label>
+                                    <label>{"Radius "}{ input(&fields.radius, "100", "In world units (100 per metre)", field(|f, v| f.radius = v)) }</label>
+                                    <label>{"First at "}{ input(&fields.start, "15", "Degrees clockwise from north", field(|f, v| f.start = v)) }</label>
+                                </>
+                            },
+                            Kind::Grid => html! {
+                                <>
+                                    { centre_picker }
+                                    <label>{"Rows "}{ input(&fields.rows, "1", "North to south", field(|f, v| f.rows = v)) }</label>
+                                    <label>{"Columns "}{ input(&fields.columns, "1", "West to east", field(|f, v| f.columns = v)) }</label>
+                                    <label>{"Spacing "}{ input(&fields.spacing.0, "25", "Between columns", field(|f, v| f.spacing.0 = v)) }</label>
+                                    { input(&fields.spacing.1, "25", "Between rows", field(|f, v| f.spacing.1 = v)) }
+                                </>
+                            },
+                            Kind::Line => html! {
+                                <>
+                                    { path_picker }
+                                    <label>{"Markers "}{ input(&fields.count, "1", "How many, including both ends", field(|f, v| f.count = v)) }</label>
+                                </>
+                            },
+                            Kind::Clock => html! {
+                                <>
+                                    { centre_picker }
+                                    <label>{"Radius "}{ input(&fields.radius, "100", "In world units (100 per metre)", field(|f, v| f.radius = v)) }</label>
+                                </>
+                            },
+                        }
+                    }
+                    <select onchange={on_labels} title="Icons">
+                        <option value="numbers" selected={labels_value == "numbers"}>{"Numbers 1–12"}</option>
+                        <option value="letters" selected={labels_value == "letters"}>{"Letters a–z"}</option>
+                        { for ALL_ELMS_ICONS.iter().filter(|i| !matches!(i, ElmsIcon::Num(_) | ElmsIcon::Letter(_))).map(|icon| {
+                            let value = String::from(*icon);
+                            html! {
+                                <option selected={labels_value == value} value={value.clone()}>{ value.trim_end_matches(".png").to_string() }</option>
+                            }
+                        }) }
+                    </select>
+                    <button onclick={on_generate} disabled={generator.is_none()}>{"Add"}</button>
+                    <button onclick={on_close}>{"Close"}</button>
+                </div>
+            }
+        </div>
+    }
+}
@@ -0,0 +1,50 @@
+//! Saving everything open, with its names, notes and groups, as a project
+//! file, and opening one again.
+
+use wasm_bindgen_futures::spawn_local;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::storage;
+
+#[derive(Properties, PartialEq)]
+pub struct ProjectPanelProps {
+    /// Whether there is anything to save
+    pub can_save: bool,
+    pub on_save: Callback<()>,
+    /// Called with the (file name, contents) of a project file to open
+    pub on_open: Callback<(String, String)>,
+}
+
+#[function_component(ProjectPanel)]
+pub fn project_panel(props: &ProjectPanelProps) -> Html {
+    let on_file = {
+        let on_open = props.on_open.clone();
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            let on_open = on_open.clone();
+            spawn_local(async move {
+                if let Some(file) = storage::read_file(&input).await {
+                    on_open.emit(file);
+                }
+                input.set_value("");
+            });
+        })
+    };
+
+    html! {
+        <details style="width:80%;margin:0.5em auto;text-align:left;">
+            <summary style="cursor:pointer;">{"Project file"}</summary>
+            <p style="margin:0.5em 0;font-size:0.9em;color:#ccc;">
+                {"Keeps every zone's markers and lines along with their names, notes, groups and tags, which game strings leave out."}
+            </p>
+            <div style="display:flex;gap:0.5em;align-items:center;">
+                <button onclick={props.on_save.reform(|_| ())} disabled={!props.can_save}>{"Save project"}</button>
+                <label>
+                    {"Open "}
+                    <input type="file" accept=".json,application/json" onchange={on_file} />
+                </label>
+            </div>
+        </details>
+    }
+}
@@ -0,0 +1,69 @@
+//! The ruler's controls, the measurements pinned on the map and the distances
+//! between selected markers.
+
+use marker_core::measure::Measurement;
+use yew::prelude::*;
+use yew_icons::{Icon, IconId};
+
+#[derive(Properties, PartialEq)]
+pub struct RulerPanelProps {
+    /// Whether clicks on the map measure
+    pub active: bool,
+    pub on_active: Callback<bool>,
+    /// Measurements pinned on the map being shown
+    pub measurements: Vec<Measurement>,
+    /// Called with the index of a pinned measurement to remove
+    pub on_remove: Callback<usize>,
+    pub on_clear: Callback<()>,
+    /// Between each pair of selected markers
+    pub selection: Vec<Measurement>,
+}
+
+#[function_component(RulerPanel)]
+pub fn ruler_panel(props: &RulerPanelProps) -> Html {
+    let active = props.active;
+    let point = |m: &Measurement| format!("{}, {}", m.from.x.round() as i32, m.from.z.round() as i32);
+
+    html! {
+        <div style="display:flex;flex-direction:column;gap:0.25em;align-items:center;margin-bottom:0.5em;">
+            <div style="display:flex;gap:0.5em;justify-content:center;align-items:center;">
+                <button onclick={props.on_active.reform(move |_| !active)} title="Click two points on the map. Right click or Escape cancels">
+                    { if active { "Stop measuring" } else { "Ruler" } }
+                </button>
+                <button onclick={props.on_clear.reform(|_| ())} disabled={props.measurements.is_empty()}>
+                    { format!("Clear measurements ({})", props.measurements.len()) }
+                </button>
+            </div>
+            if !props.measurements.is_empty() {
+                <ul style="padding:0;margin:0;list-style:none;font-size:0.9em;">
+                { for props.measurements.iter().enumerate().map(|(i, m)| {
+                    let on_remove = props.on_remove.clone();
+                    html! {
+                        <li style="display:flex;gap:0.5em;align-items:center;justify-content:center;">
+                            <span title="From X, Z">{ format!("From {}: {}", point(m), m.label()) }</span>
+                            <Icon
+                                style="cursor:pointer;"
+                                title={"Remove"}
+                                width={"0.8em"}
+                                height={"0.8em"}
+                                icon_id={IconId::BootstrapXLg}
+                                onclick={Callback::from(move |_| on_remove.emit(i))}
+                            />
+                        </li>
+                    }
+                }) }
+                </ul>
+            }
+            if !props.selection.is_empty() {
+                <details style="font-size:0.9em;">
+                    <summary>{"Between selected markers"}</summary>
+                    <ul style="padding:0;margin:0;list-style:none;">
+                    { for props.selection.iter().map(|m| html! {
+                        <li>{ format!("{} → {}, {}: {}", point(m), m.to.x.round() as i32, m.to.z.round() as i32, m.label()) }</li>
+                    }) }
+                    </ul>
+                </details>
+            }
+        </div>
+    }
+}
@@ -0,0 +1,84 @@
+//! Opening the SavedVariables files Elms Markers and M0R Markers keep, and
+//! downloading them again with the markers as edited, for every zone at once.
+
+use marker_core::savedvars::{Addon, ALL_ADDONS};
+use wasm_bindgen_futures::spawn_local;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::storage;
+
+#[derive(Properties, PartialEq)]
+pub struct SavedVariablesPanelProps {
+    /// The name of the file last opened for each addon, if any
+    pub opened: Vec<(Addon, String)>,
+    /// Called with the (file name, contents) of a SavedVariables file to open
+    pub on_open: Callback<(String, String)>,
+    /// Called with the addon to download a file for, and the account to
+    /// write into a new one
+    pub on_download: Callback<(Addon, String)>,
+}
+
+#[function_component(SavedVariablesPanel)]
+pub fn saved_variables_panel(props: &SavedVariablesPanelProps) -> Html {
+    let account = use_state(String::new);
+
+    let on_file = {
+        let on_open = props.on_open.clone();
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            let on_open = on_open.clone();
+            spawn_local(async move {
+                if let Some(file) = storage::read_file(&input).await {
+                    on_open.emit(file);
+                }
+                input.set_value("");
+            });
+        })
+    };
+    let on_account = {
+        let account = account.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            account.set(input.value());
+        })
+    };
+    let needs_account = ALL_ADDONS.iter().any(|addon| !props.opened.iter().any(|(a, _)| a == addon));
+
+    html! {
+        <details style="width:80%;margin:0.5em auto;text-align:left;">
+            <summary style="cursor:pointer;">{"Addon files"}</summary>
+            <p style="margin:0.5em 0;font-size:0.9em;color:#ccc;">
+                {"Open ElmsMarkers.lua or M0RMarkers.lua from your SavedVariables folder to edit every zone's markers at once. Only the account-wide markers are edited; any saved for a single character are left as they are. Log out of the game before putting the downloaded file back, or it will be overwritten."}
+            </p>
+            <div style="display:flex;gap:0.5em;align-items:center;flex-wrap:wrap;">
+                <label>
+                    {"Open "}
+                    <input type="file" accept=".lua" onchange={on_file} />
+                </label>
+                if needs_account {
+                    <input
+                        type="text"
+                        placeholder="@Account, for new files"
+                        value={(*account).clone()}
+                        oninput={on_account}
+                    />
+                }
+                { for ALL_ADDONS.iter().map(|&addon| {
+                    let opened = props.opened.iter().find(|(a, _)| *a == addon).map(|(_, name)| name.clone());
+                    let (on_download, account) = (props.on_download.clone(), (*account).clone());
+                    let title = match &opened {
+                        Some(name) => format!("{} with the markers as edited", name),
+                        None => format!("A new {} for {}", addon.file_name(), if account.is_empty() { "your account" } else { account.as_str() }),
+                    };
+                    let disabled = opened.is_none() && account.trim().is_empty();
+                    html! {
+                        <button {title} {disabled} onclick={Callback::from(move |_: MouseEvent| on_download.emit((addon, account.trim().to_string())))}>
+                            { format!("Download {}", addon.file_name()) }
+                        </button>
+                    }
+                }) }
+            </div>
+        </details>
+    }
+}
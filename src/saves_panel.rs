@@ -0,0 +1,136 @@
+use marker_core::project::Project;
+use marker_core::saves::{SaveBundle, SaveError, SavedLayout};
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::{format_time, get_timestamp, storage};
+
+#[derive(Properties, PartialEq)]
+pub struct SavesPanelProps {
+    /// The combined string currently in the editor
+    pub current: String,
+    /// The same, with names, notes and layers
+    pub project: Project,
+    /// Called with the layout to load
+    pub on_load: Callback<SavedLayout>,
+}
+
+#[function_component(SavesPanel)]
+pub fn saves_panel(props: &SavesPanelProps) -> Html {
+    let bundle = use_state(storage::load_saves);
+    let name = use_state(String::new);
+    let error = use_state(|| None::<String>);
+
+    // Runs an edit against the stored layouts, keeping storage in step.
+    let edit = {
+        let bundle = bundle.clone();
+        let error = error.clone();
+        move |f: &dyn Fn(&mut SaveBundle) -> Result<(), SaveError>| {
+            let mut new_bundle = (*bundle).clone();
+            match f(&mut new_bundle) {
+                Ok(()) => {
+                    storage::write_saves(&new_bundle);
+                    bundle.set(new_bundle);
+                    error.set(None);
+                }
+                Err(e) => error.set(Some(e.to_string())),
+            }
+        }
+    };
+
+    let on_save = {
+        let edit = edit.clone();
+        let name = name.clone();
+        let (current, project) = (props.current.clone(), props.project.clone());
+        Callback::from(move |_: MouseEvent| {
+            let now = get_timestamp();
+            edit(&|b| b.save(&name, &current, Some(&project), now));
+        })
+    };
+
+    let on_export = {
+        let bundle = bundle.clone();
+        Callback::from(move |_: MouseEvent| {
+            storage::download("elmseditor-layouts.json", "application/json", &bundle.to_json());
+        })
+    };
+
+    let window = web_sys::window();
+
+    html! {
+        <details style="width:80%;margin:0.5em auto;text-align:left;">
+            <summary style="cursor:pointer;">{ format!("Saved layouts ({})", bundle.saves.len()) }</summary>
+            <div style="display:flex;gap:0.5em;margin:0.5em 0;">
+                <input
+                    type="text"
+                    placeholder="Layout name, e.g. Rockgrove HM prog"
+                    value={(*name).clone()}
+                    oninput={{
+                        let name = name.clone();
+                        Callback::from(move |e: InputEvent| {
+                            let inp: HtmlInputElement = e.target_unchecked_into();
+                            name.set(inp.value());
+                        })
+                    }}
+                    style="flex-grow:1;"
+                />
+                <button onclick={on_save} disabled={props.current.trim().is_empty()}>{"Save current"}</button>
+                <button onclick={on_export} disabled={bundle.saves.is_empty()}>{"Export all"}</button>
+            </div>
+            if let Some(e) = &*error {
+                <div style="color:#ff6b6b;">{ e.clone() }</div>
+            }
+            <ul style="list-style:none;padding:0;margin:0;max-height:12em;overflow-y:auto;">
+                { for bundle.saves.iter().map(|layout| {
+                    let load = {
+                        let on_load = props.on_load.clone();
+                        let layout = layout.clone();
+                        Callback::from(move |_: MouseEvent| on_load.emit(layout.clone()))
+                    };
+                    let rename = {
+                        let edit = edit.clone();
+                        let window = window.clone();
+                        let old = layout.name.clone();
+                        Callback::from(move |_: MouseEvent| {
+                            let new_name = window.as_ref()
+                                .and_then(|w| w.prompt_with_message_and_default("Rename layout", &old).ok().flatten());
+                            if let Some(new_name) = new_name {
+                                edit(&|b| b.rename(&old, &new_name));
+                            }
+                        })
+                    };
+                    let duplicate = {
+                        let edit = edit.clone();
+                        let old = layout.name.clone();
+                        Callback::from(move |_: MouseEvent| {
+                            let now = get_timestamp();
+                            edit(&|b| b.duplicate(&old, now).map(|_| ()));
+                        })
+                    };
+                    let delete = {
+                        let edit = edit.clone();
+                        let window = window.clone();
+                        let old = layout.name.clone();
+                        Callback::from(move |_: MouseEvent| {
+                            let confirmed = window.as_ref()
+                                .and_then(|w| w.confirm_with_message(&format!("Delete '{}'?", old)).ok())
+                                .unwrap_or(false);
+                            if confirmed {
+                                edit(&|b| b.delete(&old));
+                            }
+                        })
+                    };
+                    html! {
+                        <li key={layout.name.clone()} style="display:flex;gap:0.5em;align-items:center;padding:2px 0;">
+                            <span style="flex-grow:1;" title={format!("Saved {}", format_time(layout.saved_at))}>{ &layout.name }</span>
+                            <button onclick={load}>{"Load"}</button>
+                            <button onclick={rename}>{"Rename"}</button>
+                            <button onclick={duplicate}>{"Duplicate"}</button>
+                            <button onclick={delete}>{"Delete"}</button>
+                        </li>
+                    }
+                }) }
+            </ul>
+        </details>
+    }
+}
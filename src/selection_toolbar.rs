@@ -0,0 +1,117 @@
+//! Bulk edits for whatever is selected on the map or in the marker list.
+
+use marker_core::marker::{hex_to_rgba, MarkerTypes, ALL_ELMS_ICONS, ALL_M0R_ICONS};
+use marker_core::selection::BulkEdit;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct SelectionToolbarProps {
+    pub marker_count: usize,
+    pub line_count: usize,
+    /// Whether any selected marker is an Elms marker, for the icon picker
+    pub has_elms: bool,
+    /// Whether any selected marker is a m0r marker, for texture and size
+    pub has_m0r: bool,
+    pub on_edit: Callback<BulkEdit>,
+    pub on_convert: Callback<MarkerTypes>,
+    pub on_clear: Callback<()>,
+}
+
+#[function_component(SelectionToolbar)]
+pub fn selection_toolbar(props: &SelectionToolbarProps) -> Html {
+    let size = use_state(|| "1".to_string());
+
+    let summary = match (props.marker_count, props.line_count) {
+        (m, 0) => format!("{} selected", m),
+        (0, l) => format!("{} {} selected", l, if l == 1 { "line" } else { "lines" }),
+        (m, l) => format!("{} markers, {} {} selected", m, l, if l == 1 { "line" } else { "lines" }),
+    };
+
+    let on_colour = props.on_edit.reform(|e: Event| {
+        let input: HtmlInputElement = e.target_unchecked_into();
+        let hex = u32::from_str_radix(input.value().trim_start_matches('#'), 16).unwrap_or(0xFFFFFF);
+        let (r, g, b, _) = hex_to_rgba(hex);
+        BulkEdit::Colour((r, g, b))
+    });
+    let on_size_input = {
+        let size = size.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            size.set(input.value());
+        })
+    };
+    let on_size = {
+        let size = size.clone();
+        props.on_edit.reform(move |_: MouseEvent| BulkEdit::Size(size.parse::<f32>().unwrap_or(1.0).clamp(0.0, 10.0)))
+    };
+
+    let grid = "display:grid;grid-template-columns:repeat(auto-fill,minmax(2.5em,1fr));gap:4px;max-height:12em;overflow-y:auto;margin-top:0.25em;";
+
+    html! {
+        <div style="display:flex;flex-direction:column;gap:0.5em;align-items:center;margin-bottom:0.5em;">
+            <div style="display:flex;gap:0.5em;justify-content:center;align-items:center;flex-wrap:wrap;">
+                <span>{ summary }</span>
+                <button onclick={props.on_edit.reform(|_| BulkEdit::SetActive(true))}>{"Enable"}</button>
+                <button onclick={props.on_edit.reform(|_| BulkEdit::SetActive(false))}>{"Disable"}</button>
+                <button onclick={props.on_edit.reform(|_| BulkEdit::Delete)} title="Delete">{"Delete"}</button>
+                <button onclick={props.on_clear.reform(|_| ())} title="Escape">{"Clear selection"}</button>
+            </div>
+            <div style="display:flex;gap:0.5em;justify-content:center;align-items:center;flex-wrap:wrap;">
+                if props.marker_count > 0 {
+                    <button onclick={props.on_convert.reform(|_| MarkerTypes::M0r)}>{"Convert to m0r"}</button>
+                    <button onclick={props.on_convert.reform(|_| MarkerTypes::Elms)}>{"Convert to Elms"}</button>
+                }
+                if props.has_m0r || props.line_count > 0 {
+                    <label title="Colour of m0r markers and lines">
+                        {"Colour "}
+                        <input type="color" onchange={on_colour} />
+                    </label>
+                }
+                if props.has_m0r {
+                    <label title="Size of m0r markers (0.0 to 10.0)">
+                        {"Size "}
+                        <input type="number" min="0" max="10" step="0.1" style="width:4em;" value={(*size).clone()} oninput={on_size_input} />
+                    </label>
+                    <button onclick={on_size}>{"Set size"}</button>
+                }
+            </div>
+            if props.has_elms {
+                <details style="width:80%;text-align:left;">
+                    <summary style="cursor:pointer;">{"Change Elms icon"}</summary>
+                    <div style={grid}>
+                        { for ALL_ELMS_ICONS.iter().map(|icon| {
+                            let on_edit = props.on_edit.clone();
+                            let icon = *icon;
+                            html! {
+                                <img
+                                    src={format!("static/icons/{}", String::from(icon))}
+                                    style="width:2em;height:2em;cursor:pointer;"
+                                    onclick={Callback::from(move |_| on_edit.emit(BulkEdit::ElmsIcon(icon)))}
+                                />
+                            }
+                        }) }
+                    </div>
+                </details>
+            }
+            if props.has_m0r {
+                <details style="width:80%;text-align:left;">
+                    <summary style="cursor:pointer;">{"Change m0r texture"}</summary>
+                    <div style={grid}>
+                        { for ALL_M0R_ICONS.iter().map(|texture| {
+                            let on_edit = props.on_edit.clone();
+                            let texture = texture.clone();
+                            html! {
+                                <img
+                                    src={format!("static/icons/m0r/{}", String::from(texture.clone()))}
+                                    style="width:2em;height:2em;cursor:pointer;"
+                                    onclick={Callback::from(move |_| on_edit.emit(BulkEdit::M0rTexture(texture.clone())))}
+                                />
+                            }
+                        }) }
+                    </div>
+                </details>
+            }
+        </div>
+    }
+}
@@ -0,0 +1,76 @@
+//! The strip along the bottom of the map: the world position under the mouse,
+//! the map and zoom, and a box to jump to a position.
+
+use marker_core::marker::parse_location;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct StatusBarProps {
+    /// World X/Z under the mouse, if it is over the map
+    pub cursor: Option<(f64, f64)>,
+    pub map_id: u16,
+    pub map_name: String,
+    pub zoom: f64,
+    /// Called with the X, Y (if given) and Z to centre the map on
+    pub on_go_to: Callback<(i32, Option<i32>, i32)>,
+}
+
+#[function_component(StatusBar)]
+pub fn status_bar(props: &StatusBarProps) -> Html {
+    let text = use_state(String::new);
+    let invalid = !text.trim().is_empty() && parse_location(&text).is_none();
+
+    let oninput = {
+        let text = text.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            text.set(input.value());
+        })
+    };
+    let go = {
+        let text = text.clone();
+        let on_go_to = props.on_go_to.clone();
+        move || {
+            if let Some(location) = parse_location(&text) {
+                on_go_to.emit(location);
+            }
+        }
+    };
+    let onkeydown = {
+        let go = go.clone();
+        Callback::from(move |e: KeyboardEvent| {
+            if e.key() == "Enter" {
+                go();
+            }
+        })
+    };
+
+    html! {
+        // Clicks and scrolling here shouldn't pan the map or place markers.
+        <div
+            onmousedown={|e: MouseEvent| e.stop_propagation()}
+            oncontextmenu={|e: MouseEvent| e.stop_propagation()}
+            onwheel={|e: WheelEvent| e.stop_propagation()}
+            style="position:absolute;left:0;right:0;bottom:0;display:flex;gap:1em;align-items:center;justify-content:space-between;padding:2px 0.5em;background:rgba(0,0,0,0.6);font-size:0.85em;cursor:default;"
+        >
+            <span title="World X, Z under the mouse" style="min-width:9em;text-align:left;">
+                { props.cursor.map_or("–".to_string(), |(x, z)| format!("{}, {}", x.round() as i32, z.round() as i32)) }
+            </span>
+            <span>{ format!("Map {} ({})", props.map_id, props.map_name) }</span>
+            <span>{ format!("Zoom {:.1}×", props.zoom) }</span>
+            <span style="display:flex;gap:0.25em;align-items:center;">
+                <input
+                    type="text"
+                    placeholder="X, Z or X, Y, Z"
+                    title="Centre the map on a position, such as one from /loc"
+                    style={format!("width:10em;{}", if invalid { "outline:1px solid #ff6b6b;" } else { "" })}
+                    value={(*text).clone()}
+                    {oninput}
+                    {onkeydown}
+                />
+                <button onclick={move |_| go()} disabled={invalid || text.trim().is_empty()}>{"Go"}</button>
+            </span>
+        </div>
+    }
+}
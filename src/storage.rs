@@ -0,0 +1,88 @@
+//! Browser storage for the autosave, named layouts and zone overrides.
+
+use marker_core::project::Project;
+use marker_core::saves::{Autosave, SaveBundle};
+use wasm_bindgen::JsCast;
+use web_sys::Storage;
+
+const AUTOSAVE_KEY: &str = "elmseditor.autosave";
+const SAVES_KEY: &str = "elmseditor.saves";
+const ZONES_KEY: &str = "elmseditor.zones";
+
+fn local_storage() -> Option<Storage> {
+    web_sys::window()?.local_storage().ok().flatten()
+}
+
+pub fn load_autosave() -> Option<Autosave> {
+    let json = local_storage()?.get_item(AUTOSAVE_KEY).ok().flatten()?;
+    Autosave::from_json(&json).filter(|a| !a.data.trim().is_empty())
+}
+
+pub fn write_autosave(data: &str, project: Option<&Project>, now: u64) {
+    if let Some(storage) = local_storage() {
+        if data.trim().is_empty() {
+            let _ = storage.remove_item(AUTOSAVE_KEY);
+        } else {
+            let autosave = Autosave { data: data.to_string(), project: project.cloned(), saved_at: now };
+            let _ = storage.set_item(AUTOSAVE_KEY, &autosave.to_json());
+        }
+    }
+}
+
+/// Named layouts. A corrupt entry is left in place, so it isn't overwritten
+/// until the user saves something new.
+pub fn load_saves() -> SaveBundle {
+    local_storage()
+        .and_then(|s| s.get_item(SAVES_KEY).ok().flatten())
+        .and_then(|json| SaveBundle::from_json(&json).ok())
+        .unwrap_or_default()
+}
+
+pub fn write_saves(bundle: &SaveBundle) {
+    if let Some(storage) = local_storage() {
+        let _ = storage.set_item(SAVES_KEY, &bundle.to_json());
+    }
+}
+
+/// The zone catalogue override file the user loaded, as (file name, contents).
+pub fn load_zone_override() -> Option<(String, String)> {
+    let json = local_storage()?.get_item(ZONES_KEY).ok().flatten()?;
+    serde_json::from_str(&json).ok()
+}
+
+pub fn write_zone_override(file: Option<(&str, &str)>) {
+    if let Some(storage) = local_storage() {
+        match file {
+            Some(file) => { let _ = storage.set_item(ZONES_KEY, &serde_json::to_string(&file).unwrap_or_default()); }
+            None => { let _ = storage.remove_item(ZONES_KEY); }
+        }
+    }
+}
+
+/// Offers `contents` to the user as a file download.
+pub fn download(filename: &str, mime: &str, contents: &str) {
+    let parts = js_sys::Array::of1(&contents.into());
+    let options = web_sys::BlobPropertyBag::new();
+    options.set_type(mime);
+    let Ok(blob) = web_sys::Blob::new_with_str_sequence_and_options(&parts, &options) else { return };
+    let Ok(url) = web_sys::Url::create_object_url_with_blob(&blob) else { return };
+    download_url(filename, &url);
+    let _ = web_sys::Url::revoke_object_url(&url);
+}
+
+/// Offers what's at `url`, such as a `data:` URL, as a file download.
+pub fn download_url(filename: &str, url: &str) {
+    let Some(document) = web_sys::window().and_then(|w| w.document()) else { return };
+    if let Ok(anchor) = document.create_element("a").map(|a| a.unchecked_into::<web_sys::HtmlAnchorElement>()) {
+        anchor.set_href(url);
+        anchor.set_download(filename);
+        anchor.click();
+    }
+}
+
+/// The name and contents of the first file picked in a file input.
+pub async fn read_file(input: &web_sys::HtmlInputElement) -> Option<(String, String)> {
+    let file = input.files()?.get(0)?;
+    let text = wasm_bindgen_futures::JsFuture::from(file.text()).await.ok()?.as_string()?;
+    Some((file.name(), text))
+}
@@ -0,0 +1,196 @@
+//! Moving, rotating, mirroring and scaling the selection, previewed on the map
+//! until it is applied.
+
+use marker_core::transform::Transform;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Tool {
+    Move,
+    Rotate,
+    Mirror,
+    Scale,
+}
+
+#[derive(Properties, PartialEq)]
+pub struct TransformPanelProps {
+    /// Centre of the selection in world X/Z
+    pub centroid: (f64, f64),
+    /// Called with the transform to draw on the map, or `None` for no preview
+    pub on_preview: Callback<Option<Transform>>,
+    pub on_apply: Callback<Transform>,
+}
+
+/// The transform the panel's fields describe, if they make sense.
+fn build(tool: Tool, fields: &Fields, centroid: (f64, f64)) -> Option<Transform> {
+    let number = |s: &str| s.trim().parse::<f64>().ok().filter(|v| v.is_finite());
+    let pivot = if fields.use_point {
+        (number(&fields.point.0)?, number(&fields.point.1)?)
+    } else {
+        centroid
+    };
+    Some(match tool {
+        Tool::Move => Transform::Translate { dx: number(&fields.dx)?, dz: number(&fields.dz)? },
+        Tool::Rotate => Transform::Rotate { pivot, degrees: number(&fields.degrees)? },
+        Tool::Mirror => Transform::Mirror { point: pivot, degrees: number(&fields.axis)? },
+        Tool::Scale => Transform::Scale { centre: centroid, factor: number(&fields.factor)?.max(0.0) },
+    })
+}
+
+#[derive(Clone, PartialEq)]
+struct Fields {
+    dx: String,
+    dz: String,
+    degrees: String,
+    /// Mirror axis, in degrees clockwise from east
+    axis: String,
+    factor: String,
+    /// Rotate or mirror about `point` rather than the centre of the selection
+    use_point: bool,
+    point: (String, String),
+}
+
+impl Default for Fields {
+    fn default() -> Self {
+        Self {
+            dx: "0".into(),
+            dz: "0".into(),
+            degrees: "90".into(),
+            axis: "90".into(),
+            factor: "1".into(),
+            use_point: false,
+            point: (String::new(), String::new()),
+        }
+    }
+}
+
+#[function_component(TransformPanel)]
+pub fn transform_panel(props: &TransformPanelProps) -> Html {
+    let tool = use_state(|| None::<Tool>);
+    let fields = use_state(Fields::default);
+    let transform = tool.and_then(|t| build(t, &fields, props.centroid));
+
+    {
+        let on_preview = props.on_preview.clone();
+        use_effect_with(transform, move |transform| {
+            on_preview.emit(*transform);
+            let on_preview = on_preview.clone();
+            move || on_preview.emit(None)
+        });
+    }
+
+    let choose = |t: Tool| {
+        let tool = tool.clone();
+        Callback::from(move |_: MouseEvent| tool.set(Some(t)))
+    };
+    let field = |set: fn(&mut Fields, String)| {
+        let fields = fields.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            let mut new_fields = (*fields).clone();
+            set(&mut new_fields, input.value());
+            fields.set(new_fields);
+        })
+    };
+    let on_use_point = {
+        let fields = fields.clone();
+        let centroid = props.centroid;
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            let mut new_fields = (*fields).clone();
+            new_fields.use_point = input.checked();
+            if new_fields.point.0.is_empty() {
+                new_fields.point = ((centroid.0.round() as i32).to_string(), (centroid.1.round() as i32).to_string());
+            }
+            fields.set(new_fields);
+        })
+    };
+    let on_apply = {
+        let tool = tool.clone();
+        let on_apply = props.on_apply.clone();
+        Callback::from(move |_: MouseEvent| {
+            if let Some(transform) = transform {
+                on_apply.emit(transform);
+            }
+            tool.set(None);
+        })
+    };
+    let on_cancel = {
+        let tool = tool.clone();
+        Callback::from(move |_: MouseEvent| tool.set(None))
+    };
+
+    let input = |value: &str, step: &str, title: &str, oninput: Callback<InputEvent>| html! {
+        <input type="number" style="width:5em;" step={step.to_string()} title={title.to_string()} value={value.to_string()} {oninput} />
+    };
+    let tool_button = |t: Tool, label: &str| html! {
+        <button onclick={choose(t)} disabled={*tool == Some(t)}>{ label }</button>
+    };
+    let pivot = html! {
+        <>
+            <label title="Turn about a point rather than the centre of the selection">
+                <input type="checkbox" checked={fields.use_point} onchange={on_use_point} />
+                {"About X, Z"}
+            </label>
+            if fields.use_point {
+                { input(&fields.point.0, "25", "Pivot X", field(|f, v| f.point.0 = v)) }
+                { input(&fields.point.1, "25", "Pivot Z", field(|f, v| f.point.1 = v)) }
+            }
+        </>
+    };
+
+    html! {
+        <div style="display:flex;flex-direction:column;gap:0.5em;align-items:center;margin-bottom:0.5em;">
+            <div style="display:flex;gap:0.5em;justify-content:center;align-items:center;">
+                {"Transform: "}
+                { tool_button(Tool::Move, "Move") }
+                { tool_button(Tool::Rotate, "Rotate") }
+                { tool_button(Tool::Mirror, "Mirror") }
+                { tool_button(Tool::Scale, "Scale") }
+            </div>
+            if let Some(t) = *tool {
+                <div style="display:flex;gap:0.5em;justify-content:center;align-items:center;flex-wrap:wrap;">
+                    {
+                        match t {
+                            Tool::Move => html! {
+                                <>
+                                    <label>{"X "}{ input(&fields.dx, "25", "East", field(|f, v| f.dx = v)) }</label>
+                                    <label>{"Z "}{ input(&fields.dz, "25", "South", field(|f, v| f.dz = v)) }</label>
+                                </>
+                            },
+                            Tool::Rotate => html! {
+                                <>
+                                    <label>{"Degrees "}{ input(&fields.degrees, "15", "Clockwise on the map", field(|f, v| f.degrees = v)) }</label>
+                                    { pivot }
+                                </>
+                            },
+                            Tool::Mirror => html! {
+                                <>
+                                    <select onchange={{
+                                        let fields = fields.clone();
+                                        Callback::from(move |e: Event| {
+                                            let input: HtmlInputElement = e.target_unchecked_into();
+                                            fields.set(Fields { axis: input.value(), ..(*fields).clone() });
+                                        })
+                                    }}>
+                                        <option value="90" selected={fields.axis == "90"}>{"Swap east and west"}</option>
+                                        <option value="0" selected={fields.axis == "0"}>{"Swap north and south"}</option>
+                                        <option value="45" selected={fields.axis == "45"}>{"Across the NW-SE diagonal"}</option>
+                                        <option value="135" selected={fields.axis == "135"}>{"Across the NE-SW diagonal"}</option>
+                                    </select>
+                                    { pivot }
+                                </>
+                            },
+                            Tool::Scale => html! {
+                                <label>{"Factor "}{ input(&fields.factor, "0.1", "Distances from the centre of the selection are multiplied by this", field(|f, v| f.factor = v)) }</label>
+                            },
+                        }
+                    }
+                    <button onclick={on_apply} disabled={transform.is_none()}>{"Apply"}</button>
+                    <button onclick={on_cancel}>{"Cancel"}</button>
+                </div>
+            }
+        </div>
+    }
+}